@@ -1,4 +1,6 @@
+use serde::Serialize;
 use crate::trusted_scraper::TrustedScraper;
+use crate::verification::{VerificationReport, Verdict};
 
 #[derive(serde::Deserialize)]
 /// Структура для пользовательской правки
@@ -9,12 +11,20 @@ pub struct Correction {
 }
 
 /// Результат проверки
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum CorrectionResult {
     AutoAccepted,
     NeedsModeration,
     Rejected,
 }
 
+/// Решение по правке вместе с доказательствами, на которых оно основано
+#[derive(Debug, Clone, Serialize)]
+pub struct CorrectionCheck {
+    pub result: CorrectionResult,
+    pub report: VerificationReport,
+}
+
 /// Active Learning: автоматическая проверка и модерация
 pub struct ActiveLearning<'a> {
     pub scraper: &'a TrustedScraper,
@@ -24,13 +34,15 @@ impl<'a> ActiveLearning<'a> {
     pub fn new(scraper: &'a TrustedScraper) -> Self {
         Self { scraper }
     }
-    /// Проверить правку: если найдено 2+ подтверждения — принять, иначе на модерацию
-    pub async fn check_correction(&self, corr: &Correction) -> CorrectionResult {
-        if self.scraper.check_multi(&corr.claim).await >= 2 {
-            CorrectionResult::AutoAccepted
-        } else {
-            CorrectionResult::NeedsModeration
-        }
+    /// Проверить правку: подтверждена источниками — принять, опровергнута — отклонить, иначе на модерацию
+    pub async fn check_correction(&self, corr: &Correction) -> CorrectionCheck {
+        let report = self.scraper.check_multi(&corr.claim).await;
+        let result = match report.verdict {
+            Verdict::Confirmed => CorrectionResult::AutoAccepted,
+            Verdict::Refuted => CorrectionResult::Rejected,
+            Verdict::Unverified => CorrectionResult::NeedsModeration,
+        };
+        CorrectionCheck { result, report }
     }
     /// Модерация (заглушка)
     pub async fn moderate(&self, _corr: &Correction) -> CorrectionResult {
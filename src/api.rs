@@ -1,12 +1,25 @@
 use actix_web::{web, App, HttpServer, Responder, HttpResponse};
 use crate::trusted_scraper::TrustedScraper;
-use crate::active_learning::{ActiveLearning, Correction};
+use crate::active_learning::{ActiveLearning, Correction, CorrectionResult};
 use crate::knowledge_graph::KnowledgeGraph;
 use crate::text_processor::TextProcessor;
-use crate::embedding_cache::EmbeddingCache;
+use crate::patterns::{DomainPatterns, PatternSet};
+use crate::embedding_cache::{CacheConfig, EmbeddingCache};
+use crate::embedder::embedder_from_spec;
+use crate::segment::Edge;
+use crate::graph_query::Query;
+use crate::qa;
+use crate::pipeline::StageSelection;
+use crate::ner::{NerModel, NerStage};
+use crate::fact_source::SourceRegistry;
+use crate::wiki_index::{LocalWikiSource, WikiIndex};
+use crate::verification::Verdict;
+use crate::resilience::HttpPolicy;
 use serde::{Serialize, Deserialize};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use petgraph::visit::EdgeRef;
+use chrono::{NaiveDate, Utc};
 
 /// Структура для запроса обработки текста
 #[derive(Deserialize)]
@@ -14,6 +27,10 @@ pub struct ProcessTextRequest {
     pub text: String,
     pub learn: Option<bool>,
     pub annotation: Option<String>,
+    pub document_date: Option<NaiveDate>, // Дата документа для "вчера", "в прошлом году" (по умолчанию сегодня)
+    pub stages: Option<Vec<String>>,      // Выполнить только эти этапы конвейера
+    #[serde(default)]
+    pub skip_stages: Vec<String>,         // Пропустить эти этапы конвейера
 }
 
 /// Структура для запроса обучения
@@ -31,14 +48,52 @@ pub struct VerifyRequest {
     pub source: Option<String>,
 }
 
+/// Структура для запроса к графу знаний
+#[derive(Deserialize)]
+pub struct QueryRequest {
+    pub query: String,
+    pub limit: Option<usize>,
+}
+
+/// Структура для вопроса на естественном языке
+#[derive(Deserialize)]
+pub struct AskRequest {
+    pub question: String,
+}
+
 /// Расширенная структура для экспорта графа
 #[derive(Serialize)]
 struct GraphExport {
     nodes: Vec<String>,
-    edges: Vec<(usize, usize, String)>,
+    edges: Vec<EdgeExport>,
     stats: GraphStats,
 }
 
+/// Ребро в экспорте: индексы концов + тип, уверенность и происхождение
+#[derive(Serialize)]
+struct EdgeExport {
+    from: usize,
+    to: usize,
+    #[serde(flatten)]
+    edge: Edge,
+}
+
+/// Фильтры экспорта графа (?min_confidence=0.7&source=Wikipedia&kind=Location)
+#[derive(Deserialize)]
+pub struct GraphFilter {
+    pub min_confidence: Option<f32>,
+    pub source: Option<String>,
+    pub kind: Option<String>,
+}
+
+impl GraphFilter {
+    fn accepts(&self, edge: &Edge) -> bool {
+        self.min_confidence.is_none_or(|min| edge.confidence >= min)
+            && self.source.as_deref().is_none_or(|s| format!("{:?}", edge.source).eq_ignore_ascii_case(s))
+            && self.kind.as_deref().is_none_or(|k| format!("{:?}", edge.kind).eq_ignore_ascii_case(k))
+    }
+}
+
 /// Статистика графа
 #[derive(Serialize)]
 struct GraphStats {
@@ -48,44 +103,68 @@ struct GraphStats {
     action_types: Vec<String>,
 }
 
-/// Обработчик для обработки текста
+/// Обработчик для обработки текста.
+/// Блокировка процессора держится только на время синхронной обработки: проверка
+/// по источникам (ожидание, повторы, таймауты) идёт без неё
 async fn process_text(
     processor: web::Data<Mutex<TextProcessor>>,
     req: web::Json<ProcessTextRequest>,
 ) -> impl Responder {
-    let mut processor = processor.lock().unwrap();
-    
-    match processor.process_text(&req.text).await {
-        result => {
-            // Если запрошено обучение
-            if req.learn.unwrap_or(false) {
-                let annotation = req.annotation.as_deref().unwrap_or("Автоматическое обучение");
-                let learned = processor.learn_from_text(&req.text, annotation).await;
-                
-                HttpResponse::Ok().json(serde_json::json!({
-                    "success": true,
-                    "result": result,
-                    "learned": learned,
-                    "message": if learned { "Факт добавлен в граф знаний" } else { "Факт отправлен на модерацию" }
-                }))
-            } else {
-                HttpResponse::Ok().json(serde_json::json!({
-                    "success": true,
-                    "result": result
-                }))
-            }
+    let document_date = req.document_date.unwrap_or_else(|| Utc::now().date_naive());
+    let selection = StageSelection { only: req.stages.clone(), skip: req.skip_stages.clone() };
+    let (result, scraper) = {
+        let processor = processor.lock().unwrap();
+        if let Err(e) = processor.pipeline().validate(&selection) {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": e.to_string(),
+                "stages": processor.pipeline().stage_names()
+            }));
         }
+        (processor.process_document(&req.text, document_date, &selection), processor.scraper().clone())
+    };
+
+    // Если запрошено обучение
+    if req.learn.unwrap_or(false) {
+        // В граф попадает этот же результат: с датой документа и выбранными этапами
+        let report = scraper.check_multi(&req.text).await;
+        let learned = processor.lock().unwrap().learn_verified(&req.text, &result, &report);
+
+        HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "result": result,
+            "learned": learned,
+            "message": if learned { "Факт добавлен в граф знаний" } else { "Факт отправлен на модерацию" }
+        }))
+    } else {
+        HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "result": result
+        }))
     }
 }
 
+/// Обработчик для просмотра этапов конвейера обработки текста
+async fn list_pipeline(processor: web::Data<Mutex<TextProcessor>>) -> impl Responder {
+    let processor = processor.lock().unwrap();
+    HttpResponse::Ok().json(serde_json::json!({
+        "stages": processor.pipeline().stage_names()
+    }))
+}
+
 /// Обработчик для обучения на новых данных
 async fn learn_from_text(
     processor: web::Data<Mutex<TextProcessor>>,
     req: web::Json<LearnRequest>,
 ) -> impl Responder {
-    let mut processor = processor.lock().unwrap();
-    
-    match processor.learn_from_text(&req.text, &req.annotation).await {
+    let (result, scraper) = {
+        let processor = processor.lock().unwrap();
+        (processor.process_document(&req.text, Utc::now().date_naive(), &StageSelection::default()), processor.scraper().clone())
+    };
+    let report = scraper.check_multi(&req.text).await;
+    let learned = processor.lock().unwrap().learn_verified(&req.text, &result, &report);
+
+    match learned {
         true => HttpResponse::Ok().json(serde_json::json!({
             "success": true,
             "message": "Факт успешно добавлен в граф знаний",
@@ -106,33 +185,39 @@ async fn verify_fact(
     processor: web::Data<Mutex<TextProcessor>>,
     req: web::Json<VerifyRequest>,
 ) -> impl Responder {
-    let processor = processor.lock().unwrap();
-    
-    match processor.verify_fact(&req.fact).await {
-        true => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "verified": true,
-            "fact": req.fact,
-            "message": "Факт верифицирован через TrustedScraper"
-        })),
-        false => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "verified": false,
-            "fact": req.fact,
-            "message": "Факт не найден в доверенных источниках"
-        }))
-    }
+    let scraper = processor.lock().unwrap().scraper().clone();
+    let report = scraper.check_multi(&req.fact).await;
+    let message = match report.verdict {
+        Verdict::Confirmed => "Факт верифицирован через TrustedScraper",
+        Verdict::Refuted => "Факт опровергнут доверенными источниками",
+        Verdict::Unverified => "Факт не найден в доверенных источниках",
+    };
+    HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "verified": report.verified(),
+        "fact": req.fact,
+        "score": report.score,
+        "verdict": report.verdict,
+        "confirmations": report.confirmations,
+        "evidence": report.evidence,
+        "message": message
+    }))
 }
 
 /// Обработчик для проверки фактов (старый эндпоинт)
 async fn check_fact(scraper: web::Data<TrustedScraper>, info: web::Query<Correction>) -> impl Responder {
     let al = ActiveLearning::new(&scraper);
-    let res = al.check_correction(&info.into_inner()).await;
-    match res {
-        crate::active_learning::CorrectionResult::AutoAccepted => HttpResponse::Ok().body("Accepted automatically"),
-        crate::active_learning::CorrectionResult::NeedsModeration => HttpResponse::Ok().body("Needs moderation"),
-        crate::active_learning::CorrectionResult::Rejected => HttpResponse::Ok().body("Rejected"),
-    }
+    let check = al.check_correction(&info.into_inner()).await;
+    let message = match check.result {
+        CorrectionResult::AutoAccepted => "Accepted automatically",
+        CorrectionResult::NeedsModeration => "Needs moderation",
+        CorrectionResult::Rejected => "Rejected",
+    };
+    HttpResponse::Ok().json(serde_json::json!({
+        "result": check.result,
+        "message": message,
+        "verification": check.report
+    }))
 }
 
 /// Обработчик для модерации (заглушка)
@@ -141,7 +226,7 @@ async fn moderate_fact(_scraper: web::Data<TrustedScraper>, _info: web::Query<Co
 }
 
 /// Обработчик для экспорта графа знаний
-async fn export_graph(graph: web::Data<Mutex<KnowledgeGraph>>) -> impl Responder {
+async fn export_graph(graph: web::Data<Mutex<KnowledgeGraph>>, filter: web::Query<GraphFilter>) -> impl Responder {
     let graph = graph.lock().unwrap();
     let mut nodes = Vec::new();
     let mut edges = Vec::new();
@@ -152,23 +237,23 @@ async fn export_graph(graph: web::Data<Mutex<KnowledgeGraph>>) -> impl Responder
         let seg = &graph.graph[idx];
         nodes.push(format!("{:?}", seg));
         
-        // Анализ типов узлов
-        match seg {
-            crate::segment::Segment::Primitive(text) => {
-                if text.contains("кот") || text.contains("рыба") {
-                    entity_types.push("животное".to_string());
-                }
-            },
-            crate::segment::Segment::Composite(_) => {
-                action_types.push("действие".to_string());
-            },
-            _ => {}
+        // Типы сущностей хранятся в тегах узлов
+        for tag in graph.tags(idx) {
+            if !entity_types.contains(tag) {
+                entity_types.push(tag.clone());
+            }
         }
     }
     
     for edge in graph.graph.edge_references() {
+        if !filter.accepts(edge.weight()) {
+            continue;
+        }
         let (a, b) = (edge.source().index(), edge.target().index());
-        edges.push((a, b, edge.weight().clone()));
+        if !action_types.contains(&edge.weight().label) {
+            action_types.push(edge.weight().label.clone());
+        }
+        edges.push(EdgeExport { from: a, to: b, edge: edge.weight().clone() });
     }
     
     let stats = GraphStats {
@@ -182,6 +267,96 @@ async fn export_graph(graph: web::Data<Mutex<KnowledgeGraph>>) -> impl Responder
     HttpResponse::Ok().json(export)
 }
 
+/// Обработчик для декларативных запросов к графу знаний
+async fn query_graph(
+    graph: web::Data<Mutex<KnowledgeGraph>>,
+    req: web::Json<QueryRequest>,
+) -> impl Responder {
+    let mut query = match Query::parse(&req.query) {
+        Ok(query) => query,
+        Err(e) => return HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": e.to_string(),
+            "query": req.query
+        })),
+    };
+    if let Some(limit) = req.limit {
+        query.limit = Some(query.limit.map_or(limit, |l| l.min(limit)));
+    }
+
+    let graph = graph.lock().unwrap();
+    let result = match query.execute(&graph) {
+        Ok(result) => result,
+        Err(e) => return HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": e.to_string(),
+            "query": req.query
+        })),
+    };
+    HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "query": req.query,
+        "variables": result.variables,
+        "count": result.rows.len(),
+        "rows": result.rows
+    }))
+}
+
+/// Обработчик для вопросов на естественном языке
+async fn ask_question(
+    processor: web::Data<Mutex<TextProcessor>>,
+    graph: web::Data<Mutex<KnowledgeGraph>>,
+    req: web::Json<AskRequest>,
+) -> impl Responder {
+    // Порядок блокировок как в остальных обработчиках: обработчик, затем граф
+    let processor = processor.lock().unwrap();
+    let graph = graph.lock().unwrap();
+    let answer = qa::answer_question(&req.question, &processor, &graph);
+    HttpResponse::Ok().json(serde_json::json!({
+        "success": answer.understood,
+        "answer": answer
+    }))
+}
+
+/// Сводка активного набора шаблонов
+fn patterns_summary(patterns: &PatternSet) -> serde_json::Value {
+    serde_json::json!({
+        "source": patterns.source_path().map(|p| p.display().to_string()),
+        "entity_rules": patterns.entities.len(),
+        "action_rules": patterns.actions.len(),
+        "property_rules": patterns.properties.len(),
+        "question_rules": patterns.questions.len(),
+        "domains": patterns.domains
+    })
+}
+
+/// Обработчик для просмотра активного набора шаблонов
+async fn list_patterns(patterns: web::Data<Mutex<PatternSet>>) -> impl Responder {
+    let mut patterns = patterns.lock().unwrap();
+    if let Err(e) = patterns.reload_if_changed() {
+        println!("⚠️ Не удалось перезагрузить шаблоны: {}", e);
+    }
+    HttpResponse::Ok().json(patterns_summary(&patterns))
+}
+
+/// Обработчик для добавления или замены области шаблонов
+async fn update_patterns(
+    patterns: web::Data<Mutex<PatternSet>>,
+    domain: web::Json<DomainPatterns>,
+) -> impl Responder {
+    let mut patterns = patterns.lock().unwrap();
+    match patterns.upsert_domain(domain.into_inner()) {
+        Ok(()) => HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "patterns": patterns_summary(&patterns)
+        })),
+        Err(e) => HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": e.to_string()
+        })),
+    }
+}
+
 /// Обработчик для статистики кеша эмбеддингов
 async fn cache_stats() -> impl Responder {
     let stats = EmbeddingCache::get_cache_stats();
@@ -190,10 +365,11 @@ async fn cache_stats() -> impl Responder {
 
 /// Обработчик для очистки кеша
 async fn clear_cache() -> impl Responder {
-    EmbeddingCache::cleanup_old_embeddings(0); // Очистить все
+    let cleared = EmbeddingCache::clear_all();
     HttpResponse::Ok().json(serde_json::json!({
         "success": true,
-        "message": "Кеш эмбеддингов очищен"
+        "message": "Кеш эмбеддингов очищен",
+        "cleared_embeddings": cleared
     }))
 }
 
@@ -202,41 +378,33 @@ async fn clear_cache() -> impl Responder {
 struct SimilarityRequest {
     text: String,
     top_k: Option<usize>,
+    min_similarity: Option<f32>,
+    tag: Option<String>,
 }
 
+/// Поиск похожих узлов графа знаний через HNSW-индекс
 async fn find_similar(
+    graph: web::Data<Mutex<KnowledgeGraph>>,
     req: web::Json<SimilarityRequest>,
 ) -> impl Responder {
-    let query_embedding = EmbeddingCache::get_cached_embedding(&req.text);
     let top_k = req.top_k.unwrap_or(5);
-    
-    // Пример поиска похожих текстов (в реальности здесь будет база данных)
-    let sample_embeddings = vec![
-        EmbeddingCache::get_cached_embedding("кот"),
-        EmbeddingCache::get_cached_embedding("кошка"),
-        EmbeddingCache::get_cached_embedding("собака"),
-        EmbeddingCache::get_cached_embedding("рыба"),
-    ];
-    
-    let similar = EmbeddingCache::find_similar_embeddings(&query_embedding, &sample_embeddings, top_k);
+    let graph = graph.lock().unwrap();
+    let similar = graph.similar_nodes(&req.text, top_k, req.min_similarity, req.tag.as_deref());
     
     let results: Vec<serde_json::Value> = similar.iter().map(|(idx, similarity)| {
         serde_json::json!({
-            "index": idx,
+            "id": graph.node_id(*idx),
+            "index": idx.index(),
             "similarity": similarity,
-            "text": match *idx {
-                0 => "кот",
-                1 => "кошка", 
-                2 => "собака",
-                3 => "рыба",
-                _ => "неизвестно"
-            }
+            "text": graph.graph[*idx].text(),
+            "tags": graph.tags(*idx)
         })
     }).collect();
     
     HttpResponse::Ok().json(serde_json::json!({
         "query": req.text,
         "top_k": top_k,
+        "indexed": graph.vectors.len(),
         "results": results
     }))
 }
@@ -256,8 +424,13 @@ async fn health_check() -> impl Responder {
 }
 
 /// Обработчик для информации о системе
-async fn system_info() -> impl Responder {
+async fn system_info(scraper: web::Data<TrustedScraper>) -> impl Responder {
     let cache_stats = EmbeddingCache::get_cache_stats();
+    let fact_sources: Vec<_> = scraper
+        .sources()
+        .iter()
+        .map(|s| serde_json::json!({"name": s.name(), "source": s.source(), "weight": s.weight(), "circuit": scraper.sources().circuit(s.name())}))
+        .collect();
     
     HttpResponse::Ok().json(serde_json::json!({
         "system": "Metastasa - Мыслящее Ядро",
@@ -269,6 +442,7 @@ async fn system_info() -> impl Responder {
             "storage": "sled + petgraph"
         },
         "cache_stats": cache_stats,
+        "fact_sources": fact_sources,
         "features": [
             "GPU Attention",
             "Логические графы знаний", 
@@ -279,20 +453,104 @@ async fn system_info() -> impl Responder {
     }))
 }
 
+/// Ограничения кеша эмбеддингов из переменных окружения:
+/// METASTASA_CACHE_CAPACITY (записей в разделе) и METASTASA_CACHE_TTL (секунд, 0 — без ограничения)
+fn cache_config_from_env() -> CacheConfig {
+    let mut config = CacheConfig::default();
+    if let Some(capacity) = std::env::var("METASTASA_CACHE_CAPACITY").ok().and_then(|v| v.parse().ok()) {
+        config.capacity = capacity;
+    }
+    if let Some(ttl) = std::env::var("METASTASA_CACHE_TTL").ok().and_then(|v| v.parse::<u64>().ok()) {
+        config.ttl_seconds = (ttl > 0).then_some(ttl);
+    }
+    config
+}
+
+/// Источники проверки фактов из переменных окружения:
+/// METASTASA_WIKI_INDEX (локальный индекс, cargo run --bin build_wiki_index),
+/// METASTASA_OFFLINE=1 (без сетевых источников), METASTASA_MIN_CONFIRMATIONS,
+/// METASTASA_HTTP_TIMEOUT и METASTASA_HTTP_RETRIES (таймаут запроса в секундах и число повторов),
+/// METASTASA_SOURCE_TIMEOUT (сколько секунд ждать источник вместе с повторами)
+fn scraper_from_env() -> std::io::Result<TrustedScraper> {
+    let seconds = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<f64>().ok()).filter(|s| *s > 0.0).map(Duration::from_secs_f64);
+    let mut policy = HttpPolicy::default();
+    if let Some(timeout) = seconds("METASTASA_HTTP_TIMEOUT") {
+        policy.timeout = timeout;
+    }
+    if let Some(retries) = std::env::var("METASTASA_HTTP_RETRIES").ok().and_then(|v| v.parse().ok()) {
+        policy.max_retries = retries;
+    }
+    let offline = std::env::var("METASTASA_OFFLINE").is_ok_and(|v| v == "1" || v == "true");
+    let mut scraper = if offline { TrustedScraper::with_sources(SourceRegistry::new()) } else { TrustedScraper::with_policy(policy) };
+    if let Some(timeout) = seconds("METASTASA_SOURCE_TIMEOUT") {
+        scraper = scraper.with_timeout(timeout);
+    }
+    if let Ok(path) = std::env::var("METASTASA_WIKI_INDEX") {
+        let index = WikiIndex::open(&path).map_err(std::io::Error::other)?;
+        println!("📖 Локальный индекс Wikipedia: {} ({} статей)", path, index.len());
+        scraper.register(Arc::new(LocalWikiSource::new(Arc::new(index))));
+    }
+    let default_confirmations = if offline { 1 } else { 2 };
+    let min_confirmations = std::env::var("METASTASA_MIN_CONFIRMATIONS").ok().and_then(|v| v.parse().ok()).unwrap_or(default_confirmations);
+    println!("🔎 Источники проверки: {:?} (нужно подтверждений: {})", scraper.sources(), min_confirmations);
+    Ok(scraper.with_min_confirmations(min_confirmations))
+}
+
 pub async fn run_api() -> std::io::Result<()> {
-    let scraper = TrustedScraper::new();
-    let graph = web::Data::new(Mutex::new(KnowledgeGraph::new(sled::Config::new().temporary(true).open().unwrap())));
-    let processor = web::Data::new(Mutex::new(TextProcessor::new()));
+    let scraper = scraper_from_env()?;
+
+    // Модель эмбеддингов: hash[:dim], ngram[:dim] или word2vec:путь
+    if let Ok(spec) = std::env::var("METASTASA_EMBEDDER") {
+        let embedder = embedder_from_spec(&spec).map_err(std::io::Error::other)?;
+        println!("🧮 Модель эмбеддингов: {} ({} измерений)", embedder.name(), embedder.dimension());
+        EmbeddingCache::set_embedder(embedder);
+    }
+    // Граф знаний хранится на диске и восстанавливается при старте
+    let db_path = std::env::var("METASTASA_DB_PATH").unwrap_or_else(|_| "data/knowledge_graph".to_string());
+    let graph = KnowledgeGraph::open(&db_path)
+        .map_err(std::io::Error::other)?;
+    println!("💾 Граф знаний: {} ({} узлов, {} связей)", db_path, graph.graph.node_count(), graph.graph.edge_count());
+    // Кеш эмбеддингов ограничен по размеру (LRU) и возрасту (TTL) и хранится рядом с графом
+    EmbeddingCache::configure(cache_config_from_env());
+    let cache_tree = graph.db.open_tree("embedding_cache").map_err(std::io::Error::other)?;
+    let restored = EmbeddingCache::persist_to(cache_tree).map_err(std::io::Error::other)?;
+    println!("🧠 Кеш эмбеддингов: восстановлено {} записей", restored);
+    // Шаблоны извлечения: файл или каталог файлов TOML/JSON (по области на файл)
+    let patterns = match std::env::var("METASTASA_PATTERNS") {
+        Ok(path) => {
+            let patterns = PatternSet::load(&path).map_err(std::io::Error::other)?;
+            println!("📐 Шаблоны: {} ({} областей)", path, patterns.domains.len());
+            patterns
+        },
+        Err(_) => PatternSet::builtin(),
+    };
+    // Один и тот же граф пополняется через /learn и экспортируется через /graph
+    let graph = Arc::new(Mutex::new(graph));
+    let mut processor = TextProcessor::with_graph(graph.clone()).with_patterns(patterns).with_scraper(scraper.clone());
+    // Статистическая модель NER (cargo run --bin train_ner) вместо словарных сущностей
+    if let Ok(path) = std::env::var("METASTASA_NER_MODEL") {
+        let model = NerModel::load(&path).map_err(std::io::Error::other)?;
+        println!("🏷️ Модель NER: {} ({} меток)", path, model.labels.len());
+        processor = processor.with_ner(NerStage::new(Arc::new(model)));
+    }
+    let patterns = web::Data::from(processor.patterns());
+    let processor = web::Data::new(Mutex::new(processor));
+    let graph = web::Data::from(graph);
     
     println!("🚀 Запуск REST API сервера на http://127.0.0.1:8080");
     println!("📚 Доступные эндпоинты:");
     println!("  POST /process    - Обработка текста");
+    println!("  GET  /pipeline   - Этапы конвейера обработки текста");
     println!("  POST /learn      - Обучение на новых данных");
     println!("  POST /verify     - Верификация фактов");
     println!("  GET  /graph      - Экспорт графа знаний");
+    println!("  POST /query      - Запрос к графу знаний");
+    println!("  POST /ask        - Вопрос на естественном языке");
+    println!("  GET  /patterns   - Активный набор шаблонов");
+    println!("  POST /patterns   - Добавление или замена области шаблонов");
     println!("  GET  /cache      - Статистика кеша");
     println!("  POST /clear      - Очистка кеша");
-    println!("  GET  /similar    - Поиск похожих эмбеддингов");
+    println!("  POST /similar    - Поиск похожих узлов графа");
     println!("  GET  /health     - Проверка здоровья");
     println!("  GET  /info       - Информация о системе");
     
@@ -301,12 +559,18 @@ pub async fn run_api() -> std::io::Result<()> {
             .app_data(web::Data::new(scraper.clone()))
             .app_data(graph.clone())
             .app_data(processor.clone())
+            .app_data(patterns.clone())
             .route("/process", web::post().to(process_text))
+            .route("/pipeline", web::get().to(list_pipeline))
             .route("/learn", web::post().to(learn_from_text))
             .route("/verify", web::post().to(verify_fact))
             .route("/check", web::get().to(check_fact))
             .route("/moderate", web::get().to(moderate_fact))
             .route("/graph", web::get().to(export_graph))
+            .route("/query", web::post().to(query_graph))
+            .route("/ask", web::post().to(ask_question))
+            .route("/patterns", web::get().to(list_patterns))
+            .route("/patterns", web::post().to(update_patterns))
             .route("/cache", web::get().to(cache_stats))
             .route("/clear", web::post().to(clear_cache))
             .route("/similar", web::post().to(find_similar))
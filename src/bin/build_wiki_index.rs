@@ -0,0 +1,47 @@
+// bin/build_wiki_index.rs
+// Построение локального индекса Wikipedia для офлайн-проверки фактов ("Мыслящее Ядро")
+//
+// cargo run --release --bin build_wiki_index -- ruwiki-latest-pages-articles.xml data/wiki_index
+// bzcat ruwiki-latest-pages-articles.xml.bz2 | cargo run --release --bin build_wiki_index -- - data/wiki_index
+// cargo run --bin build_wiki_index -- articles/ data/wiki_index   (каталог .txt/.md, заголовок — имя файла)
+
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Instant;
+use metastasa::wiki_index::{IndexBuilder, WikiIndexError};
+
+fn build(input: &str, output: &str) -> Result<(), WikiIndexError> {
+    let started = Instant::now();
+    let mut builder = IndexBuilder::create(output)?;
+    let added = if input == "-" {
+        builder.add_mediawiki(io::stdin().lock())?
+    } else if Path::new(input).is_dir() {
+        builder.add_directory(input)?
+    } else {
+        builder.add_mediawiki(BufReader::new(File::open(input)?))?
+    };
+    let stats = builder.finish()?;
+    println!("📚 Добавлено статей: {} (всего в индексе: {}, слов: {})", added, stats.articles, stats.terms);
+    println!("⏱️ Время: {:.1} с", started.elapsed().as_secs_f64());
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [input, output] = args.as_slice() else {
+        eprintln!("Использование: build_wiki_index <дамп.xml | - | каталог> <каталог индекса>");
+        return ExitCode::from(2);
+    };
+    match build(input, output) {
+        Ok(()) => {
+            println!("💾 Индекс: {} (METASTASA_WIKI_INDEX={})", output, output);
+            ExitCode::SUCCESS
+        },
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
@@ -0,0 +1,93 @@
+// bin/evaluate.rs
+// Оценка качества извлечения по размеченному корпусу для "Мыслящего Ядро"
+//
+// cargo run --bin evaluate -- tests/data/gold.jsonl [--patterns каталог] [--ner модель.json] [--min-f1 0.8] [--json] [--errors]
+
+use std::process::ExitCode;
+use std::sync::Arc;
+use metastasa::evaluation::{self, EvaluationReport};
+use metastasa::ner::{NerModel, NerStage};
+use metastasa::patterns::PatternSet;
+use metastasa::text_processor::TextProcessor;
+
+fn usage() -> ExitCode {
+    eprintln!("Использование: evaluate <корпус.jsonl> [--patterns путь] [--ner модель.json] [--min-f1 порог] [--json] [--errors]");
+    ExitCode::from(2)
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let mut corpus_path = None;
+    let mut patterns_path = None;
+    let mut ner_path = None;
+    let mut min_f1 = None;
+    let mut json = false;
+    let mut errors = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--patterns" => patterns_path = args.next(),
+            "--ner" => ner_path = args.next(),
+            "--min-f1" => match args.next().and_then(|v| v.parse::<f64>().ok()) {
+                Some(v) => min_f1 = Some(v),
+                None => return usage(),
+            },
+            "--json" => json = true,
+            "--errors" => errors = true,
+            _ if corpus_path.is_none() && !arg.starts_with("--") => corpus_path = Some(arg),
+            _ => return usage(),
+        }
+    }
+    let Some(corpus_path) = corpus_path else { return usage() };
+
+    let corpus = match evaluation::load_corpus(&corpus_path) {
+        Ok(corpus) => corpus,
+        Err(e) => {
+            eprintln!("❌ {}: {}", corpus_path, e);
+            return ExitCode::FAILURE;
+        },
+    };
+    let mut processor = TextProcessor::new();
+    if let Some(path) = patterns_path {
+        match PatternSet::load(&path) {
+            Ok(patterns) => processor = processor.with_patterns(patterns),
+            Err(e) => {
+                eprintln!("❌ {}: {}", path, e);
+                return ExitCode::FAILURE;
+            },
+        }
+    }
+
+    if let Some(path) = ner_path {
+        match NerModel::load(&path) {
+            Ok(model) => processor = processor.with_ner(NerStage::new(Arc::new(model))),
+            Err(e) => {
+                eprintln!("❌ {}: {}", path, e);
+                return ExitCode::FAILURE;
+            },
+        }
+    }
+
+    let report: EvaluationReport = evaluation::evaluate(&processor, &corpus).await;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        print!("{}", report);
+        if errors {
+            println!("\nНе найдено:");
+            report.missed.iter().for_each(|m| println!("  {}", m));
+            println!("\nЛишнее:");
+            report.spurious.iter().for_each(|m| println!("  {}", m));
+        }
+    }
+
+    let f1 = report.total().f1();
+    match min_f1 {
+        Some(min) if f1 < min => {
+            eprintln!("❌ F1 {:.3} ниже порога {:.3}", f1, min);
+            ExitCode::FAILURE
+        },
+        _ => ExitCode::SUCCESS,
+    }
+}
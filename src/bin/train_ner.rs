@@ -0,0 +1,66 @@
+// bin/train_ner.rs
+// Обучение статистической модели NER для "Мыслящего Ядро"
+//
+// cargo run --bin train_ner -- tests/data/gold.jsonl models/ner.json [--epochs 10] [--dev dev.jsonl]
+
+use std::process::ExitCode;
+use metastasa::evaluation;
+use metastasa::ner::{self, NerModel};
+use metastasa::text_processor::TextProcessor;
+
+fn usage() -> ExitCode {
+    eprintln!("Использование: train_ner <корпус.jsonl> <модель.json> [--epochs N] [--dev корпус.jsonl]");
+    ExitCode::from(2)
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let mut paths = Vec::new();
+    let mut epochs = 10;
+    let mut dev_path = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--epochs" => match args.next().and_then(|v| v.parse().ok()) {
+                Some(v) => epochs = v,
+                None => return usage(),
+            },
+            "--dev" => dev_path = args.next(),
+            _ if !arg.starts_with("--") => paths.push(arg),
+            _ => return usage(),
+        }
+    }
+    let [corpus_path, model_path] = paths.as_slice() else { return usage() };
+
+    let processor = TextProcessor::new();
+    let load = |path: &str| match evaluation::load_corpus(path) {
+        Ok(corpus) => Some(ner::prepare_corpus(&processor, &corpus)),
+        Err(e) => {
+            eprintln!("❌ {}: {}", path, e);
+            None
+        },
+    };
+    let Some(train) = load(corpus_path) else { return ExitCode::FAILURE };
+    println!("📚 Предложений для обучения: {}", train.len());
+
+    let model = match NerModel::train(&train, epochs) {
+        Ok(model) => model,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return ExitCode::FAILURE;
+        },
+    };
+    println!("🧠 Эпох: {}, меток: {}, признаков: {}", epochs, model.labels.len(), model.feature_count());
+    println!("🎯 Точность разметки слов (обучение): {:.3}", model.accuracy(&train));
+    if let Some(dev_path) = dev_path {
+        let Some(dev) = load(&dev_path) else { return ExitCode::FAILURE };
+        println!("🎯 Точность разметки слов ({}): {:.3}", dev_path, model.accuracy(&dev));
+    }
+
+    if let Err(e) = model.save(model_path) {
+        eprintln!("❌ {}: {}", model_path, e);
+        return ExitCode::FAILURE;
+    }
+    println!("💾 Модель сохранена: {}", model_path);
+    ExitCode::SUCCESS
+}
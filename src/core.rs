@@ -6,6 +6,7 @@ use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use lazy_static::lazy_static;
 use regex::Regex;
+use serde::{Serialize, Deserialize};
 
 /// Мультимодальный сегмент
 #[derive(Debug, Clone)]
@@ -24,20 +25,75 @@ pub enum SegmentType {
 }
 
 /// Тип связи между узлами графа
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum EdgeType {
     Cause,
     PartOf,
     Example,
+    IsA,      // "является", "это"
+    Location, // "водится", "живет", "находится"
+    Action,   // Прочие действия субъект-глагол-объект
+    Property, // Прочие свойства сущность-атрибут-значение
+}
+
+impl EdgeType {
+    /// Определить тип связи по метке (глаголу или атрибуту);
+    /// `fallback` используется для неизвестных меток
+    pub fn from_label(label: &str, fallback: EdgeType) -> EdgeType {
+        match label.trim().to_lowercase().as_str() {
+            "является" | "это" => EdgeType::IsA,
+            "водится" | "живет" | "обитает" | "находится" | "расположен" => EdgeType::Location,
+            "имеет" | "содержит" | "включает" | "состоит из" => EdgeType::PartOf,
+            "вызывает" | "приводит к" => EdgeType::Cause,
+            "например" => EdgeType::Example,
+            "is a" | "is an" | "is" | "are" => EdgeType::IsA,
+            "lives in" | "live in" | "inhabits" | "inhabit" | "is located in" | "are located in" => EdgeType::Location,
+            "has" | "have" | "contains" | "contain" | "includes" | "include" | "consists of" | "consist of" => EdgeType::PartOf,
+            "causes" | "cause" | "leads to" | "lead to" => EdgeType::Cause,
+            "e.g." | "for example" => EdgeType::Example,
+            _ => fallback,
+        }
+    }
+}
+
+/// Полярность факта: утверждение или отрицание ("кот не ест рыбу")
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Polarity {
+    #[default]
+    Positive,
+    Negative,
+}
+
+/// Модальность факта: насколько уверенно он утверждается в тексте
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Modality {
+    #[default]
+    Asserted, // "кот ест рыбу"
+    Probable, // "вероятно", "скорее всего", "probably"
+    Possible, // "возможно", "может быть", "maybe", "might"
+}
+
+impl Modality {
+    /// Множитель уверенности извлечённого факта
+    pub fn confidence_factor(self) -> f32 {
+        match self {
+            Modality::Asserted => 1.0,
+            Modality::Probable => 0.8,
+            Modality::Possible => 0.5,
+        }
+    }
 }
 
 /// Источник знания
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Source {
     ArXiv,
     Wikipedia,
     StackOverflow,
+    PubMed,
     UserCorrected,
+    Extracted, // Извлечено из текста без внешнего подтверждения
+    Internal,  // Внутренний источник организации (вики, хранилище документов)
 }
 
 #[derive(Debug, Clone)]
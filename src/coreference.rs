@@ -0,0 +1,223 @@
+// coreference.rs
+// Разрешение местоимений (кореференция) для "Мыслящего Ядро"
+//
+// "Кот ест рыбу. Он живет дома." — "он" заменяется на "кот" до записи в граф.
+// Эвристика: местоимение ссылается на ближайшее предшествующее упоминание
+// (сущность, субъект или объект факта) в том же или двух предыдущих предложениях,
+// совместимое по роду и числу. Род русских существительных определяется по
+// окончанию леммы, число — по расхождению словоформы и леммы.
+
+use serde::{Serialize, Deserialize};
+use crate::language::Language;
+use crate::text_processor::{Action, Entity, EntityType, Property};
+
+/// Сколько предыдущих предложений просматривается в поисках антецедента
+const SENTENCE_WINDOW: usize = 2;
+
+/// Грамматический род
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gender {
+    Masculine,
+    Feminine,
+    Neuter,
+}
+
+/// Возможные род и число слова
+#[derive(Debug, Clone, PartialEq)]
+pub struct Features {
+    pub genders: Vec<Gender>, // Для множественного числа не важен
+    pub plural: bool,
+}
+
+impl Features {
+    fn new(genders: &[Gender], plural: bool) -> Self {
+        Self { genders: genders.to_vec(), plural }
+    }
+
+    fn compatible(&self, other: &Features) -> bool {
+        self.plural == other.plural && (self.plural || self.genders.iter().any(|g| other.genders.contains(g)))
+    }
+}
+
+use Gender::{Feminine as F, Masculine as M, Neuter as N};
+
+/// Местоимения третьего лица: формы и признаки
+const RUSSIAN_PRONOUNS: &[(&str, &[Gender], bool)] = &[
+    ("он", &[M], false),
+    ("его него ему нему им ним нём нем", &[M, N], false),
+    ("она её ее неё нее ей ней ею нею", &[F], false),
+    ("оно", &[N], false),
+    ("они их них ими ними", &[], true),
+];
+
+const ENGLISH_PRONOUNS: &[(&str, &[Gender], bool)] = &[
+    ("he him his himself", &[M], false),
+    ("she her hers herself", &[F], false),
+    ("it its itself", &[N], false),
+    ("they them their theirs themselves", &[], true),
+];
+
+/// Существительные на -а/-я мужского рода
+const RUSSIAN_MASCULINE_A: &[&str] = &["папа", "дядя", "мужчина", "юноша", "дедушка", "слуга", "судья"];
+
+/// Признаки местоимения; None — слово не местоимение
+pub fn pronoun_features(word: &str, language: Language) -> Option<Features> {
+    let table = match language {
+        Language::Russian => RUSSIAN_PRONOUNS,
+        Language::English => ENGLISH_PRONOUNS,
+    };
+    let word = word.to_lowercase();
+    table
+        .iter()
+        .find(|(forms, _, _)| forms.split_whitespace().any(|f| f == word))
+        .map(|&(_, genders, plural)| Features::new(genders, plural))
+}
+
+/// Признаки существительного по лемме и словоформе в тексте
+pub fn noun_features(lemma: &str, surface: &str, entity_type: Option<&EntityType>, language: Language) -> Features {
+    let surface = surface.to_lowercase();
+    match language {
+        Language::Russian => {
+            let plural = surface != lemma && (surface.ends_with('ы') || surface.ends_with('и')) && !(lemma.ends_with('ь') && surface.ends_with('и') && surface.len() == lemma.len());
+            let genders: &[Gender] = match lemma.chars().last() {
+                _ if RUSSIAN_MASCULINE_A.contains(&lemma) => &[M],
+                Some('а' | 'я') => &[F],
+                Some('о' | 'е' | 'ё') => &[N],
+                Some('ь') => &[M, F],
+                _ => &[M],
+            };
+            Features::new(genders, plural)
+        },
+        Language::English => {
+            let plural = surface != lemma && surface.ends_with('s');
+            let genders: &[Gender] = match entity_type {
+                Some(EntityType::Person) => &[M, F],
+                Some(EntityType::Animal) => &[M, F, N],
+                _ => &[N],
+            };
+            Features::new(genders, plural)
+        },
+    }
+}
+
+/// Разрешённое местоимение
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Coreference {
+    pub pronoun: String,
+    pub antecedent: String,
+    pub sentence_id: usize,
+    pub antecedent_sentence_id: usize,
+    pub start: usize, // Смещение местоимения в исходном тексте (байты)
+}
+
+/// Упоминание, на которое может ссылаться местоимение
+#[derive(Debug, Clone)]
+struct Mention {
+    text: String,
+    position: usize,
+    sentence_id: usize,
+    features: Features,
+}
+
+/// Слот факта, который может оказаться местоимением
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Slot {
+    ActionSubject(usize),
+    ActionObject(usize),
+    PropertyEntity(usize),
+    PropertyValue(usize),
+}
+
+/// Слово слота в тексте фрагмента: первое для субъекта, последнее для объекта
+fn slot_surface(span: &str, first: bool) -> &str {
+    let mut words = span.split(|c: char| !c.is_alphanumeric() && c != '-').filter(|w| !w.is_empty());
+    if first { words.next() } else { words.next_back() }.unwrap_or("")
+}
+
+/// Разрешить местоимения в субъектах и объектах действий и свойств (замена на месте).
+/// Спаны фактов должны указывать в один и тот же текст (как после `process_text`)
+pub fn resolve(language: Language, entities: &[Entity], actions: &mut [Action], properties: &mut [Property]) -> Vec<Coreference> {
+    // Упоминания: сущности и слоты фактов, не являющиеся местоимениями
+    let mut mentions: Vec<Mention> = Vec::new();
+    let mut push_mention = |text: &str, surface: &str, position: usize, sentence_id: usize, entity_type: Option<&EntityType>| {
+        if pronoun_features(text, language).is_none() && !text.is_empty() {
+            mentions.push(Mention {
+                text: text.to_string(),
+                position,
+                sentence_id,
+                features: noun_features(text, surface, entity_type, language),
+            });
+        }
+    };
+    for e in entities.iter().filter(|e| e.entity_type != EntityType::Date) {
+        push_mention(&e.text, &e.span.text, e.span.start, e.span.sentence_id, Some(&e.entity_type));
+    }
+    let type_of = |text: &str| entities.iter().find(|e| e.text == text).map(|e| &e.entity_type);
+    for a in actions.iter() {
+        push_mention(&a.subject, slot_surface(&a.span.text, true), a.span.start, a.span.sentence_id, type_of(&a.subject));
+        push_mention(&a.object, slot_surface(&a.span.text, false), a.span.end, a.span.sentence_id, type_of(&a.object));
+    }
+    for p in properties.iter().filter(|p| p.quantity.is_none()) {
+        push_mention(&p.entity, slot_surface(&p.span.text, true), p.span.start, p.span.sentence_id, type_of(&p.entity));
+        push_mention(&p.value, slot_surface(&p.span.text, false), p.span.end, p.span.sentence_id, type_of(&p.value));
+    }
+
+    // Слоты-местоимения в порядке появления в тексте
+    let mut slots: Vec<(usize, usize, Slot, String)> = Vec::new();
+    for (i, a) in actions.iter().enumerate() {
+        slots.push((a.span.start, a.span.sentence_id, Slot::ActionSubject(i), a.subject.clone()));
+        slots.push((a.span.end, a.span.sentence_id, Slot::ActionObject(i), a.object.clone()));
+    }
+    for (i, p) in properties.iter().enumerate() {
+        slots.push((p.span.start, p.span.sentence_id, Slot::PropertyEntity(i), p.entity.clone()));
+        slots.push((p.span.end, p.span.sentence_id, Slot::PropertyValue(i), p.value.clone()));
+    }
+    slots.sort_by_key(|&(position, ..)| position);
+
+    let mut resolved = Vec::new();
+    for (position, sentence_id, slot, word) in slots {
+        let Some(features) = pronoun_features(&word, language) else { continue };
+        let antecedent = mentions
+            .iter()
+            .filter(|m| m.position < position && m.sentence_id + SENTENCE_WINDOW >= sentence_id)
+            .filter(|m| m.features.compatible(&features))
+            .max_by_key(|m| m.position)
+            .cloned();
+        let Some(antecedent) = antecedent else { continue };
+
+        match slot {
+            Slot::ActionSubject(i) => actions[i].subject = antecedent.text.clone(),
+            Slot::ActionObject(i) => actions[i].object = antecedent.text.clone(),
+            Slot::PropertyEntity(i) => properties[i].entity = antecedent.text.clone(),
+            Slot::PropertyValue(i) => properties[i].value = antecedent.text.clone(),
+        }
+        // Разрешённое местоимение само становится упоминанием ("Кот спит. Он ест. Он ...")
+        mentions.push(Mention { position, sentence_id, ..antecedent.clone() });
+        resolved.push(Coreference {
+            pronoun: word,
+            antecedent: antecedent.text,
+            sentence_id,
+            antecedent_sentence_id: antecedent.sentence_id,
+            start: position,
+        });
+    }
+
+    resolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_features() {
+        let cat = noun_features("кот", "кот", None, Language::Russian);
+        let fish = noun_features("рыба", "рыбу", None, Language::Russian);
+        let he = pronoun_features("Он", Language::Russian).unwrap();
+        assert!(cat.compatible(&he));
+        assert!(!fish.compatible(&he));
+        assert!(fish.compatible(&pronoun_features("её", Language::Russian).unwrap()));
+        assert!(noun_features("кот", "коты", None, Language::Russian).compatible(&pronoun_features("они", Language::Russian).unwrap()));
+        assert!(pronoun_features("кот", Language::Russian).is_none());
+    }
+}
@@ -0,0 +1,336 @@
+// embedder.rs
+// Подключаемые модели эмбеддингов для "Мыслящего Ядро"
+//
+// - HashEmbedder: исходная хэш-версия (сумма байтов по модулю размерности), базовая линия
+// - NgramEmbedder: хэширование символьных n-грамм слов (устойчив к словоформам: "кот" ~ "кошка")
+// - WordVectorEmbedder: локально обученные векторы слов из текстового файла (формат word2vec/GloVe)
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Модель эмбеддингов текста
+pub trait Embedder: Send + Sync + fmt::Debug {
+    /// Короткое имя модели (для статистики и логов)
+    fn name(&self) -> &str;
+
+    /// Размерность выходных векторов
+    fn dimension(&self) -> usize;
+
+    /// Эмбеддинг одного текста
+    fn embed(&self, text: &str) -> Vec<f32>;
+
+    /// Эмбеддинги пачки текстов
+    fn embed_batch(&self, texts: &[String]) -> Vec<Vec<f32>> {
+        texts.iter().map(|t| self.embed(t)).collect()
+    }
+}
+
+/// Ошибка создания модели эмбеддингов
+#[derive(Debug)]
+pub enum EmbedderError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+    DimensionMismatch { line: usize, expected: usize, found: usize },
+    Empty,
+    UnknownSpec(String),
+}
+
+impl fmt::Display for EmbedderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmbedderError::Io(e) => write!(f, "Ошибка чтения файла векторов: {}", e),
+            EmbedderError::Parse { line, message } => write!(f, "Строка {}: {}", line, message),
+            EmbedderError::DimensionMismatch { line, expected, found } => {
+                write!(f, "Строка {}: ожидалась размерность {}, получено {}", line, expected, found)
+            },
+            EmbedderError::Empty => write!(f, "Файл векторов не содержит ни одного слова"),
+            EmbedderError::UnknownSpec(spec) => write!(f, "Неизвестная модель эмбеддингов: {}", spec),
+        }
+    }
+}
+
+impl Error for EmbedderError {}
+
+impl From<io::Error> for EmbedderError {
+    fn from(e: io::Error) -> Self {
+        EmbedderError::Io(e)
+    }
+}
+
+/// L2-нормализация вектора на месте
+fn normalize(vector: &mut [f32]) {
+    let norm: f32 = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for val in vector.iter_mut() {
+            *val /= norm;
+        }
+    }
+}
+
+/// FNV-1a: стабильный между запусками хэш (в отличие от DefaultHasher)
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Размерность моделей по умолчанию
+pub const DEFAULT_DIMENSION: usize = 64;
+
+/// Базовая хэш-модель: сумма байтов по позициям modulo размерность
+#[derive(Debug, Clone)]
+pub struct HashEmbedder {
+    dimension: usize,
+}
+
+impl HashEmbedder {
+    /// Паникует при нулевой размерности
+    pub fn new(dimension: usize) -> Self {
+        assert!(dimension > 0, "HashEmbedder: размерность эмбеддингов должна быть больше нуля");
+        Self { dimension }
+    }
+}
+
+impl Default for HashEmbedder {
+    fn default() -> Self {
+        Self::new(DEFAULT_DIMENSION)
+    }
+}
+
+impl Embedder for HashEmbedder {
+    fn name(&self) -> &str {
+        "hash"
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn embed(&self, text: &str) -> Vec<f32> {
+        let mut embedding = vec![0.0; self.dimension];
+        for (i, &byte) in text.as_bytes().iter().enumerate() {
+            embedding[i % self.dimension] += byte as f32 / 255.0;
+        }
+        normalize(&mut embedding);
+        embedding
+    }
+}
+
+/// Хэширование символьных n-грамм (feature hashing, как в fastText без обучения)
+#[derive(Debug, Clone)]
+pub struct NgramEmbedder {
+    dimension: usize,
+    min_n: usize,
+    max_n: usize,
+}
+
+impl NgramEmbedder {
+    /// Паникует при нулевой размерности
+    pub fn new(dimension: usize, min_n: usize, max_n: usize) -> Self {
+        assert!(dimension > 0, "NgramEmbedder: размерность эмбеддингов должна быть больше нуля");
+        Self { dimension, min_n: min_n.max(1), max_n: max_n.max(min_n.max(1)) }
+    }
+
+    fn add_feature(&self, embedding: &mut [f32], feature: &str, weight: f32) {
+        let hash = fnv1a(feature.as_bytes());
+        let bucket = (hash % self.dimension as u64) as usize;
+        // Старший бит задаёт знак: уменьшает смещение от коллизий
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        embedding[bucket] += sign * weight;
+    }
+}
+
+impl Default for NgramEmbedder {
+    fn default() -> Self {
+        Self::new(DEFAULT_DIMENSION, 2, 4)
+    }
+}
+
+impl Embedder for NgramEmbedder {
+    fn name(&self) -> &str {
+        "ngram"
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn embed(&self, text: &str) -> Vec<f32> {
+        let mut embedding = vec![0.0; self.dimension];
+        let lowered = text.to_lowercase();
+        for word in lowered.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+            // Границы слова помечаются, чтобы префиксы и суффиксы различались
+            let chars: Vec<char> = format!("<{}>", word).chars().collect();
+            for n in self.min_n..=self.max_n {
+                for window in chars.windows(n) {
+                    self.add_feature(&mut embedding, &window.iter().collect::<String>(), 1.0);
+                }
+            }
+            self.add_feature(&mut embedding, word, 1.0);
+        }
+        normalize(&mut embedding);
+        embedding
+    }
+}
+
+/// Векторы слов, обученные локально (word2vec/GloVe в текстовом формате)
+#[derive(Debug, Clone)]
+pub struct WordVectorEmbedder {
+    dimension: usize,
+    vectors: HashMap<String, Vec<f32>>,
+}
+
+impl WordVectorEmbedder {
+    /// Загрузить векторы из файла: строки `слово v1 v2 ... vN`,
+    /// необязательный заголовок word2vec `число_слов размерность` пропускается
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, EmbedderError> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Загрузить векторы из произвольного источника строк
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self, EmbedderError> {
+        let mut vectors = HashMap::new();
+        let mut dimension = 0;
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let mut parts = line.split_whitespace();
+            let Some(word) = parts.next() else { continue };
+            let values: Vec<&str> = parts.collect();
+
+            // Заголовок word2vec: два целых числа в первой строке
+            if i == 0 && values.len() == 1 && word.parse::<usize>().is_ok() && values[0].parse::<usize>().is_ok() {
+                continue;
+            }
+
+            let vector = values
+                .iter()
+                .map(|v| v.parse::<f32>())
+                .collect::<Result<Vec<f32>, _>>()
+                .map_err(|e| EmbedderError::Parse { line: i + 1, message: e.to_string() })?;
+            if dimension == 0 {
+                dimension = vector.len();
+            } else if vector.len() != dimension {
+                return Err(EmbedderError::DimensionMismatch { line: i + 1, expected: dimension, found: vector.len() });
+            }
+            vectors.insert(word.to_lowercase(), vector);
+        }
+
+        if vectors.is_empty() || dimension == 0 {
+            return Err(EmbedderError::Empty);
+        }
+        Ok(Self { dimension, vectors })
+    }
+
+    /// Число слов в словаре
+    pub fn vocabulary_size(&self) -> usize {
+        self.vectors.len()
+    }
+}
+
+impl Embedder for WordVectorEmbedder {
+    fn name(&self) -> &str {
+        "word2vec"
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Среднее векторов известных слов; неизвестные слова пропускаются
+    fn embed(&self, text: &str) -> Vec<f32> {
+        let mut embedding = vec![0.0; self.dimension];
+        let lowered = text.to_lowercase();
+        for word in lowered.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+            if let Some(vector) = self.vectors.get(word) {
+                for (acc, v) in embedding.iter_mut().zip(vector) {
+                    *acc += v;
+                }
+            }
+        }
+        normalize(&mut embedding);
+        embedding
+    }
+}
+
+/// Модель по умолчанию: базовая хэш-модель (как до подключаемых моделей),
+/// чтобы сохранённые эмбеддинги и пороги сходства оставались прежними
+pub fn default_embedder() -> Arc<dyn Embedder> {
+    Arc::new(HashEmbedder::default())
+}
+
+/// Создать модель по текстовому описанию:
+/// `hash[:dim]`, `ngram[:dim]`, `word2vec:путь/к/файлу`
+pub fn embedder_from_spec(spec: &str) -> Result<Arc<dyn Embedder>, EmbedderError> {
+    let (kind, arg) = match spec.split_once(':') {
+        Some((kind, arg)) => (kind.trim(), Some(arg.trim())),
+        None => (spec.trim(), None),
+    };
+    let dimension = |arg: Option<&str>| -> Result<usize, EmbedderError> {
+        match arg {
+            None => Ok(DEFAULT_DIMENSION),
+            Some(d) => d.parse().ok().filter(|&d| d > 0).ok_or_else(|| EmbedderError::UnknownSpec(spec.to_string())),
+        }
+    };
+
+    match (kind, arg) {
+        ("hash", arg) => Ok(Arc::new(HashEmbedder::new(dimension(arg)?))),
+        ("ngram", arg) => Ok(Arc::new(NgramEmbedder::new(dimension(arg)?, 2, 4))),
+        ("word2vec", Some(path)) => Ok(Arc::new(WordVectorEmbedder::load(path)?)),
+        _ => Err(EmbedderError::UnknownSpec(spec.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding_cache::EmbeddingCache;
+
+    #[test]
+    fn test_ngram_similarity_beats_hash() {
+        let ngram = NgramEmbedder::default();
+        let sim = |e: &dyn Embedder, a: &str, b: &str| EmbeddingCache::cosine_similarity(&e.embed(a), &e.embed(b));
+        assert!(sim(&ngram, "кот", "кошка") > sim(&ngram, "кот", "дерево"));
+        assert!(sim(&ngram, "рыба", "рыбу") > sim(&ngram, "рыба", "кошка"));
+        assert_eq!(ngram.embed("Кот").len(), 64);
+    }
+
+    #[test]
+    fn test_word_vectors_from_reader() {
+        let data = "3 2\nкот 1.0 0.0\nкошка 0.9 0.1\nдерево 0.0 1.0\n";
+        let embedder = WordVectorEmbedder::from_reader(data.as_bytes()).unwrap();
+        assert_eq!(embedder.dimension(), 2);
+        assert_eq!(embedder.vocabulary_size(), 3);
+        let cat = embedder.embed("Кот");
+        let kitty = embedder.embed("кошка");
+        assert!(EmbeddingCache::cosine_similarity(&cat, &kitty) > 0.9);
+        assert_eq!(embedder.embed("собака"), vec![0.0, 0.0]);
+
+        let broken = WordVectorEmbedder::from_reader("кот 1.0 0.0\nрыба 1.0\n".as_bytes());
+        assert!(matches!(broken, Err(EmbedderError::DimensionMismatch { line: 2, .. })));
+    }
+
+    #[test]
+    fn test_embedder_from_spec() {
+        assert_eq!(embedder_from_spec("hash").unwrap().name(), "hash");
+        assert_eq!(embedder_from_spec("ngram:128").unwrap().dimension(), 128);
+        assert!(embedder_from_spec("bert").is_err());
+        assert!(embedder_from_spec("word2vec").is_err());
+        assert!(embedder_from_spec("ngram:0").is_err());
+        assert_eq!(default_embedder().name(), "hash");
+    }
+
+    #[test]
+    #[should_panic(expected = "размерность эмбеддингов должна быть больше нуля")]
+    fn test_zero_dimension_rejected() {
+        NgramEmbedder::new(0, 2, 4);
+    }
+}
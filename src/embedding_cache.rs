@@ -3,28 +3,78 @@
 // Подробные комментарии для понимания архитектуры
 
 use std::collections::HashMap;
+use std::num::NonZeroUsize;
 use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
 use lazy_static::lazy_static;
+use lru::LruCache;
 use serde::{Serialize, Deserialize};
 use crate::text_processor::{Entity, Action, Property};
+use crate::embedder::{Embedder, HashEmbedder, default_embedder};
 
-/// Кеш эмбеддингов для оптимизации производительности
+/// Ключ в sled, под которым хранится модель закешированных векторов
+const MODEL_KEY: &[u8] = b"meta:model";
+
+/// Ограничения кеша: размер каждого раздела и время жизни записи
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CacheConfig {
+    pub capacity: usize,          // Максимум записей в каждом разделе (LRU)
+    pub ttl_seconds: Option<u64>, // Время жизни записи; None — без ограничения
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self { capacity: 10_000, ttl_seconds: Some(24 * 60 * 60) }
+    }
+}
+
+/// Раздел кеша (по типу закешированного объекта)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CacheKind {
+    Text,
+    Entity,
+    Action,
+    Property,
+}
+
+impl CacheKind {
+    const ALL: [CacheKind; 4] = [CacheKind::Text, CacheKind::Entity, CacheKind::Action, CacheKind::Property];
+
+    /// Префикс ключей раздела в sled
+    fn prefix(self) -> &'static str {
+        match self {
+            CacheKind::Text => "text",
+            CacheKind::Entity => "entity",
+            CacheKind::Action => "action",
+            CacheKind::Property => "property",
+        }
+    }
+
+    fn from_prefix(prefix: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.prefix() == prefix)
+    }
+}
+
+/// Кеш эмбеддингов для оптимизации производительности:
+/// LRU по размеру, TTL по возрасту записи, необязательная сквозная запись в sled
 #[derive(Debug, Clone)]
 pub struct EmbeddingCache {
-    pub embeddings: HashMap<String, Vec<f32>>,
-    pub entity_embeddings: HashMap<String, Vec<f32>>,
-    pub action_embeddings: HashMap<String, Vec<f32>>,
-    pub property_embeddings: HashMap<String, Vec<f32>>,
+    pub embeddings: LruCache<String, CachedEmbedding>,
+    pub entity_embeddings: LruCache<String, CachedEmbedding>,
+    pub action_embeddings: LruCache<String, CachedEmbedding>,
+    pub property_embeddings: LruCache<String, CachedEmbedding>,
+    pub embedder: Arc<dyn Embedder>, // Модель, которой вычисляются все эмбеддинги
+    pub config: CacheConfig,
+    storage: Option<sled::Tree>,     // Копия кеша на диске, переживает перезапуск
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+    expired: u64,
 }
 
 /// Глобальный кеш эмбеддингов (thread-safe)
 lazy_static! {
-    static ref EMBEDDING_CACHE: Arc<Mutex<EmbeddingCache>> = Arc::new(Mutex::new(EmbeddingCache {
-        embeddings: HashMap::new(),
-        entity_embeddings: HashMap::new(),
-        action_embeddings: HashMap::new(),
-        property_embeddings: HashMap::new(),
-    }));
+    static ref EMBEDDING_CACHE: Arc<Mutex<EmbeddingCache>> = Arc::new(Mutex::new(EmbeddingCache::new()));
 }
 
 /// Структура для кешированного эмбеддинга
@@ -33,78 +83,277 @@ pub struct CachedEmbedding {
     pub text: String,
     pub embedding: Vec<f32>,
     pub confidence: f32,
-    pub timestamp: u64,
+    pub timestamp: u64, // Время вычисления, секунды с UNIX_EPOCH
+}
+
+fn now_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+impl Default for EmbeddingCache {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl EmbeddingCache {
-    /// Создать новый кеш эмбеддингов
+    /// Создать новый кеш эмбеддингов с моделью по умолчанию
     pub fn new() -> Self {
+        Self::with_embedder(default_embedder())
+    }
+
+    /// Создать кеш эмбеддингов с заданной моделью
+    pub fn with_embedder(embedder: Arc<dyn Embedder>) -> Self {
+        Self::with_config(embedder, CacheConfig::default())
+    }
+
+    /// Создать кеш эмбеддингов с заданной моделью и ограничениями
+    pub fn with_config(embedder: Arc<dyn Embedder>, config: CacheConfig) -> Self {
+        let capacity = NonZeroUsize::new(config.capacity).unwrap_or(NonZeroUsize::MIN);
         Self {
-            embeddings: HashMap::new(),
-            entity_embeddings: HashMap::new(),
-            action_embeddings: HashMap::new(),
-            property_embeddings: HashMap::new(),
+            embeddings: LruCache::new(capacity),
+            entity_embeddings: LruCache::new(capacity),
+            action_embeddings: LruCache::new(capacity),
+            property_embeddings: LruCache::new(capacity),
+            embedder,
+            config,
+            storage: None,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+            expired: 0,
         }
     }
 
-    /// Получить кешированный эмбеддинг или вычислить новый
-    pub fn get_cached_embedding(text: &str) -> Vec<f32> {
+    /// Заменить модель глобального кеша (закешированные векторы сбрасываются,
+    /// так как векторы разных моделей несравнимы)
+    pub fn set_embedder(embedder: Arc<dyn Embedder>) {
         let mut cache = EMBEDDING_CACHE.lock().unwrap();
-        cache.embeddings.entry(text.to_string()).or_insert_with(|| {
-            Self::calculate_embedding(text)
-        }).clone()
+        cache.embedder = embedder;
+        cache.clear();
     }
 
-    /// Вычислить эмбеддинг для текста (упрощенная версия)
-    pub fn calculate_embedding(text: &str) -> Vec<f32> {
-        // Упрощенный эмбеддинг на основе хэша
-        // В реальной реализации здесь будет интеграция с BERT или другой моделью
-        let mut embedding = vec![0.0; 64]; // 64-мерный вектор
-        let bytes = text.as_bytes();
-        
-        for (i, &byte) in bytes.iter().enumerate() {
-            let idx = i % 64;
-            embedding[idx] += byte as f32 / 255.0;
+    /// Текущая модель глобального кеша
+    pub fn embedder() -> Arc<dyn Embedder> {
+        EMBEDDING_CACHE.lock().unwrap().embedder.clone()
+    }
+
+    /// Задать ограничения глобального кеша (текущие записи сбрасываются)
+    pub fn configure(config: CacheConfig) {
+        let mut cache = EMBEDDING_CACHE.lock().unwrap();
+        let storage = cache.storage.take();
+        *cache = Self::with_config(cache.embedder.clone(), config);
+        if let Some(tree) = storage {
+            cache.storage = Some(tree);
+            cache.clear();
         }
-        
-        // Нормализация
-        let norm: f32 = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
-        if norm > 0.0 {
-            for val in &mut embedding {
-                *val /= norm;
+    }
+
+    /// Подключить глобальный кеш к дереву sled; возвращает число восстановленных записей
+    pub fn persist_to(tree: sled::Tree) -> sled::Result<usize> {
+        EMBEDDING_CACHE.lock().unwrap().attach_storage(tree)
+    }
+
+    /// Очистить глобальный кеш; возвращает число удалённых записей
+    pub fn clear_all() -> usize {
+        EMBEDDING_CACHE.lock().unwrap().clear()
+    }
+
+    /// Подключить хранилище: записи той же модели загружаются в память,
+    /// просроченные и записи другой модели удаляются
+    pub fn attach_storage(&mut self, tree: sled::Tree) -> sled::Result<usize> {
+        let model = self.model_tag();
+        if tree.get(MODEL_KEY)?.as_deref() != Some(model.as_bytes()) {
+            tree.clear()?;
+            tree.insert(MODEL_KEY, model.as_bytes())?;
+        }
+
+        // Хранилище подключается сразу: вытесненные при загрузке записи удаляются и с диска
+        self.storage = Some(tree.clone());
+        let entries = tree.iter().collect::<sled::Result<Vec<_>>>()?;
+        let now = now_seconds();
+        let mut stale = Vec::new();
+        let mut restored = 0;
+        for (key, value) in entries {
+            if &*key == MODEL_KEY {
+                continue;
+            }
+            let decoded = std::str::from_utf8(&key).ok()
+                .and_then(|k| k.split_once(':'))
+                .and_then(|(prefix, k)| Some((CacheKind::from_prefix(prefix)?, k.to_string())))
+                .zip(serde_json::from_slice::<CachedEmbedding>(&value).ok());
+            match decoded {
+                Some(((kind, k), entry)) if !self.is_expired(&entry, now) => {
+                    self.put(kind, k, entry);
+                    restored += 1;
+                },
+                _ => stale.push(key),
             }
         }
-        
+        for key in stale {
+            tree.remove(key)?;
+        }
+        Ok(restored)
+    }
+
+    /// Имя и размерность модели: записи другой модели на диске недействительны
+    fn model_tag(&self) -> String {
+        format!("{}:{}", self.embedder.name(), self.embedder.dimension())
+    }
+
+    fn slot(&mut self, kind: CacheKind) -> &mut LruCache<String, CachedEmbedding> {
+        match kind {
+            CacheKind::Text => &mut self.embeddings,
+            CacheKind::Entity => &mut self.entity_embeddings,
+            CacheKind::Action => &mut self.action_embeddings,
+            CacheKind::Property => &mut self.property_embeddings,
+        }
+    }
+
+    fn is_expired(&self, entry: &CachedEmbedding, now: u64) -> bool {
+        self.config.ttl_seconds
+            .is_some_and(|ttl| now.saturating_sub(entry.timestamp) > ttl)
+    }
+
+    fn storage_key(kind: CacheKind, key: &str) -> String {
+        format!("{}:{}", kind.prefix(), key)
+    }
+
+    fn store(&self, kind: CacheKind, key: &str, entry: &CachedEmbedding) {
+        let Some(tree) = &self.storage else { return };
+        let result = serde_json::to_vec(entry)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| tree.insert(Self::storage_key(kind, key), bytes).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            println!("⚠️ Не удалось сохранить эмбеддинг на диск: {}", e);
+        }
+    }
+
+    fn unstore(&self, kind: CacheKind, key: &str) {
+        if let Some(tree) = &self.storage
+            && let Err(e) = tree.remove(Self::storage_key(kind, key))
+        {
+            println!("⚠️ Не удалось удалить эмбеддинг с диска: {}", e);
+        }
+    }
+
+    /// Положить запись в раздел; вытесненная LRU запись удаляется и с диска
+    fn put(&mut self, kind: CacheKind, key: String, entry: CachedEmbedding) {
+        if let Some((old_key, _)) = self.slot(kind).push(key.clone(), entry)
+            && old_key != key
+        {
+            self.evictions += 1;
+            self.unstore(kind, &old_key);
+        }
+    }
+
+    /// Взять запись из кеша или вычислить её и записать (в память и на диск)
+    fn get_or_compute(&mut self, kind: CacheKind, key: String, text: &str, confidence: f32, now: u64) -> Vec<f32> {
+        let cached = self.slot(kind).get(&key).cloned();
+        match cached {
+            Some(entry) if !self.is_expired(&entry, now) => {
+                self.hits += 1;
+                return entry.embedding;
+            },
+            Some(_) => {
+                self.expired += 1;
+                self.slot(kind).pop(&key);
+            },
+            None => {},
+        }
+
+        self.misses += 1;
+        let entry = CachedEmbedding {
+            text: text.to_string(),
+            embedding: self.embedder.embed(text),
+            confidence,
+            timestamp: now,
+        };
+        let embedding = entry.embedding.clone();
+        self.store(kind, &key, &entry);
+        self.put(kind, key, entry);
         embedding
     }
 
+    /// Удалить записи старше max_age_seconds; возвращает число удалённых
+    pub fn evict_older_than(&mut self, max_age_seconds: u64, now: u64) -> usize {
+        let mut removed = 0;
+        for kind in CacheKind::ALL {
+            let stale: Vec<String> = self.slot(kind)
+                .iter()
+                .filter(|(_, entry)| now.saturating_sub(entry.timestamp) > max_age_seconds)
+                .map(|(key, _)| key.clone())
+                .collect();
+            for key in stale {
+                self.slot(kind).pop(&key);
+                self.unstore(kind, &key);
+                removed += 1;
+            }
+        }
+        self.expired += removed as u64;
+        removed
+    }
+
+    /// Очистить все разделы (и копию на диске); возвращает число удалённых записей
+    pub fn clear(&mut self) -> usize {
+        let removed = self.len();
+        for kind in CacheKind::ALL {
+            self.slot(kind).clear();
+        }
+        if let Some(tree) = &self.storage {
+            let model = self.model_tag();
+            if let Err(e) = tree.clear().and_then(|_| tree.insert(MODEL_KEY, model.as_bytes()).map(|_| ())) {
+                println!("⚠️ Не удалось очистить кеш на диске: {}", e);
+            }
+        }
+        removed
+    }
+
+    /// Число записей во всех разделах
+    pub fn len(&self) -> usize {
+        self.embeddings.len() + self.entity_embeddings.len()
+            + self.action_embeddings.len() + self.property_embeddings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Получить кешированный эмбеддинг или вычислить новый
+    pub fn get_cached_embedding(text: &str) -> Vec<f32> {
+        let mut cache = EMBEDDING_CACHE.lock().unwrap();
+        cache.get_or_compute(CacheKind::Text, text.to_string(), text, 1.0, now_seconds())
+    }
+
+    /// Вычислить эмбеддинг для текста базовой хэш-моделью (64 измерения)
+    pub fn calculate_embedding(text: &str) -> Vec<f32> {
+        HashEmbedder::new(64).embed(text)
+    }
+
     /// Получить эмбеддинг для сущности
     pub fn get_entity_embedding(entity: &Entity) -> Vec<f32> {
         let mut cache = EMBEDDING_CACHE.lock().unwrap();
-        let key = format!("{}:{}", entity.text, format!("{:?}", entity.entity_type));
-        cache.entity_embeddings.entry(key).or_insert_with(|| {
-            Self::calculate_embedding(&entity.text)
-        }).clone()
+        let key = format!("{}:{:?}", entity.text, entity.entity_type);
+        cache.get_or_compute(CacheKind::Entity, key, &entity.text, entity.confidence, now_seconds())
     }
 
     /// Получить эмбеддинг для действия
     pub fn get_action_embedding(action: &Action) -> Vec<f32> {
         let mut cache = EMBEDDING_CACHE.lock().unwrap();
         let key = format!("{}:{}:{}", action.subject, action.verb, action.object);
-        cache.action_embeddings.entry(key).or_insert_with(|| {
-            let combined = format!("{} {} {}", action.subject, action.verb, action.object);
-            Self::calculate_embedding(&combined)
-        }).clone()
+        let combined = format!("{} {} {}", action.subject, action.verb, action.object);
+        cache.get_or_compute(CacheKind::Action, key, &combined, action.confidence, now_seconds())
     }
 
     /// Получить эмбеддинг для свойства
     pub fn get_property_embedding(property: &Property) -> Vec<f32> {
         let mut cache = EMBEDDING_CACHE.lock().unwrap();
         let key = format!("{}:{}:{}", property.entity, property.attribute, property.value);
-        cache.property_embeddings.entry(key).or_insert_with(|| {
-            let combined = format!("{} {} {}", property.entity, property.attribute, property.value);
-            Self::calculate_embedding(&combined)
-        }).clone()
+        let combined = format!("{} {} {}", property.entity, property.attribute, property.value);
+        cache.get_or_compute(CacheKind::Property, key, &combined, property.confidence, now_seconds())
     }
 
     /// Косинусное сходство между двумя векторами
@@ -136,40 +385,56 @@ impl EmbeddingCache {
         similarities
     }
 
-    /// Очистить старые эмбеддинги (управление памятью)
-    pub fn cleanup_old_embeddings(_max_age_seconds: u64) {
+    /// Очистить старые эмбеддинги (управление памятью); возвращает число удалённых
+    pub fn cleanup_old_embeddings(max_age_seconds: u64) -> usize {
         let mut cache = EMBEDDING_CACHE.lock().unwrap();
-        let _current_time = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        
-        // Удаляем старые эмбеддинги
-        cache.embeddings.retain(|_, _| true); // Пока оставляем все
-        cache.entity_embeddings.retain(|_, _| true);
-        cache.action_embeddings.retain(|_, _| true);
-        cache.property_embeddings.retain(|_, _| true);
+        cache.evict_older_than(max_age_seconds, now_seconds())
     }
 
-    /// Получить статистику кеша
-    pub fn get_cache_stats() -> CacheStats {
-        let cache = EMBEDDING_CACHE.lock().unwrap();
+    /// Статистика этого экземпляра кеша
+    pub fn stats(&self) -> CacheStats {
+        let lookups = self.hits + self.misses;
         CacheStats {
-            total_embeddings: cache.embeddings.len(),
-            entity_embeddings: cache.entity_embeddings.len(),
-            action_embeddings: cache.action_embeddings.len(),
-            property_embeddings: cache.property_embeddings.len(),
+            embedder: self.embedder.name().to_string(),
+            dimension: self.embedder.dimension(),
+            total_embeddings: self.embeddings.len(),
+            entity_embeddings: self.entity_embeddings.len(),
+            action_embeddings: self.action_embeddings.len(),
+            property_embeddings: self.property_embeddings.len(),
+            capacity: self.config.capacity,
+            ttl_seconds: self.config.ttl_seconds,
+            persistent: self.storage.is_some(),
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+            expired: self.expired,
+            hit_rate: if lookups == 0 { 0.0 } else { self.hits as f32 / lookups as f32 },
         }
     }
+
+    /// Получить статистику кеша
+    pub fn get_cache_stats() -> CacheStats {
+        EMBEDDING_CACHE.lock().unwrap().stats()
+    }
 }
 
 /// Статистика кеша эмбеддингов
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CacheStats {
+    pub embedder: String,
+    pub dimension: usize,
     pub total_embeddings: usize,
     pub entity_embeddings: usize,
     pub action_embeddings: usize,
     pub property_embeddings: usize,
+    pub capacity: usize,          // Предел записей в каждом разделе
+    pub ttl_seconds: Option<u64>,
+    pub persistent: bool,         // Подключена ли копия на диске
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,           // Вытеснено по LRU
+    pub expired: u64,             // Удалено по возрасту
+    pub hit_rate: f32,
 }
 
 /// Параллельная обработка текстов с кешированием
@@ -252,4 +517,61 @@ mod tests {
         assert!(stats.action_embeddings >= 0);
         assert!(stats.property_embeddings >= 0);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_lru_eviction_and_counters() {
+        let config = CacheConfig { capacity: 2, ttl_seconds: None };
+        let mut cache = EmbeddingCache::with_config(Arc::new(HashEmbedder::new(8)), config);
+        for text in ["кот", "рыба", "кот", "море"] {
+            cache.get_or_compute(CacheKind::Text, text.to_string(), text, 1.0, 100);
+        }
+        // "рыба" дольше всех не использовалась и вытеснена
+        assert!(cache.embeddings.contains("кот"));
+        assert!(!cache.embeddings.contains("рыба"));
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.evictions), (1, 3, 1));
+        assert_eq!(cache.clear(), 2);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_ttl_expiry() {
+        let config = CacheConfig { capacity: 10, ttl_seconds: Some(60) };
+        let mut cache = EmbeddingCache::with_config(Arc::new(HashEmbedder::new(8)), config);
+        cache.get_or_compute(CacheKind::Text, "кот".into(), "кот", 1.0, 1000);
+        cache.get_or_compute(CacheKind::Text, "рыба".into(), "рыба", 1.0, 1050);
+
+        // Через 61 секунду "кот" просрочен и вычисляется заново
+        cache.get_or_compute(CacheKind::Text, "кот".into(), "кот", 1.0, 1061);
+        assert_eq!(cache.stats().expired, 1);
+        assert_eq!(cache.stats().misses, 3);
+
+        assert_eq!(cache.evict_older_than(30, 1090), 1);
+        assert!(!cache.embeddings.contains("рыба"));
+        assert!(cache.embeddings.contains("кот"));
+    }
+
+    #[test]
+    fn test_persistent_cache_survives_restart() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let tree = db.open_tree("embedding_cache").unwrap();
+        let embedder: Arc<dyn Embedder> = Arc::new(HashEmbedder::new(8));
+        let now = now_seconds();
+
+        let mut cache = EmbeddingCache::with_embedder(embedder.clone());
+        assert_eq!(cache.attach_storage(tree.clone()).unwrap(), 0);
+        let original = cache.get_or_compute(CacheKind::Entity, "кот:Unknown".into(), "кот", 0.9, now);
+
+        let mut restarted = EmbeddingCache::with_embedder(embedder);
+        assert_eq!(restarted.attach_storage(tree.clone()).unwrap(), 1);
+        let restored = restarted.get_or_compute(CacheKind::Entity, "кот:Unknown".into(), "кот", 0.9, now);
+        assert_eq!(restored, original);
+        assert_eq!(restarted.stats().hits, 1);
+
+        // Векторы другой модели несравнимы: кеш на диске сбрасывается
+        let mut other = EmbeddingCache::with_embedder(Arc::new(HashEmbedder::new(16)));
+        assert_eq!(other.attach_storage(tree.clone()).unwrap(), 0);
+        assert_eq!(tree.len(), 1);
+    }
+}
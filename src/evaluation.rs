@@ -0,0 +1,301 @@
+// evaluation.rs
+// Оценка качества извлечения по размеченному корпусу для "Мыслящего Ядро"
+//
+// Корпус — JSONL: по документу на строку с эталонными сущностями, действиями и свойствами.
+// Документы прогоняются через TextProcessor, результат сравнивается с эталоном:
+// точность/полнота/F1 по типам сущностей, действиям и свойствам и матрица путаницы типов
+// (сущность найдена, но с другим типом). Повторы внутри документа не учитываются.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use chrono::{NaiveDate, Utc};
+use serde::{Serialize, Deserialize};
+use crate::core::Polarity;
+use crate::text_processor::{EntityType, ProcessingResult, TextProcessor};
+
+/// Обозначение отсутствующей пары в матрице путаницы
+pub const NONE: &str = "—";
+
+/// Эталонная сущность
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoldEntity {
+    pub text: String,
+    #[serde(rename = "type")]
+    pub entity_type: EntityType,
+}
+
+/// Эталонный факт: действие (субъект-глагол-объект) или свойство (сущность-атрибут-значение)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoldTriple {
+    #[serde(alias = "entity")]
+    pub subject: String,
+    #[serde(alias = "attribute")]
+    pub verb: String,
+    #[serde(alias = "value")]
+    pub object: String,
+    #[serde(default)]
+    pub polarity: Polarity,
+}
+
+/// Размеченный документ корпуса
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoldDocument {
+    pub text: String,
+    #[serde(default)]
+    pub document_date: Option<NaiveDate>, // Для относительных дат ("вчера")
+    #[serde(default)]
+    pub entities: Vec<GoldEntity>,
+    #[serde(default)]
+    pub actions: Vec<GoldTriple>,
+    #[serde(default)]
+    pub properties: Vec<GoldTriple>,
+}
+
+/// Ошибка загрузки корпуса
+#[derive(Debug)]
+pub enum EvaluationError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for EvaluationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvaluationError::Io(e) => write!(f, "Ошибка чтения корпуса: {}", e),
+            EvaluationError::Parse { line, message } => write!(f, "Строка {}: {}", line, message),
+        }
+    }
+}
+
+impl Error for EvaluationError {}
+
+impl From<io::Error> for EvaluationError {
+    fn from(e: io::Error) -> Self {
+        EvaluationError::Io(e)
+    }
+}
+
+/// Разбор корпуса JSONL (пустые строки и строки с # пропускаются)
+pub fn parse_corpus(content: &str) -> Result<Vec<GoldDocument>, EvaluationError> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(i, line)| {
+            serde_json::from_str(line).map_err(|e| EvaluationError::Parse { line: i + 1, message: e.to_string() })
+        })
+        .collect()
+}
+
+/// Загрузка корпуса из файла
+pub fn load_corpus<P: AsRef<Path>>(path: P) -> Result<Vec<GoldDocument>, EvaluationError> {
+    parse_corpus(&fs::read_to_string(path)?)
+}
+
+/// Счётчики совпадений
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Scores {
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub false_negatives: usize,
+}
+
+impl Scores {
+    pub fn precision(&self) -> f64 {
+        ratio(self.true_positives, self.true_positives + self.false_positives)
+    }
+
+    pub fn recall(&self) -> f64 {
+        ratio(self.true_positives, self.true_positives + self.false_negatives)
+    }
+
+    pub fn f1(&self) -> f64 {
+        let (p, r) = (self.precision(), self.recall());
+        if p + r == 0.0 { 0.0 } else { 2.0 * p * r / (p + r) }
+    }
+
+    fn add(&mut self, other: &Scores) {
+        self.true_positives += other.true_positives;
+        self.false_positives += other.false_positives;
+        self.false_negatives += other.false_negatives;
+    }
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 { 0.0 } else { numerator as f64 / denominator as f64 }
+}
+
+/// Итог оценки по корпусу
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EvaluationReport {
+    pub documents: usize,
+    pub entities: BTreeMap<String, Scores>, // По типам сущностей
+    pub actions: Scores,
+    pub properties: Scores,
+    pub confusion: BTreeMap<String, BTreeMap<String, usize>>, // Эталонный тип → найденный тип → число
+    pub missed: Vec<String>,   // Не найдено: "#3 action: кот ест рыба"
+    pub spurious: Vec<String>, // Найдено лишнее
+}
+
+impl EvaluationReport {
+    /// Сущности всех типов вместе (микроусреднение)
+    pub fn entities_total(&self) -> Scores {
+        let mut total = Scores::default();
+        self.entities.values().for_each(|s| total.add(s));
+        total
+    }
+
+    /// Все сущности, действия и свойства вместе
+    pub fn total(&self) -> Scores {
+        let mut total = self.entities_total();
+        total.add(&self.actions);
+        total.add(&self.properties);
+        total
+    }
+
+    fn confuse(&mut self, gold: &str, predicted: &str) {
+        *self.confusion.entry(gold.to_string()).or_default().entry(predicted.to_string()).or_default() += 1;
+    }
+
+    /// Добавить сравнение одного документа (номер — для списков ошибок)
+    pub fn add_document(&mut self, id: usize, gold: &GoldDocument, result: &ProcessingResult) {
+        self.documents += 1;
+
+        // Сущности: совпадение текста и типа; совпадение только текста — путаница типов
+        let type_name = |t: &EntityType| format!("{:?}", t);
+        let gold_entities: BTreeSet<(String, String)> =
+            gold.entities.iter().map(|e| (e.text.to_lowercase(), type_name(&e.entity_type))).collect();
+        let mut predicted: BTreeSet<(String, String)> =
+            result.entities.iter().map(|e| (e.text.to_lowercase(), type_name(&e.entity_type))).collect();
+        let mut unmatched = Vec::new();
+        for (text, kind) in gold_entities {
+            if predicted.remove(&(text.clone(), kind.clone())) {
+                self.entities.entry(kind.clone()).or_default().true_positives += 1;
+                self.confuse(&kind, &kind);
+            } else {
+                unmatched.push((text, kind));
+            }
+        }
+        for (text, kind) in unmatched {
+            self.entities.entry(kind.clone()).or_default().false_negatives += 1;
+            match predicted.iter().find(|(t, _)| *t == text).cloned() {
+                Some(found) => {
+                    predicted.remove(&found);
+                    self.entities.entry(found.1.clone()).or_default().false_positives += 1;
+                    self.confuse(&kind, &found.1);
+                    self.missed.push(format!("#{} entity: {} ({}, найдено как {})", id, text, kind, found.1));
+                },
+                None => {
+                    self.confuse(&kind, NONE);
+                    self.missed.push(format!("#{} entity: {} ({})", id, text, kind));
+                },
+            }
+        }
+        for (text, kind) in predicted {
+            self.entities.entry(kind.clone()).or_default().false_positives += 1;
+            self.confuse(NONE, &kind);
+            self.spurious.push(format!("#{} entity: {} ({})", id, text, kind));
+        }
+
+        // Факты: совпадение всей тройки с полярностью
+        let actions = result.actions.iter().map(|a| triple(&a.subject, &a.verb, &a.object, a.polarity));
+        let scores = self.compare_triples(id, "action", &gold.actions, actions);
+        self.actions.add(&scores);
+        let properties = result.properties.iter().map(|p| triple(&p.entity, &p.attribute, &p.value, p.polarity));
+        let scores = self.compare_triples(id, "property", &gold.properties, properties);
+        self.properties.add(&scores);
+    }
+
+    fn compare_triples(&mut self, id: usize, kind: &str, gold: &[GoldTriple], predicted: impl Iterator<Item = String>) -> Scores {
+        let gold: BTreeSet<String> = gold.iter().map(|t| triple(&t.subject, &t.verb, &t.object, t.polarity)).collect();
+        let predicted: BTreeSet<String> = predicted.collect();
+        for missed in gold.difference(&predicted) {
+            self.missed.push(format!("#{} {}: {}", id, kind, missed));
+        }
+        for spurious in predicted.difference(&gold) {
+            self.spurious.push(format!("#{} {}: {}", id, kind, spurious));
+        }
+        let true_positives = gold.intersection(&predicted).count();
+        Scores {
+            true_positives,
+            false_positives: predicted.len() - true_positives,
+            false_negatives: gold.len() - true_positives,
+        }
+    }
+}
+
+fn triple(subject: &str, verb: &str, object: &str, polarity: Polarity) -> String {
+    let negation = if polarity == Polarity::Negative { "не " } else { "" };
+    format!("{} {}{} {}", subject.to_lowercase(), negation, verb.to_lowercase(), object.to_lowercase())
+}
+
+impl fmt::Display for EvaluationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let row = |f: &mut fmt::Formatter<'_>, name: &str, s: &Scores| {
+            writeln!(
+                f,
+                "{:<16} {:>5} {:>5} {:>5}   P={:.3}  R={:.3}  F1={:.3}",
+                name, s.true_positives, s.false_positives, s.false_negatives, s.precision(), s.recall(), s.f1()
+            )
+        };
+        writeln!(f, "Документов: {}", self.documents)?;
+        writeln!(f, "{:<16} {:>5} {:>5} {:>5}", "", "TP", "FP", "FN")?;
+        for (kind, scores) in &self.entities {
+            row(f, kind, scores)?;
+        }
+        row(f, "Сущности", &self.entities_total())?;
+        row(f, "Действия", &self.actions)?;
+        row(f, "Свойства", &self.properties)?;
+        row(f, "Всего", &self.total())?;
+
+        writeln!(f, "\nПутаница типов (эталон → найдено):")?;
+        for (gold, row) in &self.confusion {
+            for (predicted, count) in row.iter().filter(|(predicted, _)| *predicted != gold) {
+                writeln!(f, "  {} → {}: {}", gold, predicted, count)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Прогнать корпус через обработчик и сравнить с эталоном
+/// (документы без даты считаются написанными сегодня)
+pub async fn evaluate(processor: &TextProcessor, corpus: &[GoldDocument]) -> EvaluationReport {
+    let mut report = EvaluationReport::default();
+    for (id, document) in corpus.iter().enumerate() {
+        let date = document.document_date.unwrap_or_else(|| Utc::now().date_naive());
+        let result = processor.process_text_at(&document.text, date).await;
+        report.add_document(id + 1, document, &result);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scores_and_confusion() {
+        let corpus = parse_corpus(concat!(
+            "# кот — животное, москва — место; \"рыбу\" размечена неверно как растение\n",
+            r#"{"text": "Кот ест рыбу в Москве.", "entities": [{"text": "кот", "type": "Animal"}, {"text": "рыба", "type": "Plant"}, {"text": "москва", "type": "Location"}], "actions": [{"subject": "кот", "verb": "ест", "object": "рыба"}, {"subject": "кот", "verb": "пьет", "object": "вода"}]}"#,
+        ))
+        .unwrap();
+        let report = evaluate(&TextProcessor::new(), &corpus).await;
+
+        assert_eq!(report.documents, 1);
+        assert_eq!(report.entities["Animal"], Scores { true_positives: 1, false_positives: 1, false_negatives: 0 });
+        assert_eq!(report.entities["Plant"].false_negatives, 1);
+        assert_eq!(report.confusion["Plant"]["Animal"], 1);
+        assert_eq!(report.actions, Scores { true_positives: 1, false_positives: 0, false_negatives: 1 });
+        assert_eq!(report.actions.precision(), 1.0);
+        assert_eq!(report.actions.recall(), 0.5);
+        assert!((report.actions.f1() - 2.0 / 3.0).abs() < 1e-9);
+        assert!(report.missed.iter().any(|m| m == "#1 action: кот пьет вода"));
+        assert!(parse_corpus("{\"text\": 1}").is_err());
+    }
+}
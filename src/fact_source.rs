@@ -0,0 +1,265 @@
+// fact_source.rs
+// Подключаемые источники проверки фактов для "Мыслящего Ядро"
+//
+// FactSource — источник, который ищет подтверждение утверждению (Wikipedia, arXiv, PubMed,
+// внутренняя вики, локальное хранилище документов). Источники регистрируются в SourceRegistry,
+// которым пользуется TrustedScraper; добавление своего источника не требует правки скрапера.
+//
+// Реестр опрашивает источники параллельно, каждый — не дольше общего таймаута; источник,
+// который раз за разом не отвечает, временно отключается предохранителем (resilience).
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use chrono::NaiveDate;
+use serde::{Serialize, Deserialize};
+use tokio::task::JoinSet;
+use crate::core::Source;
+use crate::resilience::{BreakerConfig, BreakerPermit, CircuitBreaker, CircuitState};
+use crate::verification::Stance;
+
+/// Future поиска, пригодный для `dyn FactSource`
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Источник проверки фактов
+pub trait FactSource: Send + Sync {
+    /// Уникальное имя источника ("wikipedia", "company-wiki")
+    fn name(&self) -> &str;
+
+    /// Происхождение подтверждённых фактов в графе знаний
+    fn source(&self) -> Source {
+        Source::Internal
+    }
+
+    /// Надёжность источника (0..1): вес его подтверждения
+    fn weight(&self) -> f32 {
+        1.0
+    }
+
+    /// Поиск подтверждения утверждению
+    fn search<'a>(&'a self, claim: &'a str) -> BoxFuture<'a, Evidence>;
+}
+
+/// Документ, найденный источником
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceDocument {
+    pub title: String,
+    pub url: Option<String>,
+    pub snippet: String, // Фрагмент, в котором найдено утверждение
+    #[serde(default)]
+    pub published: Option<NaiveDate>,
+    #[serde(default)]
+    pub score: f32,      // Лексическое перекрытие с утверждением (0..1)
+    #[serde(default)]
+    pub stance: Stance,
+}
+
+impl EvidenceDocument {
+    /// Документ без оценки: перекрытие и позицию выставляет `VerificationReport::aggregate`
+    pub fn new(title: impl Into<String>, url: Option<String>, snippet: impl Into<String>) -> Self {
+        Self { title: title.into(), url, snippet: snippet.into(), published: None, score: 0.0, stance: Stance::Neutral }
+    }
+}
+
+/// Как прошёл опрос источника
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SourceStatus {
+    pub circuit: CircuitState, // Состояние предохранителя после опроса
+    pub requests: u32,         // HTTP-запросов (с повторами)
+    pub retries: u32,
+    pub throttled_ms: u64,     // Ожидание ограничителя частоты
+    pub elapsed_ms: u64,
+    pub timed_out: bool,
+}
+
+/// Ответ источника на утверждение
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Evidence {
+    pub source_name: String,
+    pub source: Source,
+    pub weight: f32,
+    pub found: bool,
+    pub hits: usize,           // Число найденных документов
+    #[serde(default)]
+    pub documents: Vec<EvidenceDocument>, // Документы с фрагментами (если источник их отдаёт)
+    pub error: Option<String>, // Источник недоступен или ответил не по формату
+    #[serde(default)]
+    pub status: SourceStatus,
+}
+
+impl Evidence {
+    /// Результат поиска: `hits` найденных документов
+    pub fn hits(source: &dyn FactSource, hits: usize) -> Self {
+        Self {
+            source_name: source.name().to_string(),
+            source: source.source(),
+            weight: source.weight(),
+            found: hits > 0,
+            hits,
+            documents: Vec::new(),
+            error: None,
+            status: SourceStatus::default(),
+        }
+    }
+
+    /// Результат поиска с найденными документами
+    pub fn documents(source: &dyn FactSource, documents: Vec<EvidenceDocument>) -> Self {
+        let hits = documents.len();
+        Self { documents, ..Self::hits(source, hits) }
+    }
+
+    /// Источник не смог ответить
+    pub fn failed(source: &dyn FactSource, error: impl fmt::Display) -> Self {
+        Self { error: Some(error.to_string()), ..Self::hits(source, 0) }
+    }
+
+    /// Ответ вместе с числом запросов и повторов источника
+    pub fn with_status(mut self, status: SourceStatus) -> Self {
+        self.status = status;
+        self
+    }
+}
+
+/// Зарегистрированный источник и его предохранитель
+#[derive(Clone)]
+struct Registered {
+    source: Arc<dyn FactSource>,
+    breaker: Arc<Mutex<CircuitBreaker>>,
+}
+
+/// Упорядоченный набор источников (ответы возвращаются в порядке регистрации).
+/// Клоны реестра разделяют предохранители источников
+#[derive(Clone)]
+pub struct SourceRegistry {
+    sources: Vec<Registered>,
+    timeout: Duration, // Сколько ждать ответа источника (вместе с повторами)
+    breaker: BreakerConfig,
+}
+
+impl Default for SourceRegistry {
+    fn default() -> Self {
+        Self { sources: Vec::new(), timeout: Duration::from_secs(30), breaker: BreakerConfig::default() }
+    }
+}
+
+impl fmt::Debug for SourceRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+/// Опрос источника через предохранитель и с ограничением по времени
+async fn guarded_search(entry: Registered, claim: String, timeout: Duration) -> Evidence {
+    let source = entry.source.as_ref();
+    let Some(permit) = BreakerPermit::acquire(&entry.breaker) else {
+        let status = SourceStatus { circuit: CircuitState::Open, ..SourceStatus::default() };
+        return Evidence::failed(source, "источник временно отключён после серии ошибок").with_status(status);
+    };
+
+    let started = Instant::now();
+    let mut evidence = match tokio::time::timeout(timeout, source.search(&claim)).await {
+        Ok(evidence) => evidence,
+        Err(_) => {
+            let status = SourceStatus { timed_out: true, ..SourceStatus::default() };
+            Evidence::failed(source, format!("нет ответа за {:.1} с", timeout.as_secs_f32())).with_status(status)
+        },
+    };
+    evidence.status.elapsed_ms = started.elapsed().as_millis() as u64;
+
+    evidence.status.circuit = permit.record(evidence.error.is_none());
+    evidence
+}
+
+impl SourceRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Таймаут опроса одного источника
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Параметры предохранителей (для источников, зарегистрированных после вызова)
+    pub fn with_breaker(mut self, breaker: BreakerConfig) -> Self {
+        self.breaker = breaker;
+        self
+    }
+
+    /// Добавить источник; источник с тем же именем заменяется (на прежнем месте)
+    pub fn register(&mut self, source: Arc<dyn FactSource>) {
+        let entry = Registered { source, breaker: Arc::new(Mutex::new(CircuitBreaker::new(self.breaker))) };
+        match self.sources.iter().position(|s| s.source.name() == entry.source.name()) {
+            Some(i) => self.sources[i] = entry,
+            None => self.sources.push(entry),
+        }
+    }
+
+    /// Убрать источник по имени
+    pub fn unregister(&mut self, name: &str) -> Option<Arc<dyn FactSource>> {
+        let i = self.sources.iter().position(|s| s.source.name() == name)?;
+        Some(self.sources.remove(i).source)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn FactSource>> {
+        self.iter().find(|s| s.name() == name)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.iter().map(|s| s.name()).collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn FactSource>> {
+        self.sources.iter().map(|s| &s.source)
+    }
+
+    /// Состояние предохранителя источника
+    pub fn circuit(&self, name: &str) -> Option<CircuitState> {
+        let entry = self.sources.iter().find(|s| s.source.name() == name)?;
+        Some(entry.breaker.lock().unwrap().state())
+    }
+
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// Опросить все источники параллельно
+    pub async fn search(&self, claim: &str) -> Vec<Evidence> {
+        let mut tasks = JoinSet::new();
+        for (i, entry) in self.sources.iter().enumerate() {
+            let (entry, claim, timeout) = (entry.clone(), claim.to_string(), self.timeout);
+            tasks.spawn(async move { (i, guarded_search(entry, claim, timeout).await) });
+        }
+
+        let mut evidence: Vec<Option<Evidence>> = vec![None; self.sources.len()];
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((i, e)) => evidence[i] = Some(e),
+                Err(e) => println!("⚠️ Опрос источника завершился аварийно: {}", e),
+            }
+        }
+        evidence
+            .into_iter()
+            .zip(&self.sources)
+            .map(|(e, entry)| {
+                // Неудача аварийного опроса уже учтена при сбросе разрешения
+                e.unwrap_or_else(|| Evidence::failed(entry.source.as_ref(), "опрос завершился аварийно"))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,377 @@
+// feeds.rs
+// Запросы к научным источникам и разбор их ответов для "Мыслящего Ядро"
+//
+// Утверждение сводится к ключевым словам (служебные слова и отрицания убираются:
+// "кот не ест рыбу" ищется как "кот ест рыбу", позицию решает оценка доказательств).
+// Ответы разбираются как XML, а не поиском подстрок:
+// - arXiv — лента Atom: <entry> с <id>, <title>, <summary>, <published>
+// - PubMed eSearch — <IdList><Id>; eSummary — <DocSum> с Item Title/PubDate;
+//   eFetch — <PubmedArticle> с <AbstractText> (аннотации)
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use chrono::NaiveDate;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::reader::Reader;
+use serde::{Serialize, Deserialize};
+use crate::fact_source::EvidenceDocument;
+
+/// Сколько ключевых слов оставлять в запросе
+pub const MAX_KEYWORDS: usize = 6;
+
+/// Служебные слова, не несущие смысла для поиска (включая отрицания и связки)
+const RUSSIAN_STOPWORDS: &[&str] = &[
+    "и", "в", "во", "не", "ни", "нет", "на", "что", "с", "со", "по", "это", "как", "из", "к", "ко", "у",
+    "для", "является", "являются", "был", "была", "были", "было", "есть", "или", "а", "но", "же", "ли",
+    "от", "до", "о", "об", "при", "за", "под", "над", "без", "также", "все", "всё", "его", "её", "их",
+];
+const ENGLISH_STOPWORDS: &[&str] = &[
+    "the", "and", "of", "to", "in", "is", "a", "an", "that", "for", "with", "as", "are", "by", "not",
+    "no", "was", "were", "be", "been", "it", "its", "on", "at", "or", "from", "this", "these", "does",
+    "do", "did", "has", "have", "had", "can", "may", "there",
+];
+
+/// Служебное слово (в нижнем регистре) любого из поддерживаемых языков
+pub(crate) fn is_stopword(word: &str) -> bool {
+    RUSSIAN_STOPWORDS.contains(&word) || ENGLISH_STOPWORDS.contains(&word)
+}
+
+/// Ключевые слова утверждения в исходной форме (без повторов, не более MAX_KEYWORDS).
+/// Короткие слова пропускаются, кроме чисел и обозначений с цифрами ("p53")
+pub fn keywords(claim: &str) -> Vec<String> {
+    let mut keywords: Vec<String> = Vec::new();
+    for word in claim.split(|c: char| !c.is_alphanumeric() && c != '-').map(|w| w.trim_matches('-')) {
+        let lower = word.to_lowercase();
+        let meaningful = word.chars().any(|c| c.is_numeric()) || word.chars().count() > 2;
+        if word.is_empty() || !meaningful || is_stopword(&lower) {
+            continue;
+        }
+        if !keywords.contains(&lower) {
+            keywords.push(lower);
+        }
+        if keywords.len() == MAX_KEYWORDS {
+            break;
+        }
+    }
+    keywords
+}
+
+/// Ошибка разбора ответа источника
+#[derive(Debug)]
+pub enum FeedError {
+    Xml(String),
+    Service(String), // Источник вернул сообщение об ошибке вместо результатов
+}
+
+impl fmt::Display for FeedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeedError::Xml(e) => write!(f, "Ошибка разбора XML: {}", e),
+            FeedError::Service(e) => write!(f, "Источник вернул ошибку: {}", e),
+        }
+    }
+}
+
+impl Error for FeedError {}
+
+impl From<quick_xml::Error> for FeedError {
+    fn from(e: quick_xml::Error) -> Self {
+        FeedError::Xml(e.to_string())
+    }
+}
+
+/// Публикация из научного источника
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Publication {
+    pub id: String,               // arXiv: "2101.00001v1", PubMed: PMID
+    pub title: String,
+    pub summary: String,          // Аннотация (может быть пустой)
+    pub published: Option<NaiveDate>,
+    pub url: Option<String>,
+}
+
+impl From<Publication> for EvidenceDocument {
+    fn from(p: Publication) -> Self {
+        let mut document = EvidenceDocument::new(p.title, p.url, p.summary);
+        document.published = p.published;
+        document
+    }
+}
+
+/// Пробелы и переводы строк внутри полей схлопываются
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn attribute(e: &BytesStart, name: &str) -> Option<String> {
+    let value = e.try_get_attribute(name).ok()??;
+    value.unescape_value().ok().map(|v| v.into_owned())
+}
+
+/// Текстовое содержимое (включая CDATA) в поле, если оно собирается
+fn push_text(event: &Event, into: Option<&mut String>) -> Result<(), FeedError> {
+    match (event, into) {
+        (Event::Text(e), Some(text)) => text.push_str(&e.unescape().map_err(|e| FeedError::Xml(e.to_string()))?),
+        (Event::CData(e), Some(text)) => text.push_str(&String::from_utf8_lossy(e)),
+        _ => {},
+    }
+    Ok(())
+}
+
+/// Дата PubMed: "2019 Mar 5", "2019 Mar", "2019 Mar-Apr", "2019"
+pub fn parse_pubmed_date(text: &str) -> Option<NaiveDate> {
+    const MONTHS: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+    let mut parts = text.split_whitespace();
+    let year = parts.next()?.parse().ok()?;
+    let month = parts
+        .next()
+        .and_then(|m| MONTHS.iter().position(|name| m.to_lowercase().starts_with(name)))
+        .map_or(1, |i| i as u32 + 1);
+    let day = parts.next().and_then(|d| d.parse().ok()).unwrap_or(1);
+    NaiveDate::from_ymd_opt(year, month, day).or_else(|| NaiveDate::from_ymd_opt(year, month, 1))
+}
+
+/// Лента Atom от arXiv
+pub fn parse_arxiv(xml: &str) -> Result<Vec<Publication>, FeedError> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Field { Id, Title, Summary, Published }
+
+    let mut reader = Reader::from_str(xml);
+    let mut publications = Vec::new();
+    let mut entry: Option<Publication> = None;
+    let mut field: Option<Field> = None;
+    let mut text = String::new();
+
+    loop {
+        let event = reader.read_event()?;
+        match &event {
+            Event::Start(e) if entry.is_some() => {
+                field = match e.local_name().as_ref() {
+                    b"id" => Some(Field::Id),
+                    b"title" => Some(Field::Title),
+                    b"summary" => Some(Field::Summary),
+                    b"published" => Some(Field::Published),
+                    _ => field,
+                };
+                text.clear();
+            },
+            Event::Start(e) if e.local_name().as_ref() == b"entry" => entry = Some(Publication::default()),
+            Event::End(e) if e.local_name().as_ref() == b"entry" => {
+                if let Some(publication) = entry.take() {
+                    // Неверный запрос arXiv возвращает запись-ошибку
+                    if publication.id.contains("/api/errors") {
+                        return Err(FeedError::Service(publication.summary));
+                    }
+                    publications.push(publication);
+                }
+            },
+            Event::End(_) => {
+                if let (Some(f), Some(publication)) = (field.take(), entry.as_mut()) {
+                    let value = collapse(&text);
+                    match f {
+                        Field::Id => {
+                            publication.id = value.rsplit("/abs/").next().unwrap_or(&value).to_string();
+                            publication.url = Some(value);
+                        },
+                        Field::Title => publication.title = value,
+                        Field::Summary => publication.summary = value,
+                        Field::Published => publication.published = value.get(..10).and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok()),
+                    }
+                }
+            },
+            Event::Eof => break,
+            _ => push_text(&event, field.map(|_| &mut text))?,
+        }
+    }
+    Ok(publications)
+}
+
+/// Идентификаторы статей из ответа PubMed eSearch
+pub fn parse_esearch(xml: &str) -> Result<Vec<String>, FeedError> {
+    let mut reader = Reader::from_str(xml);
+    let (mut ids, mut error) = (Vec::new(), None);
+    let mut current: Option<String> = None;
+    let mut in_id_list = false;
+
+    loop {
+        let event = reader.read_event()?;
+        match &event {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"IdList" => in_id_list = true,
+                b"Id" if in_id_list => current = Some(String::new()),
+                b"ERROR" => current = Some(String::new()),
+                _ => {},
+            },
+            Event::End(e) => match e.local_name().as_ref() {
+                b"IdList" => in_id_list = false,
+                b"Id" => ids.extend(current.take().map(|id| id.trim().to_string())),
+                b"ERROR" => error = current.take(),
+                _ => {},
+            },
+            Event::Eof => break,
+            _ => push_text(&event, current.as_mut())?,
+        }
+    }
+    match error {
+        Some(message) if ids.is_empty() => Err(FeedError::Service(collapse(&message))),
+        _ => Ok(ids),
+    }
+}
+
+/// Заголовки и даты статей из ответа PubMed eSummary
+pub fn parse_esummary(xml: &str) -> Result<Vec<Publication>, FeedError> {
+    #[derive(Clone, Copy)]
+    enum Field { Id, Title, Date }
+
+    let mut reader = Reader::from_str(xml);
+    let mut publications = Vec::new();
+    let mut summary: Option<Publication> = None;
+    let mut field: Option<Field> = None;
+    let mut text = String::new();
+
+    loop {
+        let event = reader.read_event()?;
+        match &event {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"DocSum" => summary = Some(Publication::default()),
+                b"Id" if summary.is_some() => field = Some(Field::Id),
+                b"Item" if summary.is_some() => {
+                    field = match attribute(e, "Name").as_deref() {
+                        Some("Title") => Some(Field::Title),
+                        Some("PubDate") => Some(Field::Date),
+                        _ => None,
+                    }
+                },
+                _ => {},
+            },
+            Event::End(e) if e.local_name().as_ref() == b"DocSum" => {
+                if let Some(mut publication) = summary.take() {
+                    publication.url = Some(format!("https://pubmed.ncbi.nlm.nih.gov/{}/", publication.id));
+                    publications.push(publication);
+                }
+            },
+            Event::End(_) => {
+                if let (Some(f), Some(publication)) = (field.take(), summary.as_mut()) {
+                    let value = collapse(&text);
+                    match f {
+                        Field::Id => publication.id = value,
+                        Field::Title => publication.title = value,
+                        Field::Date => publication.published = parse_pubmed_date(&value),
+                    }
+                }
+                text.clear();
+            },
+            Event::Eof => break,
+            _ => push_text(&event, field.map(|_| &mut text))?,
+        }
+    }
+    Ok(publications)
+}
+
+/// Аннотации статей (PMID → текст) из ответа PubMed eFetch; разделы аннотации склеиваются
+pub fn parse_efetch_abstracts(xml: &str) -> Result<HashMap<String, String>, FeedError> {
+    let mut reader = Reader::from_str(xml);
+    let mut abstracts = HashMap::new();
+    let (mut pmid, mut abstract_text) = (None::<String>, String::new());
+    let mut reading_pmid = false;
+    let mut section: Option<String> = None;
+
+    loop {
+        let event = reader.read_event()?;
+        match &event {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"PubmedArticle" => {
+                    pmid = None;
+                    abstract_text.clear();
+                },
+                // Первый PMID статьи — её собственный (дальше идут ссылки на другие статьи)
+                b"PMID" if pmid.is_none() => {
+                    reading_pmid = true;
+                    pmid = Some(String::new());
+                },
+                b"AbstractText" => section = Some(String::new()),
+                _ => {},
+            },
+            Event::End(e) => match e.local_name().as_ref() {
+                b"PMID" => reading_pmid = false,
+                b"AbstractText" => {
+                    if let Some(text) = section.take() {
+                        if !abstract_text.is_empty() {
+                            abstract_text.push(' ');
+                        }
+                        abstract_text.push_str(&collapse(&text));
+                    }
+                },
+                b"PubmedArticle" => {
+                    if let Some(id) = pmid.take() {
+                        abstracts.insert(id.trim().to_string(), std::mem::take(&mut abstract_text));
+                    }
+                },
+                _ => {},
+            },
+            Event::Eof => break,
+            _ if reading_pmid => push_text(&event, pmid.as_mut())?,
+            _ => push_text(&event, section.as_mut())?,
+        }
+    }
+    Ok(abstracts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keywords() {
+        assert_eq!(keywords("Кот не ест рыбу и мясо"), vec!["кот", "ест", "рыбу", "мясо"]);
+        assert_eq!(keywords("Is p53 a tumor suppressor? p53 is"), vec!["p53", "tumor", "suppressor"]);
+        assert!(keywords("и в на").is_empty());
+    }
+
+    #[test]
+    fn test_parse_feeds() {
+        let atom = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>arXiv Query: all:p53</title>
+  <id>http://arxiv.org/api/abc</id>
+  <entry>
+    <id>http://arxiv.org/abs/1901.00001v2</id>
+    <published>2019-01-02T10:00:00Z</published>
+    <title>p53 as a tumor
+      suppressor</title>
+    <summary>We show that p53 &amp; MDM2 regulate growth.</summary>
+    <author><name>A. Author</name></author>
+  </entry>
+</feed>"#;
+        let entries = parse_arxiv(atom).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id, "1901.00001v2");
+        assert_eq!(entries[0].title, "p53 as a tumor suppressor");
+        assert_eq!(entries[0].summary, "We show that p53 & MDM2 regulate growth.");
+        assert_eq!(entries[0].published, NaiveDate::from_ymd_opt(2019, 1, 2));
+
+        let search = "<eSearchResult><Count>2</Count><IdList><Id>111</Id><Id>222</Id></IdList></eSearchResult>";
+        assert_eq!(parse_esearch(search).unwrap(), vec!["111", "222"]);
+        assert!(parse_esearch("<eSearchResult><ERROR>Invalid query</ERROR></eSearchResult>").is_err());
+
+        let summary = r#"<eSummaryResult><DocSum><Id>111</Id>
+<Item Name="PubDate" Type="Date">2020 Mar 5</Item>
+<Item Name="AuthorList" Type="List"><Item Name="Author" Type="String">Smith J</Item></Item>
+<Item Name="Title" Type="String">TP53 mutations in cancer.</Item></DocSum></eSummaryResult>"#;
+        let summaries = parse_esummary(summary).unwrap();
+        assert_eq!(summaries[0].title, "TP53 mutations in cancer.");
+        assert_eq!(summaries[0].published, NaiveDate::from_ymd_opt(2020, 3, 5));
+        assert_eq!(summaries[0].url.as_deref(), Some("https://pubmed.ncbi.nlm.nih.gov/111/"));
+
+        let fetch = r#"<PubmedArticleSet><PubmedArticle><MedlineCitation><PMID Version="1">111</PMID>
+<Article><Abstract><AbstractText Label="BACKGROUND">TP53 is <i>frequently</i> mutated.</AbstractText>
+<AbstractText Label="RESULTS">Loss of p53 drives growth.</AbstractText></Abstract></Article>
+<CommentsCorrectionsList><CommentsCorrections><PMID Version="1">999</PMID></CommentsCorrections></CommentsCorrectionsList>
+</MedlineCitation></PubmedArticle></PubmedArticleSet>"#;
+        let abstracts = parse_efetch_abstracts(fetch).unwrap();
+        assert_eq!(abstracts.len(), 1);
+        assert_eq!(abstracts["111"], "TP53 is frequently mutated. Loss of p53 drives growth.");
+        assert_eq!(parse_pubmed_date("2018 Nov-Dec"), NaiveDate::from_ymd_opt(2018, 11, 1));
+    }
+}
@@ -0,0 +1,721 @@
+// graph_query.rs
+// Декларативный язык запросов к графу знаний для "Мыслящего Ядро"
+//
+// Запрос — набор тройных шаблонов `субъект связь объект` и фильтров,
+// разделённых переводом строки, `;` или отдельной точкой:
+//
+//   ?x ест рыба
+//   ?x водится ?где . FILTER confidence >= 0.6
+//   FILTER ?x tag Animal
+//   FILTER value >= 3 кг
+//   FILTER ?когда >= 2020-01-01
+//   FILTER time >= 2020
+//   ORDER BY ?когда DESC
+//   LIMIT 10
+//
+// Термы: `?имя` — переменная, слово или "строка в кавычках" — литерал.
+// Общие переменные в нескольких шаблонах дают многошаговые соединения.
+// Отрицательные рёбра ("кот не ест рыбу") учитываются только с фильтром
+// `FILTER polarity = negative` или `FILTER polarity = any`.
+//
+// Шаблон, в котором и концы, и связь — ещё не связанные переменные (`?a ?p ?b`),
+// перебирает весь граф и отклоняется; число промежуточных решений ограничено
+// MAX_SOLUTIONS, а без ORDER BY соединение останавливается, набрав LIMIT решений.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use chrono::Datelike;
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use serde::Serialize;
+use crate::core::{Modality, Polarity, Source};
+use crate::knowledge_graph::KnowledgeGraph;
+use crate::language;
+use crate::lemmatizer::Lemmatizer;
+use crate::quantity::{self, Quantity};
+use crate::temporal::{self, TimeValue};
+
+/// Терм шаблона: переменная или литерал
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    Var(String),
+    Literal(String),
+}
+
+/// Тройной шаблон: субъект -связь-> объект
+#[derive(Debug, Clone, PartialEq)]
+pub struct TriplePattern {
+    pub subject: Term,
+    pub predicate: Term,
+    pub object: Term,
+}
+
+/// Предел числа решений на любом шаге соединения
+pub const MAX_SOLUTIONS: usize = 10_000;
+
+/// Оператор сравнения в фильтрах
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    fn parse(token: &str) -> Option<Self> {
+        match token {
+            "=" | "==" => Some(CompareOp::Eq),
+            "!=" => Some(CompareOp::Ne),
+            "<" => Some(CompareOp::Lt),
+            "<=" => Some(CompareOp::Le),
+            ">" => Some(CompareOp::Gt),
+            ">=" => Some(CompareOp::Ge),
+            _ => None,
+        }
+    }
+
+    fn compare(self, left: f32, right: f32) -> bool {
+        match self {
+            CompareOp::Eq => left == right,
+            CompareOp::Ne => left != right,
+            CompareOp::Lt => left < right,
+            CompareOp::Le => left <= right,
+            CompareOp::Gt => left > right,
+            CompareOp::Ge => left >= right,
+        }
+    }
+}
+
+/// Фильтр: применяется ко всем рёбрам решения или к узлу-переменной
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Confidence(CompareOp, f32),
+    Source(String),
+    Kind(String),
+    Tag(String, String), // (переменная, тег)
+    Polarity(Option<Polarity>), // None — любые рёбра; без фильтра — только утверждения
+    Value(CompareOp, Quantity), // Величина ребра той же размерности (в каноничных единицах)
+    Date(String, CompareOp, TimeValue), // Узел-дата переменной (сравниваются начала интервалов)
+    Time(CompareOp, TimeValue), // Дата факта на ребре; рёбра без даты не проходят
+}
+
+/// Сортировка решений по значению переменной: даты — хронологически, числа — по величине
+#[derive(Debug, Clone, PartialEq)]
+pub struct Order {
+    pub var: String,
+    pub descending: bool,
+}
+
+/// Разобранный запрос
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub patterns: Vec<TriplePattern>,
+    pub filters: Vec<Filter>,
+    pub order: Option<Order>,
+    pub limit: Option<usize>,
+}
+
+/// Ошибка разбора запроса
+#[derive(Debug, PartialEq)]
+pub enum QueryError {
+    Empty,
+    UnterminatedString,
+    InvalidPattern(String),
+    InvalidFilter(String),
+    InvalidLimit(String),
+    InvalidOrder(String),
+    Unbounded(String),
+    TooManySolutions(usize),
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::Empty => write!(f, "Запрос не содержит шаблонов"),
+            QueryError::UnterminatedString => write!(f, "Незакрытая кавычка в запросе"),
+            QueryError::InvalidPattern(c) => write!(f, "Шаблон должен состоять из трёх термов: {}", c),
+            QueryError::InvalidFilter(c) => write!(f, "Некорректный фильтр: {}", c),
+            QueryError::InvalidLimit(c) => write!(f, "Некорректный LIMIT: {}", c),
+            QueryError::InvalidOrder(c) => write!(f, "Некорректный ORDER BY: {}", c),
+            QueryError::Unbounded(c) => write!(f, "Шаблон перебирает весь граф — задайте связь или один из концов: {}", c),
+            QueryError::TooManySolutions(n) => write!(f, "Запрос даёт больше {} решений — уточните шаблоны или добавьте LIMIT", n),
+        }
+    }
+}
+
+impl Error for QueryError {}
+
+/// Ребро, подтверждающее решение
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchedEdge {
+    pub subject: String,
+    pub label: String,
+    pub object: String,
+    pub confidence: f32,
+    pub source: Source,
+    pub polarity: Polarity,
+    pub modality: Modality,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<TimeValue>,
+}
+
+/// Одно решение запроса: значения переменных и рёбра, на которых оно основано
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryRow {
+    pub bindings: BTreeMap<String, String>,
+    pub edges: Vec<MatchedEdge>,
+}
+
+/// Результат выполнения запроса
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryResult {
+    pub variables: Vec<String>,
+    pub rows: Vec<QueryRow>,
+}
+
+/// Значение переменной во время выполнения
+#[derive(Debug, Clone, PartialEq)]
+enum Bound {
+    Node(NodeIndex),
+    Label(String),
+}
+
+/// Частичное решение
+#[derive(Debug, Clone, Default)]
+struct Solution {
+    vars: HashMap<String, Bound>,
+    edges: Vec<EdgeIndex>,
+}
+
+impl Query {
+    /// Разобрать текст запроса
+    pub fn parse(input: &str) -> Result<Query, QueryError> {
+        let mut query = Query { patterns: Vec::new(), filters: Vec::new(), order: None, limit: None };
+
+        for clause in tokenize(input)? {
+            let first = clause[0].to_uppercase();
+            if first == "FILTER" {
+                query.filters.push(parse_filter(&clause[1..])?);
+            } else if first == "ORDER" {
+                query.order = Some(parse_order(&clause).ok_or_else(|| QueryError::InvalidOrder(clause.join(" ")))?);
+            } else if first == "LIMIT" {
+                let limit = clause.get(1).and_then(|n| n.parse().ok()).filter(|_| clause.len() == 2);
+                query.limit = Some(limit.ok_or_else(|| QueryError::InvalidLimit(clause.join(" ")))?);
+            } else if clause.len() == 3 {
+                let pattern = TriplePattern {
+                    subject: parse_term(&clause[0]),
+                    predicate: parse_term(&clause[1]),
+                    object: parse_term(&clause[2]),
+                };
+                let bound = query.variables();
+                let free = |term: &Term| matches!(term, Term::Var(name) if !bound.contains(name));
+                if free(&pattern.subject) && free(&pattern.predicate) && free(&pattern.object) {
+                    return Err(QueryError::Unbounded(clause.join(" ")));
+                }
+                query.patterns.push(pattern);
+            } else {
+                return Err(QueryError::InvalidPattern(clause.join(" ")));
+            }
+        }
+
+        if query.patterns.is_empty() {
+            return Err(QueryError::Empty);
+        }
+        Ok(query)
+    }
+
+    /// Переменные запроса в порядке первого появления
+    pub fn variables(&self) -> Vec<String> {
+        let mut vars = Vec::new();
+        for p in &self.patterns {
+            for term in [&p.subject, &p.predicate, &p.object] {
+                if let Term::Var(name) = term
+                    && !vars.contains(name)
+                {
+                    vars.push(name.clone());
+                }
+            }
+        }
+        vars
+    }
+
+    /// Выполнить запрос над графом знаний (вложенные циклы по шаблонам).
+    /// Больше MAX_SOLUTIONS решений на одном шаге — ошибка
+    pub fn execute(&self, kg: &KnowledgeGraph) -> Result<QueryResult, QueryError> {
+        // Без сортировки достаточно первых LIMIT решений, прошедших фильтры
+        let wanted = match self.order {
+            None => self.limit.unwrap_or(usize::MAX),
+            Some(_) => usize::MAX,
+        };
+        let mut solutions = vec![Solution::default()];
+        for (step, pattern) in self.patterns.iter().enumerate() {
+            let last = step + 1 == self.patterns.len();
+            let mut next = Vec::new();
+            'join: for solution in &solutions {
+                for extended in self.extend(kg, pattern, solution) {
+                    if last && !self.node_filters_pass(kg, &extended) {
+                        continue;
+                    }
+                    next.push(extended);
+                    if last && next.len() >= wanted {
+                        break 'join;
+                    }
+                    if next.len() > MAX_SOLUTIONS {
+                        return Err(QueryError::TooManySolutions(MAX_SOLUTIONS));
+                    }
+                }
+            }
+            solutions = next;
+            if solutions.is_empty() {
+                break;
+            }
+        }
+
+        let variables = self.variables();
+        let mut rows: Vec<QueryRow> = solutions
+            .into_iter()
+            .filter(|s| self.node_filters_pass(kg, s))
+            .map(|s| to_row(kg, &variables, s))
+            .collect();
+
+        if let Some(order) = &self.order {
+            rows.sort_by(|a, b| {
+                let ordering = compare_values(a.bindings.get(&order.var), b.bindings.get(&order.var));
+                if order.descending { ordering.reverse() } else { ordering }
+            });
+        }
+        rows.truncate(self.limit.unwrap_or(usize::MAX));
+
+        Ok(QueryResult { variables, rows })
+    }
+
+    /// Продолжить частичное решение всеми рёбрами, подходящими под шаблон
+    fn extend(&self, kg: &KnowledgeGraph, pattern: &TriplePattern, solution: &Solution) -> Vec<Solution> {
+        let subject = resolve_node(kg, &pattern.subject, solution);
+        let object = resolve_node(kg, &pattern.object, solution);
+        if subject == Some(None) || object == Some(None) {
+            return Vec::new(); // Литерал отсутствует в графе или переменная связана не с узлом
+        }
+
+        let candidates: Vec<EdgeIndex> = match (subject.flatten(), object.flatten()) {
+            (Some(a), _) => kg.graph.edges_directed(a, Direction::Outgoing).map(|e| e.id()).collect(),
+            (None, Some(b)) => kg.graph.edges_directed(b, Direction::Incoming).map(|e| e.id()).collect(),
+            (None, None) => kg.graph.edge_indices().collect(),
+        };
+
+        let mut extended = Vec::new();
+        for idx in candidates {
+            if solution.edges.contains(&idx) || !self.edge_filters_pass(kg, idx) {
+                continue;
+            }
+            let (a, b) = kg.graph.edge_endpoints(idx).expect("ребро существует");
+            let label = &kg.graph[idx].label;
+
+            let mut next = solution.clone();
+            if bind(&mut next, &pattern.subject, Bound::Node(a), kg)
+                && bind(&mut next, &pattern.predicate, Bound::Label(label.clone()), kg)
+                && bind(&mut next, &pattern.object, Bound::Node(b), kg)
+            {
+                next.edges.push(idx);
+                extended.push(next);
+            }
+        }
+        extended
+    }
+
+    fn edge_filters_pass(&self, kg: &KnowledgeGraph, idx: EdgeIndex) -> bool {
+        let edge = &kg.graph[idx];
+        let polarity_filtered = self.filters.iter().any(|f| matches!(f, Filter::Polarity(_)));
+        if !polarity_filtered && edge.polarity == Polarity::Negative {
+            return false;
+        }
+        self.filters.iter().all(|f| match f {
+            Filter::Confidence(op, value) => op.compare(edge.confidence, *value),
+            Filter::Source(s) => format!("{:?}", edge.source).eq_ignore_ascii_case(s),
+            Filter::Kind(k) => format!("{:?}", edge.kind).eq_ignore_ascii_case(k),
+            Filter::Polarity(p) => p.is_none_or(|p| edge.polarity == p),
+            Filter::Value(op, value) => edge.quantity.as_ref().is_some_and(|q| {
+                q.dimension == value.dimension && op.compare(q.value as f32, value.value as f32)
+            }),
+            Filter::Time(op, value) => edge.time.is_some_and(|time| {
+                op.compare(time.start.num_days_from_ce() as f32, value.start.num_days_from_ce() as f32)
+            }),
+            Filter::Tag(..) | Filter::Date(..) => true,
+        })
+    }
+
+    fn node_filters_pass(&self, kg: &KnowledgeGraph, solution: &Solution) -> bool {
+        self.filters.iter().all(|f| match f {
+            Filter::Tag(var, tag) => match solution.vars.get(var) {
+                Some(Bound::Node(idx)) => kg.tags(*idx).iter().any(|t| t.eq_ignore_ascii_case(tag)),
+                _ => false,
+            },
+            Filter::Date(var, op, value) => match solution.vars.get(var) {
+                Some(Bound::Node(idx)) => TimeValue::parse_iso(&kg.graph[*idx].text())
+                    .is_some_and(|date| op.compare(date.start.num_days_from_ce() as f32, value.start.num_days_from_ce() as f32)),
+                _ => false,
+            },
+            _ => true,
+        })
+    }
+}
+
+/// Узел, на который указывает терм: `None` — терм свободен,
+/// `Some(None)` — терм задан, но такого узла нет
+fn resolve_node(kg: &KnowledgeGraph, term: &Term, solution: &Solution) -> Option<Option<NodeIndex>> {
+    match term {
+        Term::Literal(text) => Some(kg.find_primitive(text)),
+        Term::Var(name) => match solution.vars.get(name) {
+            Some(Bound::Node(idx)) => Some(Some(*idx)),
+            Some(Bound::Label(_)) => Some(None),
+            None => None,
+        },
+    }
+}
+
+/// Связать терм со значением; false — противоречие с уже связанным значением или литералом
+fn bind(solution: &mut Solution, term: &Term, value: Bound, kg: &KnowledgeGraph) -> bool {
+    match term {
+        Term::Literal(text) => match value {
+            Bound::Node(idx) => kg.find_primitive(text) == Some(idx),
+            Bound::Label(label) => same_label(&label, text),
+        },
+        Term::Var(name) => match solution.vars.get(name) {
+            Some(existing) => *existing == value,
+            None => {
+                solution.vars.insert(name.clone(), value);
+                true
+            }
+        },
+    }
+}
+
+/// Метки совпадают с точностью до формы глагола ("едят" и "ест")
+fn same_label(label: &str, text: &str) -> bool {
+    let lemmatizer = Lemmatizer::shared_for(language::detect_language(text));
+    label.to_lowercase() == text.to_lowercase() || lemmatizer.lemmatize_verb(label) == lemmatizer.lemmatize_verb(text)
+}
+
+fn to_row(kg: &KnowledgeGraph, variables: &[String], solution: Solution) -> QueryRow {
+    let bindings = variables
+        .iter()
+        .filter_map(|name| {
+            let value = match solution.vars.get(name)? {
+                Bound::Node(idx) => kg.graph[*idx].text(),
+                Bound::Label(label) => label.clone(),
+            };
+            Some((name.clone(), value))
+        })
+        .collect();
+
+    let edges = solution
+        .edges
+        .iter()
+        .map(|&idx| {
+            let (a, b) = kg.graph.edge_endpoints(idx).expect("ребро существует");
+            let edge = &kg.graph[idx];
+            MatchedEdge {
+                subject: kg.graph[a].text(),
+                label: edge.label.clone(),
+                object: kg.graph[b].text(),
+                confidence: edge.confidence,
+                source: edge.source,
+                polarity: edge.polarity,
+                modality: edge.modality,
+                time: edge.time,
+            }
+        })
+        .collect();
+
+    QueryRow { bindings, edges }
+}
+
+/// ORDER BY ?var [ASC|DESC]
+fn parse_order(clause: &[String]) -> Option<Order> {
+    let descending = match clause {
+        [_, by, _] if by.eq_ignore_ascii_case("BY") => false,
+        [_, by, _, dir] if by.eq_ignore_ascii_case("BY") && dir.eq_ignore_ascii_case("ASC") => false,
+        [_, by, _, dir] if by.eq_ignore_ascii_case("BY") && dir.eq_ignore_ascii_case("DESC") => true,
+        _ => return None,
+    };
+    match parse_term(&clause[2]) {
+        Term::Var(var) => Some(Order { var, descending }),
+        Term::Literal(_) => None,
+    }
+}
+
+/// Порядок значений переменной: даты (ISO 8601) хронологически, затем числа, затем строки;
+/// решения без значения — в конце
+fn compare_values(a: Option<&String>, b: Option<&String>) -> Ordering {
+    let (a, b) = match (a, b) {
+        (Some(a), Some(b)) => (a, b),
+        (a, b) => return a.is_none().cmp(&b.is_none()),
+    };
+    if let (Some(x), Some(y)) = (TimeValue::parse_iso(a), TimeValue::parse_iso(b)) {
+        return x.cmp(&y);
+    }
+    if let (Some(x), Some(y)) = (quantity::parse_quantity(a), quantity::parse_quantity(b))
+        && let Some(ordering) = x.compare(&y)
+    {
+        return ordering;
+    }
+    a.cmp(b)
+}
+
+fn parse_term(token: &str) -> Term {
+    match token.strip_prefix('?') {
+        Some(name) if !name.is_empty() => Term::Var(name.to_string()),
+        _ => Term::Literal(token.to_string()),
+    }
+}
+
+fn parse_filter(tokens: &[String]) -> Result<Filter, QueryError> {
+    let invalid = || QueryError::InvalidFilter(tokens.join(" "));
+    match tokens {
+        [field, op, value] if field.eq_ignore_ascii_case("confidence") => {
+            let op = CompareOp::parse(op).ok_or_else(invalid)?;
+            let value = value.parse().map_err(|_| invalid())?;
+            Ok(Filter::Confidence(op, value))
+        },
+        [field, op, value] if field.eq_ignore_ascii_case("source") && op == "=" => Ok(Filter::Source(value.clone())),
+        [field, op, value] if field.eq_ignore_ascii_case("kind") && op == "=" => Ok(Filter::Kind(value.clone())),
+        [field, op, value] if field.eq_ignore_ascii_case("polarity") && op == "=" => match value.to_lowercase().as_str() {
+            "positive" => Ok(Filter::Polarity(Some(Polarity::Positive))),
+            "negative" => Ok(Filter::Polarity(Some(Polarity::Negative))),
+            "any" => Ok(Filter::Polarity(None)),
+            _ => Err(invalid()),
+        },
+        [field, op, value @ ..] if field.eq_ignore_ascii_case("value") && !value.is_empty() => {
+            let op = CompareOp::parse(op).ok_or_else(invalid)?;
+            let value = quantity::parse_quantity(&value.join(" ")).ok_or_else(invalid)?;
+            Ok(Filter::Value(op, value))
+        },
+        [field, op, value @ ..] if field.eq_ignore_ascii_case("time") && !value.is_empty() => {
+            let op = CompareOp::parse(op).ok_or_else(invalid)?;
+            let date = temporal::parse_date(&value.join(" "), chrono::Utc::now().date_naive()).ok_or_else(invalid)?;
+            Ok(Filter::Time(op, date))
+        },
+        [var, op, value @ ..] if var.starts_with('?') && CompareOp::parse(op).is_some() && !value.is_empty() => {
+            let date = temporal::parse_date(&value.join(" "), chrono::Utc::now().date_naive()).ok_or_else(invalid)?;
+            Ok(Filter::Date(var[1..].to_string(), CompareOp::parse(op).unwrap(), date))
+        },
+        [var, keyword, tag] if keyword.eq_ignore_ascii_case("tag") => match parse_term(var) {
+            Term::Var(name) => Ok(Filter::Tag(name, tag.clone())),
+            Term::Literal(_) => Err(invalid()),
+        },
+        _ => Err(invalid()),
+    }
+}
+
+/// Разбить запрос на предложения (клаузы) из токенов
+fn tokenize(input: &str) -> Result<Vec<Vec<String>>, QueryError> {
+    let mut clauses = Vec::new();
+    let mut current: Vec<String> = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    fn finish(current: &mut Vec<String>, clauses: &mut Vec<Vec<String>>) {
+        if !current.is_empty() {
+            clauses.push(std::mem::take(current));
+        }
+    }
+
+    while let Some(&c) = chars.peek() {
+        if c == '\n' || c == ';' {
+            chars.next();
+            finish(&mut current, &mut clauses);
+        } else if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut literal = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(ch) => literal.push(ch),
+                    None => return Err(QueryError::UnterminatedString),
+                }
+            }
+            current.push(literal);
+        } else {
+            // Точка завершает шаблон, только если за ней пробел или конец запроса:
+            // "1.5", "т.е" и литералы в кавычках ("2000 г.") остаются целыми
+            let mut word = String::new();
+            let mut ends_clause = false;
+            while let Some(&ch) = chars.peek() {
+                if ch.is_whitespace() || ch == ';' || ch == '"' {
+                    break;
+                }
+                chars.next();
+                if ch == '.' && chars.peek().is_none_or(|next| next.is_whitespace() || *next == ';') {
+                    ends_clause = true;
+                    break;
+                }
+                word.push(ch);
+            }
+            if !word.is_empty() {
+                current.push(word);
+            }
+            if ends_clause {
+                finish(&mut current, &mut clauses);
+            }
+        }
+    }
+    finish(&mut current, &mut clauses);
+
+    Ok(clauses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::EdgeType;
+    use crate::segment::Edge;
+
+    fn sample_graph() -> KnowledgeGraph {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let mut kg = KnowledgeGraph::new(db);
+        let cat = kg.upsert_primitive("кот");
+        let dog = kg.upsert_primitive("собака");
+        let fish = kg.upsert_primitive("рыба");
+        let sea = kg.upsert_primitive("море");
+        kg.add_tag(cat, "Animal").unwrap();
+        kg.add_edge(cat, fish, Edge::new(EdgeType::Action, "ест", 0.9, Source::Wikipedia));
+        kg.add_edge(dog, fish, Edge::new(EdgeType::Action, "ест", 0.4, Source::Extracted));
+        kg.add_edge(fish, sea, Edge::new(EdgeType::Location, "водится", 0.8, Source::Wikipedia));
+        kg
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let query = Query::parse("?x ест рыба . FILTER confidence >= 0.5\nLIMIT 3").unwrap();
+        assert_eq!(query.patterns.len(), 1);
+        assert_eq!(query.patterns[0].subject, Term::Var("x".into()));
+        assert_eq!(query.filters, vec![Filter::Confidence(CompareOp::Ge, 0.5)]);
+        assert_eq!(query.limit, Some(3));
+        assert_eq!(Query::parse("?x ест"), Err(QueryError::InvalidPattern("?x ест".into())));
+        assert_eq!(Query::parse("FILTER ?x tag Animal"), Err(QueryError::Empty));
+
+        // Точка внутри литерала и числа не разделяет шаблоны
+        let query = Query::parse("?x основан \"2000 г.\". ?x масса 1.5. FILTER confidence > 0.5.").unwrap();
+        assert_eq!(query.patterns.len(), 2);
+        assert_eq!(query.patterns[0].object, Term::Literal("2000 г.".into()));
+        assert_eq!(query.patterns[1].object, Term::Literal("1.5".into()));
+        assert_eq!(query.filters, vec![Filter::Confidence(CompareOp::Gt, 0.5)]);
+    }
+
+    #[test]
+    fn test_single_pattern_with_filters() {
+        let kg = sample_graph();
+        let result = Query::parse("?x ест рыба").unwrap().execute(&kg).unwrap();
+        assert_eq!(result.rows.len(), 2);
+
+        let result = Query::parse("?x ест рыба; FILTER confidence > 0.5").unwrap().execute(&kg).unwrap();
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0].bindings["x"], "кот");
+        assert_eq!(result.rows[0].edges[0].source, Source::Wikipedia);
+
+        let result = Query::parse("?x ?p рыба\nFILTER ?x tag animal").unwrap().execute(&kg).unwrap();
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0].bindings["p"], "ест");
+    }
+
+    #[test]
+    fn test_multi_hop_join() {
+        let kg = sample_graph();
+        let result = Query::parse("?x ест ?y . ?y водится ?где").unwrap().execute(&kg).unwrap();
+        assert_eq!(result.variables, vec!["x", "y", "где"]);
+        assert_eq!(result.rows.len(), 2);
+        assert!(result.rows.iter().all(|r| r.bindings["где"] == "море" && r.edges.len() == 2));
+
+        let result = Query::parse("?x ест ?y . ?y водится \"луна\"").unwrap().execute(&kg).unwrap();
+        assert!(result.rows.is_empty());
+    }
+    #[test]
+    fn test_negative_edges_excluded_by_default() {
+        let mut kg = sample_graph();
+        let horse = kg.upsert_primitive("лошадь");
+        let fish = kg.find_primitive("рыба").unwrap();
+        let mut negated = Edge::new(EdgeType::Action, "ест", 0.8, Source::Extracted);
+        negated.polarity = Polarity::Negative;
+        kg.add_edge(horse, fish, negated);
+
+        let result = Query::parse("?x ест рыба").unwrap().execute(&kg).unwrap();
+        assert!(result.rows.iter().all(|r| r.bindings["x"] != "лошадь"));
+
+        let result = Query::parse("?x ест рыба . FILTER polarity = negative").unwrap().execute(&kg).unwrap();
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0].edges[0].polarity, Polarity::Negative);
+        assert_eq!(Query::parse("?x ест рыба . FILTER polarity = any").unwrap().execute(&kg).unwrap().rows.len(), 3);
+        assert!(Query::parse("?x ест рыба . FILTER polarity = maybe").is_err());
+    }
+
+    #[test]
+    fn test_value_filter_compares_quantities() {
+        let mut kg = sample_graph();
+        let cat = kg.find_primitive("кот").unwrap();
+        let dog = kg.find_primitive("собака").unwrap();
+        for (node, text) in [(cat, "4.5 кг"), (dog, "30 кг")] {
+            let value = kg.upsert_primitive(text);
+            let mut edge = Edge::new(EdgeType::Property, "масса", 0.8, Source::Extracted);
+            edge.quantity = quantity::parse_quantity(text);
+            kg.add_edge(node, value, edge);
+        }
+
+        // 5000 г и 5 кг — одна и та же величина
+        let result = Query::parse("?x масса ?m . FILTER value > 5000 г").unwrap().execute(&kg).unwrap();
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0].bindings["x"], "собака");
+        assert_eq!(Query::parse("?x масса ?m . FILTER value <= 5 kg").unwrap().execute(&kg).unwrap().rows[0].bindings["x"], "кот");
+        // Другая размерность не сравнивается
+        assert!(Query::parse("?x масса ?m . FILTER value > 1 м").unwrap().execute(&kg).unwrap().rows.is_empty());
+        assert!(Query::parse("?x масса ?m . FILTER value > много").is_err());
+    }
+
+    #[test]
+    fn test_date_filter_and_order() {
+        let mut kg = sample_graph();
+        let fish = kg.find_primitive("рыба").unwrap();
+        for (who, when) in [("кот", "2023-05-05"), ("собака", "1801-01-01/1900-12-31"), ("лошадь", "2024")] {
+            let subject = kg.upsert_primitive(who);
+            let date = kg.upsert_primitive(when);
+            kg.add_edge(subject, date, Edge::new(EdgeType::Property, "дата", 0.8, Source::Extracted));
+            kg.add_edge(subject, fish, Edge::new(EdgeType::Action, "видел", 0.8, Source::Extracted));
+        }
+
+        let result = Query::parse("?x дата ?когда . ORDER BY ?когда").unwrap().execute(&kg).unwrap();
+        let order: Vec<&str> = result.rows.iter().map(|r| r.bindings["x"].as_str()).collect();
+        assert_eq!(order, vec!["собака", "кот", "лошадь"]);
+
+        let result = Query::parse("?x дата ?когда . FILTER ?когда >= 1 января 2000 года . ORDER BY ?когда DESC . LIMIT 1").unwrap().execute(&kg).unwrap();
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0].bindings["x"], "лошадь");
+        assert!(matches!(Query::parse("?x дата ?когда . ORDER ?когда"), Err(QueryError::InvalidOrder(_))));
+    }
+
+    #[test]
+    fn test_unbounded_patterns_and_solution_cap() {
+        assert_eq!(Query::parse("?a ?p ?b . ?c ?q ?d"), Err(QueryError::Unbounded("?a ?p ?b".into())));
+        assert!(matches!(Query::parse("?x ест ?y . ?c ?q ?d"), Err(QueryError::Unbounded(_))));
+        assert!(Query::parse("?x ест ?y . ?y ?p ?z").is_ok());
+        assert!(Query::parse("?a ест ?b").is_ok());
+
+        // 150 едоков рыбы: пары едоков — 22350 решений, больше предела
+        let mut kg = sample_graph();
+        let fish = kg.find_primitive("рыба").unwrap();
+        for i in 0..150 {
+            let eater = kg.upsert_primitive(&format!("едок{}", i));
+            kg.add_edge(eater, fish, Edge::new(EdgeType::Action, "ест", 0.5, Source::Extracted));
+        }
+        let pairs = "?x ест рыба . ?y ест рыба";
+        assert_eq!(Query::parse(pairs).unwrap().execute(&kg).unwrap_err(), QueryError::TooManySolutions(MAX_SOLUTIONS));
+        // Без ORDER BY соединение останавливается на LIMIT; с сортировкой нужны все решения
+        assert_eq!(Query::parse(&format!("{} . LIMIT 5", pairs)).unwrap().execute(&kg).unwrap().rows.len(), 5);
+        assert!(Query::parse(&format!("{} . ORDER BY ?x . LIMIT 5", pairs)).unwrap().execute(&kg).is_err());
+    }
+}
@@ -1,31 +1,276 @@
-use petgraph::graph::{Graph, NodeIndex};
-use sled::Db;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+use petgraph::graph::{EdgeIndex, Graph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use serde::{Serialize, Deserialize};
+use sled::{Db, Tree};
 use uuid::Uuid;
-use crate::segment::{Segment, KnowledgeNode as SegKnowledgeNode};
+use crate::segment::{Segment, Edge};
+use crate::embedding_cache::EmbeddingCache;
+use crate::vector_index::{VectorIndex, IndexError};
+
+/// Имена деревьев sled, в которых хранится граф
+const NODES_TREE: &str = "kg_nodes";
+const EDGES_TREE: &str = "kg_edges";
+const VECTORS_TREE: &str = "kg_vectors";
+
+/// Запись узла на диске: стабильный id + сегмент
+#[derive(Serialize, Deserialize)]
+struct StoredNode {
+    id: Uuid,
+    segment: Segment,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// Запись ребра на диске: концы ссылаются на стабильные id узлов
+#[derive(Serialize, Deserialize)]
+struct StoredEdge {
+    source: Uuid,
+    target: Uuid,
+    edge: Edge,
+}
+
+/// Ошибка хранилища графа знаний
+#[derive(Debug)]
+pub enum GraphError {
+    Storage(sled::Error),
+    Serialization(serde_json::Error),
+    Index(IndexError),
+    DanglingEdge(Uuid),
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::Storage(e) => write!(f, "Ошибка хранилища sled: {}", e),
+            GraphError::Serialization(e) => write!(f, "Ошибка сериализации: {}", e),
+            GraphError::Index(e) => write!(f, "Ошибка векторного индекса: {}", e),
+            GraphError::DanglingEdge(id) => write!(f, "Ребро ссылается на несуществующий узел {}", id),
+        }
+    }
+}
+
+impl Error for GraphError {}
+
+impl From<sled::Error> for GraphError {
+    fn from(e: sled::Error) -> Self {
+        GraphError::Storage(e)
+    }
+}
+
+impl From<IndexError> for GraphError {
+    fn from(e: IndexError) -> Self {
+        GraphError::Index(e)
+    }
+}
+
+impl From<serde_json::Error> for GraphError {
+    fn from(e: serde_json::Error) -> Self {
+        GraphError::Serialization(e)
+    }
+}
 
 /// Граф знаний: petgraph + sled для хранения
 pub struct KnowledgeGraph {
-    pub graph: Graph<Segment, String>, // Узлы теперь содержат Segment
+    pub graph: Graph<Segment, Edge>, // Узлы содержат Segment, рёбра — типизированные связи
     pub db: Db,
+    pub vectors: VectorIndex, // Эмбеддинги узлов для поиска похожих
+    nodes: Tree,
+    edges: Tree,
+    ids: Vec<Uuid>, // NodeIndex -> стабильный id
+    keys: Vec<[u8; 8]>, // NodeIndex -> ключ записи в sled
+    edge_keys: Vec<[u8; 8]>, // EdgeIndex -> ключ записи в sled
+    tags: Vec<Vec<String>>, // NodeIndex -> теги узла (типы сущностей и т.п.)
+    index_by_id: HashMap<Uuid, NodeIndex>,
+    index_by_text: HashMap<String, NodeIndex>, // Дедупликация примитивов
 }
 
 impl KnowledgeGraph {
-    /// Создать новый граф знаний
+    /// Создать граф знаний поверх базы sled (существующие данные подгружаются)
     pub fn new(db: Db) -> Self {
-        Self {
+        Self::load(db).expect("загрузка графа знаний из sled")
+    }
+
+    /// Открыть граф знаний по пути к базе sled и восстановить его с диска
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, GraphError> {
+        Self::load(sled::open(path)?)
+    }
+
+    /// Восстановить petgraph из деревьев sled (узлы и рёбра в порядке добавления)
+    pub fn load(db: Db) -> Result<Self, GraphError> {
+        let nodes = db.open_tree(NODES_TREE)?;
+        let edges = db.open_tree(EDGES_TREE)?;
+        let embedder = EmbeddingCache::embedder();
+        let model = format!("{}:{}", embedder.name(), embedder.dimension());
+        let vectors = VectorIndex::open(db.open_tree(VECTORS_TREE)?, embedder.dimension(), &model)?;
+        let mut kg = Self {
             graph: Graph::new(),
             db,
+            vectors,
+            nodes,
+            edges,
+            ids: Vec::new(),
+            keys: Vec::new(),
+            edge_keys: Vec::new(),
+            tags: Vec::new(),
+            index_by_id: HashMap::new(),
+            index_by_text: HashMap::new(),
+        };
+
+        for entry in kg.nodes.iter() {
+            let (key, value) = entry?;
+            let stored: StoredNode = serde_json::from_slice(&value)?;
+            let mut node_key = [0u8; 8];
+            node_key.copy_from_slice(&key);
+            kg.insert_in_memory(node_key, stored.id, stored.segment, stored.tags);
         }
+
+        for entry in kg.edges.iter() {
+            let (key, value) = entry?;
+            let stored: StoredEdge = serde_json::from_slice(&value)?;
+            let a = kg.node_index(stored.source).ok_or(GraphError::DanglingEdge(stored.source))?;
+            let b = kg.node_index(stored.target).ok_or(GraphError::DanglingEdge(stored.target))?;
+            let mut edge_key = [0u8; 8];
+            edge_key.copy_from_slice(&key);
+            kg.graph.add_edge(a, b, stored.edge);
+            kg.edge_keys.push(edge_key);
+        }
+
+        // Узлы без векторов (новая база или смена модели эмбеддингов) индексируются заново
+        for idx in kg.graph.node_indices() {
+            if !kg.vectors.contains(kg.ids[idx.index()]) {
+                kg.index_node(idx)?;
+            }
+        }
+        kg.db.flush()?;
+
+        Ok(kg)
     }
 
     /// Добавить узел с вложенным сегментом
     pub fn add_node(&mut self, segment: Segment) -> NodeIndex {
-        self.graph.add_node(segment)
+        self.try_add_node(segment).expect("запись узла в sled")
+    }
+
+    /// Добавить узел и записать его в sled
+    pub fn try_add_node(&mut self, segment: Segment) -> Result<NodeIndex, GraphError> {
+        let id = Uuid::new_v4();
+        let key = self.next_key()?;
+        let record = serde_json::to_vec(&StoredNode { id, segment: segment.clone(), tags: Vec::new() })?;
+        self.nodes.insert(key, record)?;
+        let idx = self.insert_in_memory(key, id, segment, Vec::new());
+        self.index_node(idx)?;
+        Ok(idx)
+    }
+
+    /// Узлы, похожие на текст: пары (узел, косинусное сходство) по убыванию сходства.
+    /// `tag` оставляет только узлы с этим тегом, `min_similarity` — только достаточно близкие
+    pub fn similar_nodes(&self, text: &str, top_k: usize, min_similarity: Option<f32>, tag: Option<&str>) -> Vec<(NodeIndex, f32)> {
+        let query = EmbeddingCache::get_cached_embedding(text);
+        let has_tag = |id: &Uuid| match (tag, self.node_index(*id)) {
+            (None, _) => true,
+            (Some(tag), Some(idx)) => self.tags(idx).iter().any(|t| t.eq_ignore_ascii_case(tag)),
+            (Some(_), None) => false,
+        };
+        let hits = match min_similarity {
+            Some(min) => self.vectors.search_radius(&query, min, Some(&has_tag)).into_iter().take(top_k).collect(),
+            None => self.vectors.search(&query, top_k, Some(&has_tag)),
+        };
+        hits.into_iter()
+            .filter_map(|(id, sim)| self.node_index(id).map(|idx| (idx, sim)))
+            .collect()
+    }
+
+    /// Найти примитивный узел по тексту или создать новый (без дубликатов)
+    pub fn upsert_primitive(&mut self, text: &str) -> NodeIndex {
+        match self.find_primitive(text) {
+            Some(idx) => idx,
+            None => self.add_node(Segment::Primitive(normalize_label(text))),
+        }
+    }
+
+    /// Найти примитивный узел по тексту (без учёта регистра и пробелов по краям)
+    pub fn find_primitive(&self, text: &str) -> Option<NodeIndex> {
+        self.index_by_text.get(&normalize_label(text)).copied()
+    }
+
+    /// Добавить тег узлу (повторные теги игнорируются); возвращает true, если тег новый
+    pub fn add_tag(&mut self, idx: NodeIndex, tag: &str) -> Result<bool, GraphError> {
+        if self.tags[idx.index()].iter().any(|t| t == tag) {
+            return Ok(false);
+        }
+        self.tags[idx.index()].push(tag.to_string());
+        let record = serde_json::to_vec(&StoredNode {
+            id: self.ids[idx.index()],
+            segment: self.graph[idx].clone(),
+            tags: self.tags[idx.index()].clone(),
+        })?;
+        self.nodes.insert(self.keys[idx.index()], record)?;
+        Ok(true)
+    }
+
+    /// Теги узла
+    pub fn tags(&self, idx: NodeIndex) -> &[String] {
+        self.tags.get(idx.index()).map(|t| t.as_slice()).unwrap_or(&[])
     }
 
     /// Добавить ребро между двумя узлами
-    pub fn add_edge(&mut self, a: NodeIndex, b: NodeIndex, label: String) {
-        self.graph.add_edge(a, b, label);
+    pub fn add_edge(&mut self, a: NodeIndex, b: NodeIndex, edge: Edge) -> EdgeIndex {
+        self.try_add_edge(a, b, edge).expect("запись ребра в sled")
+    }
+
+    /// Добавить ребро и записать его в sled
+    pub fn try_add_edge(&mut self, a: NodeIndex, b: NodeIndex, edge: Edge) -> Result<EdgeIndex, GraphError> {
+        let key = self.next_key()?;
+        self.write_edge(key, a, b, &edge)?;
+        let idx = self.graph.add_edge(a, b, edge);
+        self.edge_keys.push(key);
+        Ok(idx)
+    }
+
+    /// Найти ребро a -> b с заданной меткой
+    pub fn find_edge_labeled(&self, a: NodeIndex, b: NodeIndex, label: &str) -> Option<EdgeIndex> {
+        self.graph.edges_connecting(a, b).find(|e| e.weight().label == label).map(|e| e.id())
+    }
+
+    /// Добавить ребро, если связи a -> b с той же меткой и полярностью ещё нет; иначе подтвердить
+    /// существующую: обновить `last_verified`, источник и уверенность (берётся максимум).
+    /// Возвращает true, если ребро новое
+    pub fn ensure_edge(&mut self, a: NodeIndex, b: NodeIndex, edge: Edge) -> Result<bool, GraphError> {
+        let existing = self
+            .graph
+            .edges_connecting(a, b)
+            .find(|e| e.weight().label == edge.label && e.weight().polarity == edge.polarity && e.weight().time == edge.time)
+            .map(|e| e.id());
+        let Some(idx) = existing else {
+            self.try_add_edge(a, b, edge)?;
+            return Ok(true);
+        };
+        let existing = &mut self.graph[idx];
+        if edge.confidence >= existing.confidence {
+            existing.confidence = edge.confidence;
+            existing.source = edge.source;
+            existing.span = edge.span.or(existing.span.take());
+            existing.modality = edge.modality;
+            existing.quantity = edge.quantity.or(existing.quantity.take());
+        }
+        existing.last_verified = existing.last_verified.max(edge.last_verified);
+        let updated = existing.clone();
+        self.write_edge(self.edge_keys[idx.index()], a, b, &updated)?;
+        Ok(false)
+    }
+
+    /// Стабильный id узла (сохраняется между перезапусками)
+    pub fn node_id(&self, idx: NodeIndex) -> Option<Uuid> {
+        self.ids.get(idx.index()).copied()
+    }
+
+    /// Индекс узла в petgraph по стабильному id
+    pub fn node_index(&self, id: Uuid) -> Option<NodeIndex> {
+        self.index_by_id.get(&id).copied()
     }
 
     /// Найти узел по предикату (например, по id)
@@ -56,4 +301,125 @@ impl KnowledgeGraph {
             Segment::Link(_node) => println!("{}Link to KnowledgeNode", "  ".repeat(depth)),
         }
     }
-} 
\ No newline at end of file
+
+    /// Записать эмбеддинг текста узла в векторный индекс
+    fn index_node(&mut self, idx: NodeIndex) -> Result<(), GraphError> {
+        let embedding = EmbeddingCache::get_cached_embedding(&self.graph[idx].text());
+        self.vectors.insert(self.ids[idx.index()], embedding)?;
+        Ok(())
+    }
+
+    fn insert_in_memory(&mut self, key: [u8; 8], id: Uuid, segment: Segment, tags: Vec<String>) -> NodeIndex {
+        let label = match &segment {
+            Segment::Primitive(text) => Some(normalize_label(text)),
+            _ => None,
+        };
+        let idx = self.graph.add_node(segment);
+        if let Some(label) = label {
+            self.index_by_text.entry(label).or_insert(idx);
+        }
+        self.ids.push(id);
+        self.keys.push(key);
+        self.tags.push(tags);
+        self.index_by_id.insert(id, idx);
+        idx
+    }
+
+    fn write_edge(&self, key: [u8; 8], a: NodeIndex, b: NodeIndex, edge: &Edge) -> Result<(), GraphError> {
+        let record = serde_json::to_vec(&StoredEdge {
+            source: self.ids[a.index()],
+            target: self.ids[b.index()],
+            edge: edge.clone(),
+        })?;
+        self.edges.insert(key, record)?;
+        Ok(())
+    }
+
+    /// Синхронно сбросить записи на диск. Отдельные изменения не ждут fsync
+    /// (sled сбрасывает их в фоне); пакет изменений завершается одним вызовом flush
+    pub fn flush(&self) -> Result<(), GraphError> {
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Монотонный ключ записи: порядок в sled совпадает с порядком добавления
+    fn next_key(&self) -> Result<[u8; 8], GraphError> {
+        Ok(self.db.generate_id()?.to_be_bytes())
+    }
+}
+
+/// Нормализация текста узла для дедупликации
+fn normalize_label(text: &str) -> String {
+    text.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{EdgeType, Source};
+
+    #[test]
+    fn test_reload_from_disk() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let (cat_id, fish_id) = {
+            let mut kg = KnowledgeGraph::new(db.clone());
+            let cat = kg.add_node(Segment::Primitive("кот".into()));
+            let fish = kg.add_node(Segment::Primitive("рыба".into()));
+            kg.add_edge(cat, fish, Edge::new(EdgeType::Action, "ест", 0.7, Source::Wikipedia));
+            (kg.node_id(cat).unwrap(), kg.node_id(fish).unwrap())
+        };
+
+        let kg = KnowledgeGraph::load(db).unwrap();
+        assert_eq!(kg.graph.node_count(), 2);
+        assert_eq!(kg.graph.edge_count(), 1);
+        let cat = kg.node_index(cat_id).unwrap();
+        let fish = kg.node_index(fish_id).unwrap();
+        assert_eq!(kg.graph[cat], Segment::Primitive("кот".into()));
+        let edge = &kg.graph[kg.graph.find_edge(cat, fish).unwrap()];
+        assert_eq!(edge.label, "ест");
+        assert_eq!(edge.kind, EdgeType::Action);
+        assert_eq!(edge.source, Source::Wikipedia);
+    }
+
+    #[test]
+    fn test_similar_nodes_survive_reload() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        {
+            let mut kg = KnowledgeGraph::new(db.clone());
+            let cat = kg.upsert_primitive("кошка");
+            kg.upsert_primitive("дерево");
+            kg.add_tag(cat, "Animal").unwrap();
+        }
+
+        let kg = KnowledgeGraph::load(db).unwrap();
+        assert_eq!(kg.vectors.len(), 2);
+        let similar = kg.similar_nodes("кошки", 1, None, None);
+        assert_eq!(kg.graph[similar[0].0].text(), "кошка");
+        assert!(kg.similar_nodes("дерево", 5, None, Some("Animal")).iter().all(|(idx, _)| kg.graph[*idx].text() == "кошка"));
+    }
+
+    #[test]
+    fn test_upsert_deduplicates_and_keeps_tags() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        {
+            let mut kg = KnowledgeGraph::new(db.clone());
+            let a = kg.upsert_primitive("Кот");
+            let b = kg.upsert_primitive("кот ");
+            assert_eq!(a, b);
+            assert!(kg.add_tag(a, "Animal").unwrap());
+            assert!(!kg.add_tag(a, "Animal").unwrap());
+            let fish = kg.upsert_primitive("рыба");
+            assert!(kg.ensure_edge(a, fish, Edge::new(EdgeType::Action, "ест", 0.5, Source::Extracted)).unwrap());
+            assert!(!kg.ensure_edge(a, fish, Edge::new(EdgeType::Action, "ест", 0.9, Source::ArXiv)).unwrap());
+        }
+
+        let kg = KnowledgeGraph::load(db).unwrap();
+        assert_eq!(kg.graph.node_count(), 2);
+        assert_eq!(kg.graph.edge_count(), 1);
+        let cat = kg.find_primitive("кот").unwrap();
+        assert_eq!(kg.tags(cat), ["Animal".to_string()]);
+        let edge = &kg.graph[kg.graph.edge_indices().next().unwrap()];
+        assert_eq!(edge.confidence, 0.9);
+        assert_eq!(edge.source, Source::ArXiv);
+    }
+}
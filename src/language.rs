@@ -0,0 +1,108 @@
+// language.rs
+// Определение языка документа для "Мыслящего Ядро"
+//
+// Язык определяется по доле кириллических и латинских букв; при смешанном
+// тексте решают частые служебные слова. Каждый язык получает свои шаблоны
+// извлечения, словари сущностей и лемматизатор.
+
+use std::fmt;
+use serde::{Serialize, Deserialize};
+
+/// Язык текста (в JSON/TOML — код ISO 639-1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    #[serde(rename = "ru")]
+    Russian,
+    #[serde(rename = "en")]
+    English,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::Russian, Language::English];
+
+    /// Код ISO 639-1
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::Russian => "ru",
+            Language::English => "en",
+        }
+    }
+
+    /// Язык по коду ISO 639-1 или названию
+    pub fn from_code(code: &str) -> Option<Language> {
+        match code.trim().to_lowercase().as_str() {
+            "ru" | "rus" | "russian" => Some(Language::Russian),
+            "en" | "eng" | "english" => Some(Language::English),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+/// Частые служебные слова (для текстов со смешанным алфавитом)
+const RUSSIAN_STOPWORDS: &[&str] = &["и", "в", "не", "на", "что", "с", "по", "это", "как", "из", "к", "у", "для", "является"];
+const ENGLISH_STOPWORDS: &[&str] = &["the", "and", "of", "to", "in", "is", "a", "an", "that", "for", "with", "as", "are", "by"];
+
+/// Результат определения языка
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Detection {
+    pub language: Language,
+    pub confidence: f32, // Доля свидетельств в пользу выбранного языка
+}
+
+/// Определить язык текста; без букв — русский (язык по умолчанию) с нулевой уверенностью
+pub fn detect(text: &str) -> Detection {
+    let (mut cyrillic, mut latin) = (0usize, 0usize);
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        match c {
+            'а'..='я' | 'А'..='Я' | 'ё' | 'Ё' => cyrillic += 1,
+            'a'..='z' | 'A'..='Z' => latin += 1,
+            _ => {},
+        }
+    }
+
+    // Кириллица и латиница почти поровну: считаем служебные слова
+    let letters = cyrillic + latin;
+    if letters > 0 && cyrillic.abs_diff(latin) * 5 < letters {
+        let lowered = text.to_lowercase();
+        let words: Vec<&str> = lowered.split(|c: char| !c.is_alphabetic()).filter(|w| !w.is_empty()).collect();
+        cyrillic = words.iter().filter(|w| RUSSIAN_STOPWORDS.contains(w)).count();
+        latin = words.iter().filter(|w| ENGLISH_STOPWORDS.contains(w)).count();
+    }
+
+    let total = (cyrillic + latin) as f32;
+    if total == 0.0 {
+        return Detection { language: Language::default(), confidence: 0.0 };
+    }
+    if latin > cyrillic {
+        Detection { language: Language::English, confidence: latin as f32 / total }
+    } else {
+        Detection { language: Language::Russian, confidence: cyrillic as f32 / total }
+    }
+}
+
+/// Язык текста (без уверенности)
+pub fn detect_language(text: &str) -> Language {
+    detect(text).language
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(detect_language("Кот ест рыбу."), Language::Russian);
+        assert_eq!(detect_language("The cat eats fish."), Language::English);
+        // Латинские термины в русском тексте не меняют язык
+        assert_eq!(detect_language("Белок p53 подавляет рост опухоли и является супрессором"), Language::Russian);
+        assert_eq!(detect(" 12.05.2024 ").confidence, 0.0);
+        assert_eq!(Language::from_code("EN"), Some(Language::English));
+    }
+}
@@ -0,0 +1,360 @@
+// lemmatizer.rs
+// Морфологическая нормализация (лемматизация) слов для "Мыслящего Ядро"
+//
+// 1. Словарь словоформ (patterns/ru_lemmas.txt, patterns/en_lemmas.txt, можно дополнить своим файлом)
+// 2. Правила по окончаниям: кандидат принимается, если он известен
+//    (лемма словаря или слово из словарей сущностей)
+// 3. Осторожный запасной вариант для неизвестных существительных (рыбу → рыба):
+//    только когда вызывающий знает, что слово — существительное (WordClass::Noun),
+//    иначе неизвестное слово не меняется (несу ≠ "неса")
+//
+// Глаголы (метки связей) приводятся к инфинитиву только по словарю
+// глагольных форм (patterns/ru_verbs.txt, patterns/en_verbs.txt)
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+use lazy_static::lazy_static;
+use regex::Regex;
+use crate::language::Language;
+
+/// Встроенные словари словоформ
+const RUSSIAN_DICTIONARY: &str = include_str!("../patterns/ru_lemmas.txt");
+const ENGLISH_DICTIONARY: &str = include_str!("../patterns/en_lemmas.txt");
+const RUSSIAN_VERBS: &str = include_str!("../patterns/ru_verbs.txt");
+const ENGLISH_VERBS: &str = include_str!("../patterns/en_verbs.txt");
+
+/// Окончания и замены для подбора начальной формы (длинные окончания раньше)
+const RUSSIAN_SUFFIX_RULES: &[(&str, &str)] = &[
+    // Существительные на -ь (лошадь, болезнь)
+    ("ями", "ь"), ("ями", "я"), ("ами", "а"), ("ами", ""), ("ами", "о"),
+    ("ях", "ь"), ("ях", "я"), ("ях", "е"), ("ах", "а"), ("ах", ""), ("ах", "о"),
+    ("ям", "ь"), ("ям", "я"), ("ям", "е"), ("ам", "а"), ("ам", ""), ("ам", "о"),
+    ("ов", ""), ("ев", "й"), ("ей", "ь"), ("ей", "я"), ("ей", "е"),
+    ("ом", ""), ("ом", "о"), ("ем", "е"), ("ем", "ь"), ("ой", "а"), ("ою", "а"), ("ью", "ь"),
+    // Однобуквенные окончания
+    ("а", ""), ("а", "о"), ("у", ""), ("у", "а"), ("у", "о"), ("е", ""), ("е", "а"), ("е", "о"), ("е", "я"),
+    ("ы", ""), ("ы", "а"), ("и", "я"), ("и", "ь"), ("и", "е"), ("ю", "я"), ("ю", "ь"), ("ю", "е"),
+    ("я", "е"), ("я", "ь"),
+];
+
+/// Запасные правила для неизвестных существительных: только однозначные окончания
+/// винительного падежа женского рода (рыбу → рыба, землю → земля).
+/// Третье поле — минимальная длина основы (короткие слова "иду", "пью" не трогаем)
+const RUSSIAN_FALLBACK_RULES: &[(&str, &str, usize)] = &[("у", "а", 3), ("ю", "я", 3)];
+
+/// Окончания множественного числа английских существительных
+const ENGLISH_SUFFIX_RULES: &[(&str, &str)] = &[
+    ("ies", "y"), ("ves", "f"), ("ves", "fe"), ("es", ""), ("es", "e"), ("s", ""), ("'s", ""),
+];
+
+/// Запасные правила для английского: cities → city, foxes → fox, cats → cat
+const ENGLISH_FALLBACK_RULES: &[(&str, &str, usize)] = &[
+    ("ies", "y", 2), ("sses", "ss", 1), ("xes", "x", 1), ("ches", "ch", 1), ("shes", "sh", 1), ("'s", "", 1), ("s", "", 2),
+];
+
+/// Окончания, которые не являются признаком множественного числа (glass, virus, analysis)
+const ENGLISH_KEEP_ENDINGS: &[&str] = &["ss", "us", "is"];
+
+/// Минимальная длина основы, от которой отбрасывается окончание
+const MIN_STEM: usize = 2;
+
+/// Часть речи слова, известная вызывающему
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordClass {
+    Noun,    // Субъект, объект, сущность: к неизвестному слову применимы запасные правила
+    Unknown, // Слово текста без известной роли: неизвестное слово не меняется
+}
+
+lazy_static! {
+    static ref WORD: Regex = Regex::new(r"\w+(?:-\w+)*").unwrap();
+    static ref RUSSIAN: Lemmatizer = Lemmatizer::russian();
+    static ref ENGLISH: Lemmatizer = Lemmatizer::english();
+}
+
+/// Лемматизированный текст с привязкой к исходному: для каждого слова
+/// диапазон леммы и диапазон исходной словоформы (в байтах)
+#[derive(Debug, Clone, Default)]
+pub struct LemmatizedText {
+    pub text: String,
+    words: Vec<(Range<usize>, Range<usize>)>,
+}
+
+impl LemmatizedText {
+    /// Перевести позицию в лемматизированном тексте в позицию исходного текста
+    fn source_offset(&self, offset: usize, is_end: bool) -> usize {
+        let mut shift: isize = 0;
+        for (lemma, source) in &self.words {
+            if offset < lemma.start || (is_end && offset == lemma.start) {
+                break;
+            }
+            if offset < lemma.end || (is_end && offset == lemma.end) {
+                // Внутри слова: граница расширяется до всей словоформы
+                return if is_end { source.end } else { source.start };
+            }
+            shift = source.end as isize - lemma.end as isize;
+        }
+        (offset as isize + shift) as usize
+    }
+
+    /// Диапазон исходного текста, из которого получен диапазон лемм
+    pub fn source_range(&self, range: Range<usize>) -> Range<usize> {
+        self.source_offset(range.start, false)..self.source_offset(range.end, true)
+    }
+}
+
+/// Лемматизатор: словарь форм и правила по окончаниям
+#[derive(Debug, Clone, Default)]
+pub struct Lemmatizer {
+    forms: HashMap<String, String>, // словоформа → лемма
+    lemmas: HashSet<String>,        // известные начальные формы
+    verbs: HashMap<String, String>, // глагольная форма → инфинитив
+    rules: &'static [(&'static str, &'static str)],
+    fallback: &'static [(&'static str, &'static str, usize)],
+    keep_endings: &'static [&'static str],
+}
+
+impl Lemmatizer {
+    /// Лемматизатор со встроенным русским словарём
+    pub fn russian() -> Self {
+        let mut lemmatizer = Self { rules: RUSSIAN_SUFFIX_RULES, fallback: RUSSIAN_FALLBACK_RULES, ..Self::default() };
+        lemmatizer.add_dictionary(RUSSIAN_DICTIONARY);
+        lemmatizer.add_verb_dictionary(RUSSIAN_VERBS);
+        lemmatizer
+    }
+
+    /// Лемматизатор со встроенным английским словарём
+    pub fn english() -> Self {
+        let mut lemmatizer = Self {
+            rules: ENGLISH_SUFFIX_RULES,
+            fallback: ENGLISH_FALLBACK_RULES,
+            keep_endings: ENGLISH_KEEP_ENDINGS,
+            ..Self::default()
+        };
+        lemmatizer.add_dictionary(ENGLISH_DICTIONARY);
+        lemmatizer.add_verb_dictionary(ENGLISH_VERBS);
+        lemmatizer
+    }
+
+    /// Лемматизатор для языка
+    pub fn for_language(language: Language) -> Self {
+        match language {
+            Language::Russian => Self::russian(),
+            Language::English => Self::english(),
+        }
+    }
+
+    /// Общий экземпляр со встроенным русским словарём
+    pub fn shared() -> &'static Lemmatizer {
+        &RUSSIAN
+    }
+
+    /// Общий экземпляр для языка
+    pub fn shared_for(language: Language) -> &'static Lemmatizer {
+        match language {
+            Language::Russian => &RUSSIAN,
+            Language::English => &ENGLISH,
+        }
+    }
+
+    /// Дополнить словарь строками "лемма форма1 форма2 ..." (# — комментарий)
+    pub fn add_dictionary(&mut self, content: &str) {
+        for (lemma, forms) in dictionary_entries(content) {
+            for form in forms {
+                self.forms.insert(form, lemma.clone());
+            }
+            self.lemmas.insert(lemma);
+        }
+    }
+
+    /// Дополнить словарь глаголов строками "инфинитив форма1 форма2 ..."
+    pub fn add_verb_dictionary(&mut self, content: &str) {
+        for (infinitive, forms) in dictionary_entries(content) {
+            for form in forms {
+                self.verbs.insert(form, infinitive.clone());
+            }
+        }
+    }
+
+    /// Загрузить дополнительный словарь из файла
+    pub fn load_dictionary<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let content = fs::read_to_string(path)?;
+        self.add_dictionary(&content);
+        Ok(())
+    }
+
+    /// Число известных лемм
+    pub fn len(&self) -> usize {
+        self.lemmas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lemmas.is_empty()
+    }
+
+    /// Начальная форма слова (без внешнего словаря)
+    pub fn lemmatize(&self, word: &str) -> String {
+        self.lemmatize_with(word, |_| false)
+    }
+
+    /// Начальная форма существительного (без внешнего словаря)
+    pub fn lemmatize_noun(&self, word: &str) -> String {
+        self.lemmatize_as(word, WordClass::Noun, |_| false)
+    }
+
+    /// Начальная форма слова; `is_known` сообщает о леммах, известных вызывающему
+    /// (например, словам из словарей сущностей)
+    pub fn lemmatize_with(&self, word: &str, is_known: impl Fn(&str) -> bool) -> String {
+        self.lemmatize_as(word, WordClass::Unknown, is_known)
+    }
+
+    /// Начальная форма слова заданной части речи
+    pub fn lemmatize_as(&self, word: &str, class: WordClass, is_known: impl Fn(&str) -> bool) -> String {
+        let word = word.to_lowercase();
+        if let Some(lemma) = self.forms.get(&word) {
+            return lemma.clone();
+        }
+        let known = |w: &str| self.lemmas.contains(w) || is_known(w);
+        if known(&word) {
+            return word;
+        }
+
+        for (ending, replacement) in self.rules {
+            if let Some(stem) = word.strip_suffix(ending)
+                && stem.chars().count() >= MIN_STEM
+            {
+                let candidate = format!("{}{}", stem, replacement);
+                if known(&candidate) {
+                    return candidate;
+                }
+            }
+        }
+
+        if class == WordClass::Noun && !self.keep_endings.iter().any(|e| word.ends_with(e)) {
+            for (ending, replacement, min_stem) in self.fallback {
+                if let Some(stem) = word.strip_suffix(ending)
+                    && stem.chars().count() >= *min_stem
+                {
+                    return format!("{}{}", stem, replacement);
+                }
+            }
+        }
+        word
+    }
+
+    /// Начальная форма глагола или глагольной метки ("едят" → "есть", "lives in" → "live in").
+    /// Правил по окончаниям нет: неизвестная форма остаётся как есть
+    pub fn lemmatize_verb(&self, verb: &str) -> String {
+        verb.split_whitespace()
+            .map(|w| {
+                let w = w.to_lowercase();
+                self.verbs.get(&w).cloned().unwrap_or(w)
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Заменить каждое слово текста его леммой (разделители сохраняются)
+    pub fn lemmatize_text_with(&self, text: &str, is_known: impl Fn(&str) -> bool) -> String {
+        self.lemmatize_mapped(text, is_known).text
+    }
+
+    /// Лемматизировать текст, сохранив привязку лемм к исходным словоформам
+    pub fn lemmatize_mapped(&self, text: &str, is_known: impl Fn(&str) -> bool) -> LemmatizedText {
+        let mut result = LemmatizedText::default();
+        let mut last = 0;
+        for word in WORD.find_iter(text) {
+            result.text.push_str(&text[last..word.start()]);
+            let start = result.text.len();
+            result.text.push_str(&self.lemmatize_with(word.as_str(), &is_known));
+            result.words.push((start..result.text.len(), word.range()));
+            last = word.end();
+        }
+        result.text.push_str(&text[last..]);
+        result
+    }
+
+    /// Заменить каждое слово текста его леммой
+    pub fn lemmatize_text(&self, text: &str) -> String {
+        self.lemmatize_text_with(text, |_| false)
+    }
+}
+
+/// Строки словаря: первое слово и остальные формы (в нижнем регистре)
+fn dictionary_entries(content: &str) -> impl Iterator<Item = (String, Vec<String>)> + '_ {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut words = line.split_whitespace().map(str::to_lowercase);
+            let lemma = words.next()?;
+            Some((lemma, words.collect()))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dictionary_and_rules() {
+        let lemmatizer = Lemmatizer::russian();
+        assert_eq!(lemmatizer.lemmatize("рыбу"), "рыба");
+        assert_eq!(lemmatizer.lemmatize("Москве"), "москва");
+        assert_eq!(lemmatizer.lemmatize("людей"), "человек");
+        assert_eq!(lemmatizer.lemmatize("кот"), "кот");
+        // Неизвестное существительное: запасное правило и нетронутые короткие формы
+        assert_eq!(lemmatizer.lemmatize_noun("пиццу"), "пицца");
+        assert_eq!(lemmatizer.lemmatize_noun("иду"), "иду");
+        // Без известной части речи неизвестное слово не меняется
+        assert_eq!(lemmatizer.lemmatize("пиццу"), "пиццу");
+        assert_eq!(lemmatizer.lemmatize("несу"), "несу");
+        assert_eq!(lemmatizer.lemmatize("ест"), "ест");
+        // Глаголы — только по словарю глагольных форм
+        assert_eq!(lemmatizer.lemmatize_verb("едят"), "есть");
+        assert_eq!(lemmatizer.lemmatize_verb("Ест"), "есть");
+        assert_eq!(lemmatizer.lemmatize_verb("несу"), "несу");
+        assert_eq!(Lemmatizer::english().lemmatize_verb("lives in"), "live in");
+    }
+
+    #[test]
+    fn test_english_lemmas() {
+        let lemmatizer = Lemmatizer::english();
+        assert_eq!(lemmatizer.lemmatize("Cats"), "cat");
+        assert_eq!(lemmatizer.lemmatize("mice"), "mouse");
+        assert_eq!(lemmatizer.lemmatize_noun("cities"), "city");
+        assert_eq!(lemmatizer.lemmatize_noun("foxes"), "fox");
+        assert_eq!(lemmatizer.lemmatize("foxes"), "foxes");
+        assert_eq!(lemmatizer.lemmatize("virus"), "virus");
+        assert_eq!(lemmatizer.lemmatize("glass"), "glass");
+    }
+
+    #[test]
+    fn test_known_words_from_caller() {
+        let lemmatizer = Lemmatizer::russian();
+        let gazetteer = ["ёж", "квадрокоптер"];
+        let known = |w: &str| gazetteer.contains(&w);
+        assert_eq!(lemmatizer.lemmatize_with("квадрокоптерами", known), "квадрокоптер");
+        assert_eq!(lemmatizer.lemmatize_with("квадрокоптера", known), "квадрокоптер");
+        assert_eq!(lemmatizer.lemmatize_text_with("Кот ловит квадрокоптеры в Москве", known), "кот ловит квадрокоптер в москва");
+    }
+
+    #[test]
+    fn test_lemmatized_text_maps_to_source() {
+        let lemmatizer = Lemmatizer::russian();
+        let source = "Кот ест рыбу в Москве!";
+        let lemmatized = lemmatizer.lemmatize_mapped(source, |_| false);
+        assert_eq!(lemmatized.text, "кот ест рыба в москва!");
+
+        let find = |needle: &str| {
+            let start = lemmatized.text.find(needle).unwrap();
+            &source[lemmatized.source_range(start..start + needle.len())]
+        };
+        assert_eq!(find("рыба"), "рыбу");
+        assert_eq!(find("москва"), "Москве");
+        assert_eq!(find("рыба в москва!"), "рыбу в Москве!");
+    }
+}
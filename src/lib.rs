@@ -1,12 +1,32 @@
 pub mod segment;
 pub mod knowledge_graph;
 pub mod trusted_scraper;
+pub mod fact_source;
+pub mod wiki_index;
+pub mod verification;
+pub mod feeds;
+pub mod resilience;
 pub mod active_learning;
 pub mod api;
 pub mod text_processor;
+pub mod language;
+pub mod modality;
+pub mod quantity;
+pub mod patterns;
+pub mod lemmatizer;
+pub mod sentence_splitter;
+pub mod temporal;
+pub mod coreference;
+pub mod pipeline;
+pub mod evaluation;
+pub mod ner;
 pub mod embedding_cache;
+pub mod embedder;
+pub mod vector_index;
 pub mod attention_gpu;
 pub mod attention_logic;
 pub mod logic_attention;
 pub mod graph_cache;
+pub mod graph_query;
+pub mod qa;
 pub mod core; 
\ No newline at end of file
@@ -1,19 +1,11 @@
-mod attention_gpu;
-mod attention_logic;
-mod knowledge_graph;
-mod trusted_scraper;
-mod segment;
-mod text_processor;
-mod embedding_cache;
-
 use burn_tensor::{Tensor as BurnTensor, Distribution};
 use burn_wgpu::{Wgpu, WgpuDevice};
-use knowledge_graph::KnowledgeGraph;
+use metastasa::{attention_gpu, attention_logic, trusted_scraper};
+use metastasa::knowledge_graph::KnowledgeGraph;
 use sled;
-use tokio::runtime::Runtime;
-use segment::Segment;
-use text_processor::TextProcessor;
-use embedding_cache::EmbeddingCache;
+use metastasa::segment::Segment;
+use metastasa::text_processor::TextProcessor;
+use metastasa::embedding_cache::EmbeddingCache;
 
 #[tokio::main]
 async fn main() {
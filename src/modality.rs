@@ -0,0 +1,255 @@
+// modality.rs
+// Отрицание и модальность утверждений для "Мыслящего Ядро"
+//
+// Маркеры ("не", "возможно", "not", "probably") вырезаются из предложения перед
+// сопоставлением с шаблонами: "кот не ест рыбу" совпадает с тем же правилом, что
+// и "кот ест рыбу". Найденный факт получает полярность по маркерам отрицания внутри
+// своего фрагмента и модальность по маркерам от начала предложения до конца фрагмента.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use lazy_static::lazy_static;
+use regex::Regex;
+use crate::core::{Modality, Polarity};
+use crate::language::Language;
+
+/// Маркер в тексте
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cue {
+    Negation,
+    Modal(Modality),
+}
+
+const RUSSIAN_CUES: &[(&str, Cue)] = &[
+    ("не", Cue::Negation),
+    ("нет", Cue::Negation),
+    ("никогда", Cue::Negation),
+    ("вероятно", Cue::Modal(Modality::Probable)),
+    ("наверное", Cue::Modal(Modality::Probable)),
+    ("видимо", Cue::Modal(Modality::Probable)),
+    ("по-видимому", Cue::Modal(Modality::Probable)),
+    ("скорее всего", Cue::Modal(Modality::Probable)),
+    ("предположительно", Cue::Modal(Modality::Probable)),
+    ("возможно", Cue::Modal(Modality::Possible)),
+    ("может быть", Cue::Modal(Modality::Possible)),
+    ("вроде бы", Cue::Modal(Modality::Possible)),
+    ("якобы", Cue::Modal(Modality::Possible)),
+];
+
+const ENGLISH_CUES: &[(&str, Cue)] = &[
+    ("not", Cue::Negation),
+    ("never", Cue::Negation),
+    ("does not", Cue::Negation),
+    ("do not", Cue::Negation),
+    ("did not", Cue::Negation),
+    ("doesn't", Cue::Negation),
+    ("don't", Cue::Negation),
+    ("didn't", Cue::Negation),
+    ("cannot", Cue::Negation),
+    ("can not", Cue::Negation),
+    ("can't", Cue::Negation),
+    ("no", Cue::Negation),
+    ("probably", Cue::Modal(Modality::Probable)),
+    ("likely", Cue::Modal(Modality::Probable)),
+    ("presumably", Cue::Modal(Modality::Probable)),
+    ("apparently", Cue::Modal(Modality::Probable)),
+    ("possibly", Cue::Modal(Modality::Possible)),
+    ("perhaps", Cue::Modal(Modality::Possible)),
+    ("maybe", Cue::Modal(Modality::Possible)),
+    ("may", Cue::Modal(Modality::Possible)),
+    ("might", Cue::Modal(Modality::Possible)),
+    ("could", Cue::Modal(Modality::Possible)),
+];
+
+/// Глаголы, от которых при сокращении "n't" отрезается только отрицание ("isn't" → "is")
+const ENGLISH_CONTRACTED: &[&str] = &["is", "are", "was", "were", "has", "have", "had"];
+
+/// Предшествующие слова, после которых "may" — месяц ("in May", "since May")
+const MONTH_CONTEXT: &[&str] = &[
+    "in", "of", "since", "until", "till", "by", "from", "during", "early", "late", "mid", "next", "last", "this", "every",
+];
+
+/// Маркеры языка: выражение для поиска и значения
+struct CueSet {
+    regex: Regex,
+    cues: HashMap<&'static str, Cue>,
+    contraction: Option<Regex>, // Отрицание "n't", приросшее к глаголу
+}
+
+impl CueSet {
+    fn new(cues: &'static [(&'static str, Cue)], contracted: &[&str]) -> Self {
+        // Длинные маркеры раньше ("does not" прежде "not"); вместе с маркером уходят запятая и пробелы
+        let mut words: Vec<&str> = cues.iter().map(|&(w, _)| w).collect();
+        words.sort_by_key(|w| std::cmp::Reverse(w.len()));
+        let alternation = words.iter().map(|w| regex::escape(w).replace(' ', r"\s+")).collect::<Vec<_>>().join("|");
+        Self {
+            regex: Regex::new(&format!(r"(?i)\b({})\b,?\s*", alternation)).unwrap(),
+            cues: cues.iter().copied().collect(),
+            contraction: (!contracted.is_empty())
+                .then(|| Regex::new(&format!(r"(?i)\b(?:{})(n't)\b", contracted.join("|"))).unwrap()),
+        }
+    }
+
+    fn cue(&self, word: &str) -> Option<Cue> {
+        let word = word.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        self.cues.get(word.as_str()).copied()
+    }
+}
+
+lazy_static! {
+    static ref RUSSIAN: CueSet = CueSet::new(RUSSIAN_CUES, &[]);
+    static ref ENGLISH: CueSet = CueSet::new(ENGLISH_CUES, ENGLISH_CONTRACTED);
+}
+
+fn cue_set(language: Language) -> &'static CueSet {
+    match language {
+        Language::Russian => &RUSSIAN,
+        Language::English => &ENGLISH,
+    }
+}
+
+/// "may" — модальный глагол, а не месяц: с заглавной буквы пишется только в начале
+/// предложения, за ним идёт слово (глагол), а не число, и перед ним нет числа
+/// или предлога времени ("5 May", "in may 2023")
+fn is_modal_may(original: &str, range: Range<usize>) -> bool {
+    let before = original[..range.start].trim_end();
+    let sentence_start = before.is_empty() || before.ends_with(['.', '!', '?']);
+    if !sentence_start && original[range.clone()].starts_with('M') {
+        return false;
+    }
+    let previous = before.rsplit(|c: char| !c.is_alphanumeric()).next().unwrap_or("").to_lowercase();
+    let next = original[range.end..].trim_start().split(|c: char| !c.is_alphanumeric()).next().unwrap_or("");
+    next.starts_with(char::is_alphabetic)
+        && !previous.chars().any(|c| c.is_ascii_digit())
+        && !MONTH_CONTEXT.contains(&previous.as_str())
+}
+
+/// Текст без маркеров с привязкой к исходному и найденными маркерами
+#[derive(Debug, Clone)]
+pub struct MarkedText {
+    pub text: String,             // Текст без маркеров (по нему идёт сопоставление с шаблонами)
+    source: Vec<usize>,           // Для каждого байта `text` — смещение в исходном тексте
+    cues: Vec<(Range<usize>, Cue)>, // Маркеры (диапазоны исходного текста)
+}
+
+impl MarkedText {
+    /// Найти и вырезать маркеры языка
+    pub fn new(original: &str, language: Language) -> Self {
+        let set = cue_set(language);
+        let mut text = String::with_capacity(original.len());
+        let mut source = Vec::with_capacity(original.len());
+        let mut cues = Vec::new();
+        let mut last = 0;
+
+        // (вырезаемый фрагмент, маркер, значение) в порядке появления
+        let mut found: Vec<(Range<usize>, Range<usize>, Cue)> = Vec::new();
+        for cap in set.regex.captures_iter(original) {
+            let (whole, word) = (cap.get(0).unwrap(), cap.get(1).unwrap());
+            let Some(cue) = set.cue(word.as_str()) else { continue };
+            if word.as_str().eq_ignore_ascii_case("may") && !is_modal_may(original, word.range()) {
+                continue;
+            }
+            found.push((whole.range(), word.range(), cue));
+        }
+        if let Some(contraction) = &set.contraction {
+            for cap in contraction.captures_iter(original) {
+                let suffix = cap.get(1).unwrap().range();
+                found.push((suffix.clone(), suffix, Cue::Negation));
+            }
+            found.sort_by_key(|(cut, _, _)| cut.start);
+        }
+
+        for (cut, word, cue) in found {
+            text.push_str(&original[last..cut.start]);
+            source.extend(last..cut.start);
+            cues.push((word, cue));
+            last = cut.end;
+        }
+        text.push_str(&original[last..]);
+        source.extend(last..original.len());
+
+        Self { text, source, cues }
+    }
+
+    /// Диапазон исходного текста для диапазона текста без маркеров
+    pub fn source_range(&self, range: Range<usize>) -> Range<usize> {
+        if range.is_empty() {
+            let at = self.source.get(range.start).copied().unwrap_or_else(|| self.source.last().map_or(0, |&s| s + 1));
+            return at..at;
+        }
+        self.source[range.start]..self.source[range.end - 1] + 1
+    }
+
+    /// Найденные маркеры
+    pub fn cues(&self) -> &[(Range<usize>, Cue)] {
+        &self.cues
+    }
+
+    /// Полярность и модальность факта, найденного в диапазоне `range` исходного текста.
+    /// Нечётное число отрицаний внутри фрагмента — отрицание; из модальных маркеров
+    /// до конца фрагмента берётся наименее уверенный
+    pub fn assess(&self, range: Range<usize>) -> (Polarity, Modality) {
+        let negations = self
+            .cues
+            .iter()
+            .filter(|(r, cue)| *cue == Cue::Negation && r.start >= range.start && r.end <= range.end)
+            .count();
+        let polarity = if negations % 2 == 1 { Polarity::Negative } else { Polarity::Positive };
+
+        let modality = self
+            .cues
+            .iter()
+            .filter(|(r, _)| r.end <= range.end)
+            .filter_map(|(_, cue)| match cue {
+                Cue::Modal(m) => Some(*m),
+                Cue::Negation => None,
+            })
+            .min_by(|a, b| a.confidence_factor().total_cmp(&b.confidence_factor()))
+            .unwrap_or_default();
+
+        (polarity, modality)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markers_removed_and_assessed() {
+        let marked = MarkedText::new("возможно, кот не ест рыбу", Language::Russian);
+        assert_eq!(marked.text, "кот ест рыбу");
+        let range = marked.source_range(0..marked.text.len());
+        assert_eq!(range, "возможно, ".len().."возможно, кот не ест рыбу".len());
+        assert_eq!(marked.assess(range), (Polarity::Negative, Modality::Possible));
+
+        // "не" внутри слова — не маркер
+        assert!(MarkedText::new("кот ест немного рыбы", Language::Russian).cues().is_empty());
+
+        let marked = MarkedText::new("the cat does not eat fish", Language::English);
+        assert_eq!(marked.text, "the cat eat fish");
+        assert_eq!(marked.assess(marked.source_range(4..16)).0, Polarity::Negative);
+
+        let marked = MarkedText::new("the cat cannot eat fish", Language::English);
+        assert_eq!(marked.text, "the cat eat fish");
+        assert_eq!(marked.assess(marked.source_range(4..16)).0, Polarity::Negative);
+        let marked = MarkedText::new("a whale isn't a fish", Language::English);
+        assert_eq!(marked.text, "a whale is a fish");
+        assert_eq!(marked.assess(marked.source_range(0..marked.text.len())).0, Polarity::Negative);
+        assert_eq!(MarkedText::new("cats can't swim", Language::English).text, "cats swim");
+        assert_eq!(MarkedText::new("no cat eats grass", Language::English).cues().len(), 1);
+    }
+
+    #[test]
+    fn test_may_as_month_is_not_modal() {
+        let marked = MarkedText::new("cats may eat fish", Language::English);
+        assert_eq!(marked.text, "cats eat fish");
+        assert_eq!(marked.assess(marked.source_range(0..marked.text.len())).1, Modality::Possible);
+
+        for text in ["The cat was born in May 2023", "the cat was born on 5 may", "in may the cat ate fish", "born May, 2023"] {
+            let marked = MarkedText::new(text, Language::English);
+            assert!(marked.cues().is_empty(), "{}", text);
+            assert_eq!(marked.text, text);
+        }
+    }
+}
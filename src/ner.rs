@@ -0,0 +1,471 @@
+// ner.rs
+// Статистическое распознавание именованных сущностей для "Мыслящего Ядро"
+//
+// Усреднённый перцептрон с разметкой BIO ("B-Animal", "I-Location", "O") и жадным
+// декодированием слева направо. Признаки: слово, суффиксы и префиксы, соседние слова,
+// предыдущие метки. Обучается на размеченном корпусе (формат evaluation.rs), сохраняется
+// в JSON и подключается к TextProcessor этапом конвейера NerStage вместо словарей или
+// в дополнение к ним. Уверенность сущности — средняя softmax-вероятность меток её слов.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Serialize, Deserialize};
+use crate::evaluation::GoldDocument;
+use crate::language;
+use crate::pipeline::{Document, PipelineStage, StageError};
+use crate::text_processor::{Entity, EntityType, TextProcessor, TextSpan};
+
+/// Метка слова вне сущности
+pub const OUTSIDE: &str = "O";
+
+/// Граница предложения в признаках соседних слов и меток
+const BOUNDARY: &str = "<s>";
+
+lazy_static! {
+    static ref TOKEN: Regex = Regex::new(r"[\p{L}\p{N}]+(?:-[\p{L}\p{N}]+)*").unwrap();
+}
+
+/// Слова предложения с диапазонами (в байтах)
+pub fn tokenize(text: &str) -> Vec<(Range<usize>, String)> {
+    TOKEN.find_iter(text).map(|m| (m.range(), m.as_str().to_lowercase())).collect()
+}
+
+/// Ошибка загрузки или сохранения модели
+#[derive(Debug)]
+pub enum NerError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    Empty,
+    NoLabels,
+    LengthMismatch { sentence: usize, tokens: usize, labels: usize },
+}
+
+impl fmt::Display for NerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NerError::Io(e) => write!(f, "Ошибка чтения модели NER: {}", e),
+            NerError::Format(e) => write!(f, "Неверный формат модели NER: {}", e),
+            NerError::Empty => write!(f, "Нет размеченных предложений для обучения NER"),
+            NerError::NoLabels => write!(f, "В модели NER нет ни одной метки"),
+            NerError::LengthMismatch { sentence, tokens, labels } => write!(
+                f, "Предложение {}: {} слов, но {} меток", sentence, tokens, labels
+            ),
+        }
+    }
+}
+
+impl Error for NerError {}
+
+impl From<io::Error> for NerError {
+    fn from(e: io::Error) -> Self {
+        NerError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for NerError {
+    fn from(e: serde_json::Error) -> Self {
+        NerError::Format(e)
+    }
+}
+
+/// Предложение с метками BIO для обучения
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaggedSentence {
+    pub tokens: Vec<String>,
+    pub labels: Vec<String>,
+}
+
+/// Признаки слова `i` при уже выбранных метках предыдущих слов
+fn features(tokens: &[String], i: usize, prev: &str, prev2: &str) -> Vec<String> {
+    let word = tokens[i].as_str();
+    let chars: Vec<char> = word.chars().collect();
+    let affix = |n: usize, suffix: bool| -> String {
+        let n = n.min(chars.len());
+        if suffix { chars[chars.len() - n..].iter().collect() } else { chars[..n].iter().collect() }
+    };
+    let at = |offset: isize| -> &str {
+        let j = i as isize + offset;
+        if j < 0 { BOUNDARY } else { tokens.get(j as usize).map_or(BOUNDARY, |t| t.as_str()) }
+    };
+
+    let mut features = vec![
+        "bias".to_string(),
+        format!("w={}", word),
+        format!("s2={}", affix(2, true)),
+        format!("s3={}", affix(3, true)),
+        format!("p3={}", affix(3, false)),
+        format!("pw={}", at(-1)),
+        format!("ppw={}", at(-2)),
+        format!("nw={}", at(1)),
+        format!("pt={}", prev),
+        format!("pt2={}|{}", prev2, prev),
+        format!("pt+w={}|{}", prev, word),
+        format!("pw+s3={}|{}", at(-1), affix(3, true)),
+    ];
+    if chars.iter().all(|c| c.is_numeric()) {
+        features.push("digits".to_string());
+    }
+    if word.contains('-') {
+        features.push("hyphen".to_string());
+    }
+    features
+}
+
+/// Модель: метки и усреднённые веса признаков (по вектору на признак, элемент на метку)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NerModel {
+    pub labels: Vec<String>,
+    weights: HashMap<String, Vec<f32>>,
+}
+
+/// Состояние обучения усреднённого перцептрона
+struct Trainer {
+    labels: Vec<String>,
+    weights: HashMap<String, Vec<f32>>,
+    totals: HashMap<String, Vec<f32>>,   // Накопленные веса для усреднения
+    stamps: HashMap<String, Vec<usize>>, // Шаг последнего изменения веса
+    step: usize,
+}
+
+impl Trainer {
+    fn update(&mut self, feature: &str, label: usize, delta: f32) {
+        let n = self.labels.len();
+        let weights = self.weights.entry(feature.to_string()).or_insert_with(|| vec![0.0; n]);
+        let totals = self.totals.entry(feature.to_string()).or_insert_with(|| vec![0.0; n]);
+        let stamps = self.stamps.entry(feature.to_string()).or_insert_with(|| vec![0; n]);
+        totals[label] += (self.step - stamps[label]) as f32 * weights[label];
+        stamps[label] = self.step;
+        weights[label] += delta;
+    }
+
+    fn average(mut self) -> NerModel {
+        let step = self.step.max(1);
+        let mut weights = HashMap::new();
+        for (feature, values) in self.weights.drain() {
+            let totals = &self.totals[&feature];
+            let stamps = &self.stamps[&feature];
+            let averaged: Vec<f32> = values
+                .iter()
+                .enumerate()
+                .map(|(l, w)| (totals[l] + (step - stamps[l]) as f32 * w) / step as f32)
+                .collect();
+            if averaged.iter().any(|w| w.abs() > 1e-6) {
+                weights.insert(feature, averaged);
+            }
+        }
+        NerModel { labels: self.labels, weights }
+    }
+}
+
+impl NerModel {
+    /// Обучить модель на предложениях (`epochs` проходов в перемешанном порядке)
+    pub fn train(sentences: &[TaggedSentence], epochs: usize) -> Result<Self, NerError> {
+        if sentences.iter().all(|s| s.tokens.is_empty()) {
+            return Err(NerError::Empty);
+        }
+        if let Some((sentence, s)) = sentences.iter().enumerate().find(|(_, s)| s.labels.len() != s.tokens.len()) {
+            return Err(NerError::LengthMismatch { sentence, tokens: s.tokens.len(), labels: s.labels.len() });
+        }
+        let mut labels = vec![OUTSIDE.to_string()];
+        for label in sentences.iter().flat_map(|s| &s.labels) {
+            if !labels.contains(label) {
+                labels.push(label.clone());
+            }
+        }
+        let mut trainer = Trainer { labels, weights: HashMap::new(), totals: HashMap::new(), stamps: HashMap::new(), step: 0 };
+
+        // Детерминированное перемешивание (линейный конгруэнтный генератор)
+        let mut order: Vec<usize> = (0..sentences.len()).collect();
+        let mut seed: u64 = 0x2545F4914F6CDD1D;
+        for _ in 0..epochs {
+            for i in (1..order.len()).rev() {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                order.swap(i, (seed >> 33) as usize % (i + 1));
+            }
+            for &s in &order {
+                let sentence = &sentences[s];
+                let (mut prev, mut prev2) = (BOUNDARY.to_string(), BOUNDARY.to_string());
+                for i in 0..sentence.tokens.len() {
+                    trainer.step += 1;
+                    let features = features(&sentence.tokens, i, &prev, &prev2);
+                    let scores = score(&trainer.weights, trainer.labels.len(), &features);
+                    let guess = argmax(&scores);
+                    let truth = trainer.labels.iter().position(|l| *l == sentence.labels[i]).unwrap();
+                    if guess != truth {
+                        for feature in &features {
+                            trainer.update(feature, truth, 1.0);
+                            trainer.update(feature, guess, -1.0);
+                        }
+                    }
+                    prev2 = std::mem::replace(&mut prev, trainer.labels[guess].clone());
+                }
+            }
+        }
+        Ok(trainer.average())
+    }
+
+    /// Метки слов с уверенностью (softmax по оценкам меток)
+    pub fn tag(&self, tokens: &[String]) -> Vec<(String, f32)> {
+        let (mut prev, mut prev2) = (BOUNDARY.to_string(), BOUNDARY.to_string());
+        let mut tagged = Vec::with_capacity(tokens.len());
+        for i in 0..tokens.len() {
+            let scores = score(&self.weights, self.labels.len(), &features(tokens, i, &prev, &prev2));
+            let best = argmax(&scores);
+            let max = scores[best];
+            let sum: f32 = scores.iter().map(|s| (s - max).exp()).sum();
+            tagged.push((self.labels[best].clone(), 1.0 / sum));
+            prev2 = std::mem::replace(&mut prev, self.labels[best].clone());
+        }
+        tagged
+    }
+
+    /// Сущности предложения: диапазоны слов, тип и уверенность.
+    /// "I-X" без предшествующего "B-X" начинает новую сущность
+    pub fn entities(&self, text: &str) -> Vec<(Range<usize>, EntityType, f32)> {
+        let tokens = tokenize(text);
+        let words: Vec<String> = tokens.iter().map(|(_, w)| w.clone()).collect();
+        let mut found: Vec<(Range<usize>, String, Vec<f32>)> = Vec::new();
+
+        for ((range, _), (label, confidence)) in tokens.into_iter().zip(self.tag(&words)) {
+            let Some((prefix, kind)) = label.split_once('-') else { continue };
+            match found.last_mut() {
+                Some((last, last_kind, confidences)) if prefix == "I" && *last_kind == kind && last.end <= range.start => {
+                    last.end = range.end;
+                    confidences.push(confidence);
+                },
+                _ => found.push((range, kind.to_string(), vec![confidence])),
+            }
+        }
+
+        found
+            .into_iter()
+            .filter_map(|(range, kind, confidences)| {
+                let entity_type = serde_json::from_value(serde_json::Value::String(kind)).ok()?;
+                Some((range, entity_type, confidences.iter().sum::<f32>() / confidences.len() as f32))
+            })
+            .collect()
+    }
+
+    /// Доля слов с верной меткой
+    pub fn accuracy(&self, sentences: &[TaggedSentence]) -> f64 {
+        let (mut correct, mut total) = (0, 0);
+        for sentence in sentences {
+            for ((label, _), truth) in self.tag(&sentence.tokens).iter().zip(&sentence.labels) {
+                correct += (label == truth) as usize;
+                total += 1;
+            }
+        }
+        if total == 0 { 0.0 } else { correct as f64 / total as f64 }
+    }
+
+    pub fn feature_count(&self) -> usize {
+        self.weights.len()
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), NerError> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Загрузить модель; модель без меток отклоняется (разметка с ней невозможна)
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, NerError> {
+        let model: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if model.labels.is_empty() {
+            return Err(NerError::NoLabels);
+        }
+        Ok(model)
+    }
+}
+
+fn score(weights: &HashMap<String, Vec<f32>>, labels: usize, features: &[String]) -> Vec<f32> {
+    let mut scores = vec![0.0; labels];
+    for values in features.iter().filter_map(|f| weights.get(f)) {
+        for (score, w) in scores.iter_mut().zip(values) {
+            *score += w;
+        }
+    }
+    scores
+}
+
+fn argmax(scores: &[f32]) -> usize {
+    scores.iter().enumerate().fold(0, |best, (i, s)| if *s > scores[best] { i } else { best })
+}
+
+/// Разметка BIO по корпусу: эталонные сущности (кроме дат — их распознаёт temporal)
+/// ищутся среди слов предложения и их лемм
+pub fn prepare_corpus(processor: &TextProcessor, corpus: &[GoldDocument]) -> Vec<TaggedSentence> {
+    let mut sentences = Vec::new();
+    for document in corpus {
+        let language = language::detect_language(&document.text);
+        let entities: Vec<(Vec<String>, String)> = document
+            .entities
+            .iter()
+            .filter(|e| e.entity_type != EntityType::Date)
+            .map(|e| (tokenize(&e.text).into_iter().map(|(_, w)| w).collect(), format!("{:?}", e.entity_type)))
+            .collect();
+
+        // Словоформы, а не леммы: так же размечается текст при распознавании
+        for sentence in processor.sentences(&document.text) {
+            let tokens: Vec<String> = tokenize(&sentence.text).into_iter().map(|(_, w)| w).collect();
+            let lemmas: Vec<String> = tokens.iter().map(|w| processor.lemmatize(language, w)).collect();
+            let mut labels = vec![OUTSIDE.to_string(); tokens.len()];
+
+            for (words, kind) in entities.iter().filter(|(words, _)| !words.is_empty()) {
+                for start in 0..tokens.len().saturating_sub(words.len() - 1) {
+                    let end = start + words.len();
+                    let matches = (start..end).all(|i| tokens[i] == words[i - start] || lemmas[i] == words[i - start]);
+                    if matches && labels[start..end].iter().all(|l| l == OUTSIDE) {
+                        labels[start] = format!("B-{}", kind);
+                        labels[start + 1..end].iter_mut().for_each(|l| *l = format!("I-{}", kind));
+                    }
+                }
+            }
+            sentences.push(TaggedSentence { tokens, labels });
+        }
+    }
+    sentences
+}
+
+/// Этап конвейера: сущности по модели NER. По умолчанию заменяет словарные сущности
+/// (кроме дат), в режиме `merged` добавляет только не пересекающиеся с ними
+pub struct NerStage {
+    model: Arc<NerModel>,
+    min_confidence: f32,
+    merge: bool,
+}
+
+impl NerStage {
+    pub fn new(model: Arc<NerModel>) -> Self {
+        Self { model, min_confidence: 0.5, merge: false }
+    }
+
+    /// Оставить словарные сущности и добавлять найденные моделью рядом с ними
+    pub fn merged(mut self) -> Self {
+        self.merge = true;
+        self
+    }
+
+    /// Отбрасывать сущности с уверенностью ниже порога
+    pub fn with_min_confidence(mut self, min_confidence: f32) -> Self {
+        self.min_confidence = min_confidence;
+        self
+    }
+}
+
+impl PipelineStage for NerStage {
+    fn name(&self) -> &str {
+        "ner"
+    }
+
+    fn run(&self, processor: &TextProcessor, document: &mut Document) -> Result<(), StageError> {
+        let mut found = Vec::new();
+        for (id, sentence) in document.sentences() {
+            for (range, entity_type, confidence) in self.model.entities(sentence) {
+                if confidence < self.min_confidence {
+                    continue;
+                }
+                let text = tokenize(&sentence[range.clone()])
+                    .iter()
+                    .map(|(_, w)| processor.lemmatize(document.language, w))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                found.push(Entity {
+                    text,
+                    entity_type,
+                    confidence,
+                    time: None,
+                    span: document.locate(id, &TextSpan::new(sentence, range, 0)),
+                });
+            }
+        }
+
+        let entities = &mut document.result.entities;
+        if self.merge {
+            found.retain(|f| !entities.iter().any(|e| e.span.start < f.span.end && f.span.start < e.span.end));
+        } else {
+            entities.retain(|e| e.entity_type == EntityType::Date);
+            found.retain(|f| !entities.iter().any(|e| e.span.start < f.span.end && f.span.start < e.span.end));
+        }
+        entities.extend(found);
+        entities.sort_by_key(|e| e.span.start);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Предложение с метками по словам текста
+    fn tagged(text: &str, labels: &[&str]) -> TaggedSentence {
+        TaggedSentence {
+            tokens: tokenize(text).into_iter().map(|(_, w)| w).collect(),
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+        }
+    }
+
+    fn cities() -> Vec<TaggedSentence> {
+        let mut sentences = Vec::new();
+        for (animal, city) in [("кот", "москве"), ("пес", "омске"), ("кит", "томске"), ("лось", "перми"), ("еж", "самаре"), ("волк", "туле")] {
+            sentences.push(tagged(&format!("{} живет в {}", animal, city), &["B-Animal", "O", "O", "B-Location"]));
+            sentences.push(tagged(&format!("мы были в {} летом", city), &["O", "O", "O", "B-Location", "O"]));
+        }
+        sentences.push(tagged("мы едем в санкт-петербург", &["O", "O", "O", "B-Location"]));
+        sentences
+    }
+
+    #[test]
+    fn test_perceptron_generalizes_and_roundtrips() {
+        let model = NerModel::train(&cities(), 10).unwrap();
+        assert!(model.accuracy(&cities()) > 0.95);
+
+        // Незнакомый город после "в" — место
+        let found = model.entities("мы были в казани летом");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, 17..29);
+        assert_eq!(found[0].1, EntityType::Location);
+        assert!(found[0].2 > 0.0 && found[0].2 <= 1.0);
+
+        let path = std::env::temp_dir().join(format!("ner-{}.json", uuid::Uuid::new_v4()));
+        model.save(&path).unwrap();
+        let loaded = NerModel::load(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(loaded.tag(&tagged("кот живет в омске", &[]).tokens), model.tag(&tagged("кот живет в омске", &[]).tokens));
+        assert!(NerModel::train(&[], 1).is_err());
+    }
+
+    #[test]
+    fn test_invalid_training_data_and_model_rejected() {
+        let mismatched = tagged("кот живет в омске", &["B-Animal", "O"]);
+        assert!(matches!(
+            NerModel::train(&[mismatched], 1),
+            Err(NerError::LengthMismatch { sentence: 0, tokens: 4, labels: 2 })
+        ));
+
+        let path = std::env::temp_dir().join(format!("ner-{}.json", uuid::Uuid::new_v4()));
+        NerModel::default().save(&path).unwrap();
+        let loaded = NerModel::load(&path);
+        std::fs::remove_file(&path).ok();
+        assert!(matches!(loaded, Err(NerError::NoLabels)));
+    }
+
+    #[tokio::test]
+    async fn test_ner_stage_in_pipeline() {
+        let model = Arc::new(NerModel::train(&cities(), 10).unwrap());
+        let processor = TextProcessor::new().with_ner(NerStage::new(model));
+        let result = processor.process_text("Кот живет в Казани.").await;
+
+        let city = result.entities.iter().find(|e| e.entity_type == EntityType::Location).unwrap();
+        assert_eq!(city.span.text, "Казани");
+        assert_ne!(city.confidence, 0.8);
+        assert!(result.timings.iter().any(|t| t.stage == "ner"));
+    }
+}
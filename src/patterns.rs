@@ -0,0 +1,461 @@
+// patterns.rs
+// Наборы шаблонов извлечения для "Мыслящего Ядро"
+//
+// Словари сущностей, списки глаголов действий и шаблоны свойств описываются
+// в файлах TOML/JSON (по одному файлу на предметную область) и компилируются
+// в регулярные выражения. Там же лежат шаблоны вопросов для qa.rs: вопросы
+// на языке области разбираются теми же файлами, что и тексты.
+// Файлы отслеживаются по времени изменения, поэтому
+// набор можно править без перекомпиляции.
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use regex::Regex;
+use serde::{Serialize, Deserialize};
+use crate::text_processor::EntityType;
+use crate::language::Language;
+use crate::qa::QuestionKind;
+
+/// Встроенные наборы шаблонов (используются, пока не загружены файлы)
+const BUILTIN_PATTERNS: &[&str] = &[
+    include_str!("../patterns/default.toml"),
+    include_str!("../patterns/english.toml"),
+];
+
+/// Уверенность по умолчанию для каждого вида шаблонов
+const ENTITY_CONFIDENCE: f32 = 0.8;
+const ACTION_CONFIDENCE: f32 = 0.7;
+const PROPERTY_CONFIDENCE: f32 = 0.6;
+
+/// Ошибка загрузки или компиляции шаблонов
+#[derive(Debug)]
+pub enum PatternError {
+    Io(PathBuf, io::Error),
+    Toml(PathBuf, toml::de::Error),
+    Json(PathBuf, serde_json::Error),
+    UnsupportedFormat(PathBuf),
+    InvalidRegex { domain: String, pattern: String, message: String },
+    EmptyRule { domain: String },
+    GroupCount { domain: String, pattern: String, groups: usize },
+    InvalidName(String),
+    MissingGroup { domain: String, pattern: String, group: &'static str },
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternError::Io(path, e) => write!(f, "Ошибка чтения {}: {}", path.display(), e),
+            PatternError::Toml(path, e) => write!(f, "Ошибка разбора TOML {}: {}", path.display(), e),
+            PatternError::Json(path, e) => write!(f, "Ошибка разбора JSON {}: {}", path.display(), e),
+            PatternError::UnsupportedFormat(path) => write!(f, "Неподдерживаемый формат шаблонов: {}", path.display()),
+            PatternError::InvalidRegex { domain, pattern, message } => {
+                write!(f, "Область '{}': некорректное выражение '{}': {}", domain, pattern, message)
+            },
+            PatternError::EmptyRule { domain } => write!(f, "Область '{}': правило без слов и без шаблона", domain),
+            PatternError::GroupCount { domain, pattern, groups } => {
+                write!(f, "Область '{}': в выражении '{}' {} групп вместо трёх (субъект, глагол, объект)", domain, pattern, groups)
+            },
+            PatternError::InvalidName(name) => {
+                write!(f, "Недопустимое имя области '{}': разрешены латинские буквы, цифры, '_' и '-'", name)
+            },
+            PatternError::MissingGroup { domain, pattern, group } => {
+                write!(f, "Область '{}': в шаблоне вопроса '{}' нет группы '{}'", domain, pattern, group)
+            },
+        }
+    }
+}
+
+impl Error for PatternError {}
+
+/// Правило сущности: словарь слов или регулярное выражение
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntityRule {
+    #[serde(rename = "type")]
+    pub entity_type: EntityType,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
+}
+
+/// Правило связи (действия или свойства): список глаголов или выражение с тремя группами
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelationRule {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub verbs: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
+}
+
+/// Шаблон вопроса: выражение с именованными группами subject, verb, object
+/// (какие из них обязательны, зависит от типа вопроса)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuestionRule {
+    pub kind: QuestionKind,
+    pub pattern: String,
+}
+
+/// Шаблоны одной предметной области (содержимое одного файла)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DomainPatterns {
+    pub name: String,
+    #[serde(default)]
+    pub language: Language, // Язык текстов области (по умолчанию русский)
+    #[serde(default)]
+    pub entities: Vec<EntityRule>,
+    #[serde(default)]
+    pub actions: Vec<RelationRule>,
+    #[serde(default)]
+    pub properties: Vec<RelationRule>,
+    #[serde(default)]
+    pub questions: Vec<QuestionRule>,
+}
+
+impl DomainPatterns {
+    /// Разобрать файл шаблонов (формат по расширению: .toml или .json)
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, PatternError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|e| PatternError::Io(path.to_path_buf(), e))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::from_str(&content).map_err(|e| PatternError::Toml(path.to_path_buf(), e)),
+            Some("json") => serde_json::from_str(&content).map_err(|e| PatternError::Json(path.to_path_buf(), e)),
+            _ => Err(PatternError::UnsupportedFormat(path.to_path_buf())),
+        }
+    }
+}
+
+/// Скомпилированное правило сущности
+#[derive(Debug, Clone)]
+pub struct EntityPattern {
+    pub regex: Regex,
+    pub language: Language,
+    pub entity_type: EntityType,
+    pub confidence: f32,
+    pub gazetteer: bool, // Словарное правило: сопоставляется с лемматизированным текстом
+}
+
+/// Скомпилированное правило связи (группы: субъект, глагол, объект)
+#[derive(Debug, Clone)]
+pub struct RelationPattern {
+    pub regex: Regex,
+    pub language: Language,
+    pub confidence: f32,
+}
+
+/// Скомпилированный шаблон вопроса
+#[derive(Debug, Clone)]
+pub struct QuestionPattern {
+    pub regex: Regex,
+    pub language: Language,
+    pub kind: QuestionKind,
+}
+
+/// Откуда загружен набор: для горячей перезагрузки по времени изменения файлов
+#[derive(Debug, Clone)]
+struct PatternSource {
+    path: PathBuf,
+    modified: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+/// Активный набор шаблонов: области и их скомпилированные правила
+#[derive(Debug, Clone)]
+pub struct PatternSet {
+    pub domains: Vec<DomainPatterns>,
+    pub entities: Vec<EntityPattern>,
+    pub actions: Vec<RelationPattern>,
+    pub properties: Vec<RelationPattern>,
+    pub questions: Vec<QuestionPattern>,
+    pub vocabulary: HashSet<String>, // Слова словарей сущностей (известные леммы)
+    source: Option<PatternSource>,
+}
+
+fn invalid_regex(domain: &str, pattern: &str, e: regex::Error) -> PatternError {
+    PatternError::InvalidRegex { domain: domain.to_string(), pattern: pattern.to_string(), message: e.to_string() }
+}
+
+/// Альтернатива слов; пробелы внутри фразы ("состоит из") допускают любые пробельные символы
+fn alternation(words: &[String]) -> String {
+    words
+        .iter()
+        .map(|w| w.split_whitespace().map(regex::escape).collect::<Vec<_>>().join(r"\s+"))
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+fn compile_relation(domain: &DomainPatterns, rule: &RelationRule, default_confidence: f32) -> Result<RelationPattern, PatternError> {
+    let (language, domain) = (domain.language, domain.name.as_str());
+    let pattern = match (&rule.pattern, rule.verbs.is_empty()) {
+        (Some(pattern), _) => pattern.clone(),
+        (None, false) => format!(r"(\w+)\s+({})\s+(\w+)", alternation(&rule.verbs)),
+        (None, true) => return Err(PatternError::EmptyRule { domain: domain.to_string() }),
+    };
+    let regex = Regex::new(&pattern).map_err(|e| invalid_regex(domain, &pattern, e))?;
+    // Извлечение берёт cap[1..=3]: другое число групп привело бы к панике
+    if regex.captures_len() != 4 {
+        return Err(PatternError::GroupCount { domain: domain.to_string(), pattern, groups: regex.captures_len() - 1 });
+    }
+    Ok(RelationPattern { regex, language, confidence: rule.confidence.unwrap_or(default_confidence) })
+}
+
+/// Группы, без которых вопрос данного типа нельзя перевести в запрос
+fn required_groups(kind: &QuestionKind) -> &'static [&'static str] {
+    match kind {
+        QuestionKind::YesNo => &["subject", "verb", "object"],
+        QuestionKind::Definition | QuestionKind::Location => &["subject"],
+        QuestionKind::Subject => &["verb", "object"],
+        QuestionKind::Object => &["subject", "verb"],
+    }
+}
+
+fn compile_question(domain: &DomainPatterns, rule: &QuestionRule) -> Result<QuestionPattern, PatternError> {
+    let regex = Regex::new(&rule.pattern).map_err(|e| invalid_regex(&domain.name, &rule.pattern, e))?;
+    let names: HashSet<&str> = regex.capture_names().flatten().collect();
+    if let Some(group) = required_groups(&rule.kind).iter().copied().find(|g| !names.contains(g)) {
+        return Err(PatternError::MissingGroup { domain: domain.name.clone(), pattern: rule.pattern.clone(), group });
+    }
+    Ok(QuestionPattern { regex, language: domain.language, kind: rule.kind.clone() })
+}
+
+/// Имя области становится именем файла: только [A-Za-z0-9_-]
+fn is_valid_domain_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Файлы шаблонов пути: сам файл или все .toml/.json в каталоге (по имени)
+fn pattern_files(path: &Path) -> Result<Vec<PathBuf>, PatternError> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let entries = fs::read_dir(path).map_err(|e| PatternError::Io(path.to_path_buf(), e))?;
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| matches!(p.extension().and_then(|e| e.to_str()), Some("toml") | Some("json")))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn modification_times(path: &Path) -> Result<Vec<(PathBuf, Option<SystemTime>)>, PatternError> {
+    Ok(pattern_files(path)?
+        .into_iter()
+        .map(|file| {
+            let modified = fs::metadata(&file).and_then(|m| m.modified()).ok();
+            (file, modified)
+        })
+        .collect())
+}
+
+impl PatternSet {
+    /// Встроенный набор (patterns/default.toml и patterns/english.toml)
+    pub fn builtin() -> Self {
+        let domains = BUILTIN_PATTERNS
+            .iter()
+            .map(|content| toml::from_str(content).expect("встроенные шаблоны"))
+            .collect();
+        Self::from_domains(domains).expect("встроенные шаблоны")
+    }
+
+    /// Правила сущностей для языка
+    pub fn entities_for(&self, language: Language) -> impl Iterator<Item = &EntityPattern> {
+        self.entities.iter().filter(move |p| p.language == language)
+    }
+
+    /// Правила действий для языка
+    pub fn actions_for(&self, language: Language) -> impl Iterator<Item = &RelationPattern> {
+        self.actions.iter().filter(move |p| p.language == language)
+    }
+
+    /// Правила свойств для языка
+    pub fn properties_for(&self, language: Language) -> impl Iterator<Item = &RelationPattern> {
+        self.properties.iter().filter(move |p| p.language == language)
+    }
+
+    /// Шаблоны вопросов для языка (в порядке файлов: первый совпавший побеждает)
+    pub fn questions_for(&self, language: Language) -> impl Iterator<Item = &QuestionPattern> {
+        self.questions.iter().filter(move |p| p.language == language)
+    }
+
+    /// Скомпилировать набор из областей
+    pub fn from_domains(domains: Vec<DomainPatterns>) -> Result<Self, PatternError> {
+        let mut entities = Vec::new();
+        let mut actions = Vec::new();
+        let mut properties = Vec::new();
+        let mut questions = Vec::new();
+        let mut vocabulary = HashSet::new();
+
+        for domain in &domains {
+            for rule in &domain.entities {
+                let confidence = rule.confidence.unwrap_or(ENTITY_CONFIDENCE);
+                vocabulary.extend(rule.words.iter().map(|w| w.to_lowercase()));
+                if !rule.words.is_empty() {
+                    // Словарь: целые слова без учёта регистра
+                    let pattern = format!(r"(?i)\b(?:{})\b", alternation(&rule.words));
+                    let regex = Regex::new(&pattern).map_err(|e| invalid_regex(&domain.name, &pattern, e))?;
+                    entities.push(EntityPattern { regex, language: domain.language, entity_type: rule.entity_type.clone(), confidence, gazetteer: true });
+                }
+                if let Some(pattern) = &rule.pattern {
+                    let regex = Regex::new(pattern).map_err(|e| invalid_regex(&domain.name, pattern, e))?;
+                    entities.push(EntityPattern { regex, language: domain.language, entity_type: rule.entity_type.clone(), confidence, gazetteer: false });
+                }
+                if rule.words.is_empty() && rule.pattern.is_none() {
+                    return Err(PatternError::EmptyRule { domain: domain.name.clone() });
+                }
+            }
+            for rule in &domain.actions {
+                actions.push(compile_relation(domain, rule, ACTION_CONFIDENCE)?);
+            }
+            for rule in &domain.properties {
+                properties.push(compile_relation(domain, rule, PROPERTY_CONFIDENCE)?);
+            }
+            for rule in &domain.questions {
+                questions.push(compile_question(domain, rule)?);
+            }
+        }
+
+        Ok(Self { domains, entities, actions, properties, questions, vocabulary, source: None })
+    }
+
+    /// Загрузить набор из файла или каталога (каждый файл — отдельная область)
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, PatternError> {
+        let path = path.as_ref();
+        let modified = modification_times(path)?;
+        let domains = modified
+            .iter()
+            .map(|(file, _)| DomainPatterns::load(file))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut set = Self::from_domains(domains)?;
+        set.source = Some(PatternSource { path: path.to_path_buf(), modified });
+        Ok(set)
+    }
+
+    /// Путь, из которого загружен набор
+    pub fn source_path(&self) -> Option<&Path> {
+        self.source.as_ref().map(|s| s.path.as_path())
+    }
+
+    /// Перезагрузить набор, если файлы изменились (добавлены, удалены или изменены).
+    /// При ошибке текущий набор остаётся активным.
+    pub fn reload_if_changed(&mut self) -> Result<bool, PatternError> {
+        let Some(source) = &self.source else { return Ok(false) };
+        if modification_times(&source.path)? == source.modified {
+            return Ok(false);
+        }
+        *self = Self::load(source.path.clone())?;
+        Ok(true)
+    }
+
+    /// Добавить или заменить область. Если набор загружен из каталога,
+    /// область сохраняется в файл `<каталог>/<имя>.toml`, чтобы пережить перезагрузку.
+    pub fn upsert_domain(&mut self, domain: DomainPatterns) -> Result<(), PatternError> {
+        if !is_valid_domain_name(&domain.name) {
+            return Err(PatternError::InvalidName(domain.name));
+        }
+        let mut domains = self.domains.clone();
+        match domains.iter_mut().find(|d| d.name == domain.name) {
+            Some(existing) => *existing = domain.clone(),
+            None => domains.push(domain.clone()),
+        }
+        let mut updated = Self::from_domains(domains)?;
+
+        if let Some(source) = &self.source {
+            if source.path.is_dir() {
+                let file = source.path.join(format!("{}.toml", domain.name));
+                let content = toml::to_string_pretty(&domain).expect("сериализация шаблонов");
+                fs::write(&file, content).map_err(|e| PatternError::Io(file.clone(), e))?;
+                updated.source = Some(PatternSource { path: source.path.clone(), modified: modification_times(&source.path)? });
+            } else {
+                // Одиночный файл не перезаписывается: изменение действует до его правки
+                updated.source = self.source.clone();
+            }
+        }
+
+        *self = updated;
+        Ok(())
+    }
+}
+
+impl Default for PatternSet {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_patterns_types() {
+        let set = PatternSet::builtin();
+        let cat = set.entities.iter().find(|p| p.regex.is_match("Кот")).unwrap();
+        assert_eq!(cat.entity_type, EntityType::Animal);
+        let tree = set.entities.iter().find(|p| p.regex.is_match("дерево")).unwrap();
+        assert_eq!(tree.entity_type, EntityType::Plant);
+        // Словарь сопоставляет целые слова
+        assert!(!cat.regex.is_match("котлета"));
+        assert!(set.properties.iter().any(|p| p.regex.is_match("вода состоит  из молекул")));
+
+        // Английские правила не применяются к русским текстам и наоборот
+        assert!(set.entities_for(Language::English).any(|p| p.regex.is_match("cat")));
+        assert!(!set.entities_for(Language::Russian).any(|p| p.regex.is_match("cat")));
+    }
+
+    #[test]
+    fn test_load_directory_and_hot_reload() {
+        let dir = std::env::temp_dir().join(format!("metastasa_patterns_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("medicine.json"), r#"{
+            "name": "medicine",
+            "entities": [{"type": "ScientificTerm", "words": ["грипп"]}],
+            "actions": [{"verbs": ["лечит"], "confidence": 0.9}]
+        }"#).unwrap();
+
+        let mut set = PatternSet::load(&dir).unwrap();
+        assert_eq!(set.domains.len(), 1);
+        assert_eq!(set.actions[0].confidence, 0.9);
+        assert!(!set.reload_if_changed().unwrap());
+
+        // Новый файл области подхватывается без перезапуска
+        fs::write(dir.join("zoology.toml"), "name = \"zoology\"\n[[entities]]\ntype = \"Animal\"\nwords = [\"ёж\"]\n").unwrap();
+        assert!(set.reload_if_changed().unwrap());
+        assert_eq!(set.domains.len(), 2);
+
+        // Изменение через API сохраняется в каталог
+        let mut botany = DomainPatterns { name: "botany".into(), language: Language::Russian, entities: vec![], actions: vec![], properties: vec![], questions: vec![] };
+        botany.entities.push(EntityRule { entity_type: EntityType::Plant, words: vec!["мох".into()], pattern: None, confidence: None });
+        set.upsert_domain(botany).unwrap();
+        assert!(dir.join("botany.toml").exists());
+        assert!(!set.reload_if_changed().unwrap());
+        assert_eq!(PatternSet::load(&dir).unwrap().domains.len(), 3);
+
+        let broken = DomainPatterns { name: "broken".into(), language: Language::Russian, entities: vec![], actions: vec![RelationRule { verbs: vec![], pattern: Some("(".into()), confidence: None }], properties: vec![], questions: vec![] };
+        assert!(matches!(set.upsert_domain(broken), Err(PatternError::InvalidRegex { .. })));
+        assert_eq!(set.domains.len(), 3);
+
+        // Выражение связи обязано иметь три группы: субъект, глагол, объект
+        let two_groups = DomainPatterns { name: "two_groups".into(), language: Language::Russian, entities: vec![], actions: vec![RelationRule { verbs: vec![], pattern: Some(r"(\w+)\s+ест\s+(\w+)".into()), confidence: None }], properties: vec![], questions: vec![] };
+        assert!(matches!(set.upsert_domain(two_groups), Err(PatternError::GroupCount { groups: 2, .. })));
+
+        // Шаблон вопроса обязан содержать группы, нужные его типу
+        let question = QuestionRule { kind: QuestionKind::Object, pattern: r"^what\s+does\s+(?P<subject>\w+)\s+\w+$".into() };
+        let no_verb = DomainPatterns { name: "no_verb".into(), language: Language::English, entities: vec![], actions: vec![], properties: vec![], questions: vec![question] };
+        assert!(matches!(set.upsert_domain(no_verb), Err(PatternError::MissingGroup { group: "verb", .. })));
+
+        // Имя области не может выйти за пределы каталога
+        let escape = DomainPatterns { name: "../escape".into(), language: Language::Russian, entities: vec![], actions: vec![], properties: vec![], questions: vec![] };
+        assert!(matches!(set.upsert_domain(escape), Err(PatternError::InvalidName(_))));
+        assert!(!dir.parent().unwrap().join("escape.toml").exists());
+        assert_eq!(set.domains.len(), 3);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,416 @@
+// pipeline.rs
+// Настраиваемый конвейер обработки текста для "Мыслящего Ядро"
+//
+// Предобработка (очистка, разбиение на предложения, определение языка) создаёт Document,
+// затем по порядку выполняются этапы (PipelineStage): сущности → действия → свойства →
+// кореференция. Этапы можно вставлять (доменный NER, фильтрация, обогащение, запись в граф),
+// удалять и отключать для отдельного запроса; время каждого этапа попадает в результат.
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Instant;
+use chrono::NaiveDate;
+use serde::{Serialize, Deserialize};
+use crate::core::Source;
+use crate::coreference;
+use crate::language::{self, Language};
+use crate::lemmatizer::LemmatizedText;
+use crate::text_processor::{NormalizedText, ProcessingResult, Sentence, TextProcessor, TextSpan};
+
+/// Имя предобработки в замерах времени (не отключается: без неё нет предложений)
+pub const PREPROCESS: &str = "preprocess";
+
+/// Ошибка выполнения этапа (любая: этапы пишут сторонние команды)
+pub type StageError = Box<dyn Error + Send + Sync>;
+
+/// Этап конвейера: дополняет или исправляет результат обработки документа
+pub trait PipelineStage: Send + Sync {
+    /// Уникальное имя этапа (для настройки, отключения и замеров)
+    fn name(&self) -> &str;
+
+    /// Выполнить этап над документом
+    fn run(&self, processor: &TextProcessor, document: &mut Document) -> Result<(), StageError>;
+}
+
+/// Документ в процессе обработки: исходный текст, предложения и накопленный результат
+pub struct Document<'a> {
+    pub text: &'a str, // Исходный текст (спаны результата указывают в него)
+    pub document_date: NaiveDate,
+    pub language: Language,
+    pub result: ProcessingResult,
+    normalized: Vec<NormalizedText>,
+}
+
+impl<'a> Document<'a> {
+    /// Предобработка: очистка от HTML, разбиение на предложения и определение языка
+    pub fn new(processor: &TextProcessor, text: &'a str, document_date: NaiveDate) -> Self {
+        let normalized = processor.normalized_sentences(text);
+        let language = language::detect_language(&normalized.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" "));
+        let sentences = normalized
+            .iter()
+            .enumerate()
+            .map(|(id, sentence)| Sentence {
+                id,
+                text: sentence.text.clone(),
+                span: TextSpan::new(text, sentence.source_range(0..sentence.text.len()), id),
+            })
+            .collect();
+
+        Self {
+            text,
+            document_date,
+            language,
+            result: ProcessingResult {
+                language,
+                document_date: Some(document_date),
+                sentences,
+                ..ProcessingResult::default()
+            },
+            normalized,
+        }
+    }
+
+    /// Нормализованные предложения (нижний регистр, без разметки) с номерами
+    pub fn sentences(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.normalized.iter().map(|s| s.text.as_str()).enumerate()
+    }
+
+    /// Леммы предложения, полученные при предобработке
+    pub fn lemmas(&self, sentence_id: usize) -> &LemmatizedText {
+        &self.normalized[sentence_id].lemmas
+    }
+
+    /// Перевести фрагмент, отсчитанный от начала предложения, в исходный текст
+    pub fn locate(&self, sentence_id: usize, span: &TextSpan) -> TextSpan {
+        TextSpan::new(self.text, self.normalized[sentence_id].source_range(span.start..span.end), sentence_id)
+    }
+}
+
+/// Ошибка настройки конвейера
+#[derive(Debug, Clone, PartialEq)]
+pub enum PipelineError {
+    UnknownStage(String),
+    DuplicateStage(String),
+}
+
+impl fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PipelineError::UnknownStage(name) => write!(f, "Неизвестный этап конвейера: {}", name),
+            PipelineError::DuplicateStage(name) => write!(f, "Этап конвейера уже существует: {}", name),
+        }
+    }
+}
+
+impl Error for PipelineError {}
+
+/// Время выполнения этапа
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageTiming {
+    pub stage: String,
+    pub micros: u64,
+    #[serde(default)]
+    pub error: Option<String>, // Этап завершился ошибкой (результат остальных этапов сохраняется)
+}
+
+/// Какие этапы выполнять в конкретном запросе
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StageSelection {
+    #[serde(default)]
+    pub only: Option<Vec<String>>, // Выполнить только эти этапы (в порядке конвейера)
+    #[serde(default)]
+    pub skip: Vec<String>,         // Пропустить эти этапы
+}
+
+impl StageSelection {
+    /// Пропустить перечисленные этапы
+    pub fn skip(stages: &[&str]) -> Self {
+        Self { only: None, skip: stages.iter().map(|s| s.to_string()).collect() }
+    }
+
+    fn enabled(&self, stage: &str) -> bool {
+        self.only.as_ref().is_none_or(|only| only.iter().any(|s| s == stage)) && !self.skip.iter().any(|s| s == stage)
+    }
+}
+
+/// Упорядоченный набор этапов
+#[derive(Clone)]
+pub struct Pipeline {
+    stages: Vec<Arc<dyn PipelineStage>>,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.stage_names()).finish()
+    }
+}
+
+impl Pipeline {
+    /// Пустой конвейер (только предобработка)
+    pub fn empty() -> Self {
+        Self { stages: Vec::new() }
+    }
+
+    /// Стандартный конвейер: entities → actions → properties → coreference
+    pub fn standard() -> Self {
+        Self {
+            stages: vec![
+                Arc::new(EntityStage),
+                Arc::new(ActionStage),
+                Arc::new(PropertyStage),
+                Arc::new(CoreferenceStage),
+            ],
+        }
+    }
+
+    /// Имена этапов по порядку
+    pub fn stage_names(&self) -> Vec<&str> {
+        self.stages.iter().map(|s| s.name()).collect()
+    }
+
+    fn position(&self, name: &str) -> Result<usize, PipelineError> {
+        self.stages.iter().position(|s| s.name() == name).ok_or_else(|| PipelineError::UnknownStage(name.to_string()))
+    }
+
+    fn check_unique(&self, stage: &dyn PipelineStage) -> Result<(), PipelineError> {
+        if self.stages.iter().any(|s| s.name() == stage.name()) {
+            return Err(PipelineError::DuplicateStage(stage.name().to_string()));
+        }
+        Ok(())
+    }
+
+    /// Добавить этап в конец
+    pub fn push(&mut self, stage: Arc<dyn PipelineStage>) -> Result<(), PipelineError> {
+        self.check_unique(stage.as_ref())?;
+        self.stages.push(stage);
+        Ok(())
+    }
+
+    /// Вставить этап перед этапом `name`
+    pub fn insert_before(&mut self, name: &str, stage: Arc<dyn PipelineStage>) -> Result<(), PipelineError> {
+        self.check_unique(stage.as_ref())?;
+        let at = self.position(name)?;
+        self.stages.insert(at, stage);
+        Ok(())
+    }
+
+    /// Вставить этап после этапа `name`
+    pub fn insert_after(&mut self, name: &str, stage: Arc<dyn PipelineStage>) -> Result<(), PipelineError> {
+        self.check_unique(stage.as_ref())?;
+        let at = self.position(name)?;
+        self.stages.insert(at + 1, stage);
+        Ok(())
+    }
+
+    /// Удалить этап
+    pub fn remove(&mut self, name: &str) -> Result<Arc<dyn PipelineStage>, PipelineError> {
+        let at = self.position(name)?;
+        Ok(self.stages.remove(at))
+    }
+
+    /// Проверить, что выбор ссылается только на существующие этапы
+    pub fn validate(&self, selection: &StageSelection) -> Result<(), PipelineError> {
+        let names: HashSet<&str> = self.stage_names().into_iter().collect();
+        let unknown = selection
+            .only
+            .iter()
+            .flatten()
+            .chain(&selection.skip)
+            .find(|s| !names.contains(s.as_str()));
+        match unknown {
+            Some(name) => Err(PipelineError::UnknownStage(name.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Обработать текст: предобработка, включённые этапы по порядку, затем сводка
+    /// (сегменты, узлы и связи). Ошибка этапа записывается в замеры и не прерывает конвейер
+    pub fn run(&self, processor: &TextProcessor, text: &str, document_date: NaiveDate, selection: &StageSelection) -> ProcessingResult {
+        let started = Instant::now();
+        let mut document = Document::new(processor, text, document_date);
+        let mut timings = vec![StageTiming { stage: PREPROCESS.to_string(), micros: elapsed_micros(started), error: None }];
+
+        for stage in self.stages.iter().filter(|s| selection.enabled(s.name())) {
+            let started = Instant::now();
+            let error = stage.run(processor, &mut document).err().map(|e| e.to_string());
+            if let Some(e) = &error {
+                println!("⚠️ Этап конвейера {} завершился ошибкой: {}", stage.name(), e);
+            }
+            timings.push(StageTiming { stage: stage.name().to_string(), micros: elapsed_micros(started), error });
+        }
+
+        let mut result = document.result;
+        result.summarize();
+        result.timings = timings;
+        result
+    }
+}
+
+fn elapsed_micros(started: Instant) -> u64 {
+    started.elapsed().as_micros() as u64
+}
+
+/// Сущности по словарям и правилам, даты относительно даты документа
+pub struct EntityStage;
+
+impl PipelineStage for EntityStage {
+    fn name(&self) -> &str {
+        "entities"
+    }
+
+    fn run(&self, processor: &TextProcessor, document: &mut Document) -> Result<(), StageError> {
+        let mut found = Vec::new();
+        for (id, sentence) in document.sentences() {
+            let lemmas = document.lemmas(id);
+            for mut entity in processor.extract_entities_lemmatized(sentence, lemmas, document.language, document.document_date) {
+                entity.span = document.locate(id, &entity.span);
+                found.push(entity);
+            }
+        }
+        document.result.entities.extend(found);
+        Ok(())
+    }
+}
+
+/// Действия (субъект-глагол-объект) с полярностью и модальностью
+pub struct ActionStage;
+
+impl PipelineStage for ActionStage {
+    fn name(&self) -> &str {
+        "actions"
+    }
+
+    fn run(&self, processor: &TextProcessor, document: &mut Document) -> Result<(), StageError> {
+        let mut found = Vec::new();
+        for (id, sentence) in document.sentences() {
+            for mut action in processor.extract_actions_in(sentence, document.language) {
+                action.span = document.locate(id, &action.span);
+                found.push(action);
+            }
+        }
+        document.result.actions.extend(found);
+        Ok(())
+    }
+}
+
+/// Свойства по шаблонам и числовые величины (числа внутри найденных дат пропускаются)
+pub struct PropertyStage;
+
+impl PipelineStage for PropertyStage {
+    fn name(&self) -> &str {
+        "properties"
+    }
+
+    fn run(&self, processor: &TextProcessor, document: &mut Document) -> Result<(), StageError> {
+        let mut found = Vec::new();
+        for (id, sentence) in document.sentences() {
+            let entities: Vec<_> = document.result.entities.iter().filter(|e| e.span.sentence_id == id).cloned().collect();
+            let locate = |span: &TextSpan| document.locate(id, span);
+            let mut properties = processor.extract_pattern_properties(sentence, document.language);
+            properties.iter_mut().for_each(|p| p.span = locate(&p.span));
+            properties.extend(processor.extract_quantities_with(sentence, document.language, &entities, locate));
+            found.extend(properties);
+        }
+        document.result.properties.extend(found);
+        Ok(())
+    }
+}
+
+/// Замена местоимений на антецеденты в действиях и свойствах
+pub struct CoreferenceStage;
+
+impl PipelineStage for CoreferenceStage {
+    fn name(&self) -> &str {
+        "coreference"
+    }
+
+    fn run(&self, _processor: &TextProcessor, document: &mut Document) -> Result<(), StageError> {
+        let result = &mut document.result;
+        let resolved = coreference::resolve(document.language, &result.entities, &mut result.actions, &mut result.properties);
+        result.coreferences.extend(resolved);
+        Ok(())
+    }
+}
+
+/// Запись результата в общий граф знаний без верификации (для доверенных источников).
+/// Не входит в стандартный конвейер
+pub struct GraphLinkStage {
+    pub source: Source,
+}
+
+impl PipelineStage for GraphLinkStage {
+    fn name(&self) -> &str {
+        "graph"
+    }
+
+    fn run(&self, processor: &TextProcessor, document: &mut Document) -> Result<(), StageError> {
+        let graph = processor.graph();
+        let mut graph = graph.lock().map_err(|_| "граф знаний недоступен")?;
+        processor.link_into_graph(&document.result, &mut graph, self.source)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text_processor::EntityType;
+
+    /// Доменный этап: отбрасывает сущности типа Unknown
+    struct DropUnknown;
+
+    impl PipelineStage for DropUnknown {
+        fn name(&self) -> &str {
+            "drop-unknown"
+        }
+
+        fn run(&self, _processor: &TextProcessor, document: &mut Document) -> Result<(), StageError> {
+            document.result.entities.retain(|e| e.entity_type != EntityType::Unknown);
+            Ok(())
+        }
+    }
+
+    struct Failing;
+
+    impl PipelineStage for Failing {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        fn run(&self, _processor: &TextProcessor, _document: &mut Document) -> Result<(), StageError> {
+            Err("нет связи со словарём".into())
+        }
+    }
+
+    #[test]
+    fn test_custom_stages_and_selection() {
+        let mut pipeline = Pipeline::standard();
+        pipeline.insert_after("entities", Arc::new(DropUnknown)).unwrap();
+        pipeline.push(Arc::new(Failing)).unwrap();
+        assert_eq!(pipeline.stage_names(), vec!["entities", "drop-unknown", "actions", "properties", "coreference", "failing"]);
+        assert_eq!(pipeline.push(Arc::new(Failing)), Err(PipelineError::DuplicateStage("failing".to_string())));
+        assert!(pipeline.insert_before("ner", Arc::new(EntityStage)).is_err());
+
+        let processor = TextProcessor::new().with_pipeline(pipeline);
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let result = processor.pipeline().run(&processor, "Кот ест рыбу.", date, &StageSelection::default());
+        let stages: Vec<&str> = result.timings.iter().map(|t| t.stage.as_str()).collect();
+        assert_eq!(stages, vec![PREPROCESS, "entities", "drop-unknown", "actions", "properties", "coreference", "failing"]);
+        assert_eq!(result.timings.last().unwrap().error.as_deref(), Some("нет связи со словарём"));
+        assert_eq!(result.graph_edges, vec![("кот".to_string(), "ест".to_string(), "рыба".to_string())]);
+
+        let selection = StageSelection::skip(&["actions"]);
+        let result = processor.pipeline().run(&processor, "Кот ест рыбу.", date, &selection);
+        assert!(result.actions.is_empty());
+        assert!(!result.entities.is_empty());
+        assert!(processor.pipeline().validate(&StageSelection::skip(&["ner"])).is_err());
+    }
+}
@@ -0,0 +1,285 @@
+// qa.rs
+// Ответы на вопросы на естественном языке по графу знаний "Мыслящего Ядро"
+//
+// Вопрос разбирается шаблонами вопросов из файлов шаблонов (раздел [[questions]]
+// рядом с правилами действий) для языка вопроса и переводится в запрос graph_query.
+// Словоформы нормализуются лемматизатором TextProcessor, как и при извлечении фактов.
+
+use serde::{Serialize, Deserialize};
+use crate::core::Polarity;
+use crate::graph_query::{Filter, MatchedEdge, Query, Term, TriplePattern};
+use crate::knowledge_graph::KnowledgeGraph;
+use crate::language::{self, Language};
+use crate::text_processor::TextProcessor;
+
+/// Переменная, в которую попадает ответ
+const ANSWER_VAR: &str = "ответ";
+
+/// Тип вопроса
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum QuestionKind {
+    Object,     // "Что ест кот?"
+    Subject,    // "Кто ест рыбу?"
+    Location,   // "Где водится рыба?"
+    Definition, // "Что такое кот?"
+    YesNo,      // "Ест ли кот рыбу?"
+}
+
+/// Разобранный вопрос: тип и шаблоны для поиска в графе
+#[derive(Debug, Clone)]
+pub struct ParsedQuestion {
+    pub kind: QuestionKind,
+    pub language: Language,
+    pub query: Query,
+}
+
+/// Вариант ответа с подтверждающими рёбрами и их источниками
+#[derive(Debug, Clone, Serialize)]
+pub struct AnswerItem {
+    pub text: String,
+    pub confidence: f32,
+    pub edges: Vec<MatchedEdge>,
+}
+
+/// Ответ на вопрос
+#[derive(Debug, Clone, Serialize)]
+pub struct Answer {
+    pub question: String,
+    pub understood: bool,
+    pub kind: Option<QuestionKind>,
+    pub answers: Vec<AnswerItem>,
+}
+
+/// Нормализация вопроса: нижний регистр, без знаков препинания и лишних пробелов
+fn normalize_question(question: &str) -> String {
+    question
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn var(name: &str) -> Term {
+    Term::Var(name.to_string())
+}
+
+fn single(subject: Term, predicate: Term, object: Term, filters: Vec<Filter>) -> Query {
+    Query {
+        patterns: vec![TriplePattern { subject, predicate, object }],
+        filters,
+        order: None,
+        limit: None,
+    }
+}
+
+/// Разобрать вопрос по шаблонам вопросов его языка; None — вопрос не распознан.
+/// Словоформы приводятся к леммам так же, как при извлечении фактов из текста
+pub fn parse_question(question: &str, processor: &TextProcessor) -> Option<ParsedQuestion> {
+    let q = normalize_question(question);
+    let language = language::detect_language(&q);
+    let patterns = processor.patterns();
+    let patterns = patterns.lock().unwrap();
+
+    let (kind, cap) = patterns
+        .questions_for(language)
+        .find_map(|p| p.regex.captures(&q).map(|cap| (p.kind.clone(), cap)))?;
+    let group = |name: &str| cap.name(name).map_or("", |m| m.as_str());
+    // Узел: лемма с учётом словарей сущностей ("рыбу" → "рыба", "cats" → "cat")
+    let node = |name: &str| Term::Literal(processor.lemma(&patterns, language, group(name)));
+    // Связь: глагол приводится к инфинитиву ("едят" → "есть");
+    // метки рёбер сравниваются с ним с точностью до формы глагола
+    let verb = |name: &str| Term::Literal(processor.lemmatizer(language).lemmatize_verb(group(name)));
+
+    let query = match kind {
+        QuestionKind::YesNo => single(node("subject"), verb("verb"), node("object"), vec![Filter::Polarity(None)]),
+        QuestionKind::Definition => single(node("subject"), var("связь"), var(ANSWER_VAR), vec![Filter::Kind("IsA".into())]),
+        QuestionKind::Location => single(node("subject"), var("связь"), var(ANSWER_VAR), vec![Filter::Kind("Location".into())]),
+        QuestionKind::Subject => single(var(ANSWER_VAR), verb("verb"), node("object"), vec![]),
+        QuestionKind::Object => single(node("subject"), verb("verb"), var(ANSWER_VAR), vec![]),
+    };
+
+    Some(ParsedQuestion { kind, language, query })
+}
+
+/// Ответить на вопрос по графу знаний
+pub fn answer_question(question: &str, processor: &TextProcessor, kg: &KnowledgeGraph) -> Answer {
+    let Some(parsed) = parse_question(question, processor) else {
+        return Answer {
+            question: question.to_string(),
+            understood: false,
+            kind: None,
+            answers: Vec::new(),
+        };
+    };
+
+    let rows = match parsed.query.execute(kg) {
+        Ok(result) => result.rows,
+        Err(e) => {
+            println!("⚠️ Не удалось выполнить запрос для вопроса «{}»: {}", question, e);
+            Vec::new()
+        },
+    };
+    let (yes, no) = match parsed.language {
+        Language::Russian => ("да", "нет"),
+        Language::English => ("yes", "no"),
+    };
+    let mut answers: Vec<AnswerItem> = Vec::new();
+
+    for row in rows {
+        // Для вопросов да/нет ответ — наличие связи и её полярность
+        let text = match parsed.kind {
+            QuestionKind::YesNo if row.edges.iter().any(|e| e.polarity == Polarity::Negative) => no.to_string(),
+            QuestionKind::YesNo => yes.to_string(),
+            _ => match row.bindings.get(ANSWER_VAR) {
+                Some(text) => text.clone(),
+                None => continue,
+            },
+        };
+        let confidence = row.edges.iter().map(|e| e.confidence).fold(1.0, f32::min);
+
+        match answers.iter_mut().find(|a| a.text == text) {
+            Some(existing) => {
+                existing.confidence = existing.confidence.max(confidence);
+                existing.edges.extend(row.edges);
+            },
+            None => answers.push(AnswerItem { text, confidence, edges: row.edges }),
+        }
+    }
+
+    answers.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+
+    Answer {
+        question: question.to_string(),
+        understood: true,
+        kind: Some(parsed.kind),
+        answers,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{EdgeType, Source};
+    use crate::segment::Edge;
+    use crate::patterns::{DomainPatterns, EntityRule, PatternSet, QuestionRule};
+    use crate::text_processor::EntityType;
+
+    fn sample_graph() -> KnowledgeGraph {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let mut kg = KnowledgeGraph::new(db);
+        let cat = kg.upsert_primitive("кот");
+        let fish = kg.upsert_primitive("рыба");
+        let sea = kg.upsert_primitive("море");
+        let animal = kg.upsert_primitive("животное");
+        kg.add_edge(cat, fish, Edge::new(EdgeType::Action, "ест", 0.7, Source::Wikipedia));
+        kg.add_edge(fish, sea, Edge::new(EdgeType::Location, "водится", 0.6, Source::ArXiv));
+        kg.add_edge(cat, animal, Edge::new(EdgeType::IsA, "является", 0.9, Source::Wikipedia));
+        kg
+    }
+
+    #[test]
+    fn test_parse_question_kinds() {
+        let processor = TextProcessor::new();
+        assert_eq!(parse_question("Что ест кот?", &processor).unwrap().kind, QuestionKind::Object);
+        assert_eq!(parse_question("Кто ест рыба?", &processor).unwrap().kind, QuestionKind::Subject);
+        assert_eq!(parse_question("Где водится рыба?", &processor).unwrap().kind, QuestionKind::Location);
+        assert_eq!(parse_question("Что такое кот?", &processor).unwrap().kind, QuestionKind::Definition);
+        assert_eq!(parse_question("Ест ли кот рыба?", &processor).unwrap().kind, QuestionKind::YesNo);
+        assert!(parse_question("Расскажи что-нибудь", &processor).is_none());
+    }
+
+    #[test]
+    fn test_answer_with_supporting_edges() {
+        let processor = TextProcessor::new();
+        let kg = sample_graph();
+
+        let answer = answer_question("Что ест кот?", &processor, &kg);
+        assert!(answer.understood);
+        assert_eq!(answer.answers.len(), 1);
+        assert_eq!(answer.answers[0].text, "рыба");
+        assert_eq!(answer.answers[0].edges[0].source, Source::Wikipedia);
+
+        let answer = answer_question("Где водится рыба?", &processor, &kg);
+        assert_eq!(answer.answers[0].text, "море");
+        assert_eq!(answer.answers[0].edges[0].source, Source::ArXiv);
+
+        let answer = answer_question("Что такое кот?", &processor, &kg);
+        assert_eq!(answer.answers[0].text, "животное");
+
+        let answer = answer_question("Ест ли кот рыбу?", &processor, &kg);
+        assert_eq!(answer.answers[0].text, "да");
+
+        let answer = answer_question("Кто ест рыбу?", &processor, &kg);
+        assert_eq!(answer.answers[0].text, "кот");
+
+        // Форма глагола в вопросе не обязана совпадать с меткой ребра
+        let answer = answer_question("Что едят коты?", &processor, &kg);
+        assert_eq!(answer.answers[0].text, "рыба");
+        assert_eq!(answer.answers[0].edges[0].label, "ест");
+    }
+
+    #[test]
+    fn test_negated_fact_answers_no() {
+        let processor = TextProcessor::new();
+        let mut kg = sample_graph();
+        let horse = kg.upsert_primitive("лошадь");
+        let fish = kg.find_primitive("рыба").unwrap();
+        let mut negated = Edge::new(EdgeType::Action, "ест", 0.8, Source::Extracted);
+        negated.polarity = Polarity::Negative;
+        kg.add_edge(horse, fish, negated);
+
+        assert_eq!(answer_question("Ест ли лошадь рыбу?", &processor, &kg).answers[0].text, "нет");
+        // Отрицание не попадает в ответы на вопросы "кто"/"что"
+        assert!(answer_question("Кто ест рыбу?", &processor, &kg).answers.iter().all(|a| a.text != "лошадь"));
+    }
+
+    #[test]
+    fn test_english_questions() {
+        let processor = TextProcessor::new();
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let mut kg = KnowledgeGraph::new(db);
+        let cat = kg.upsert_primitive("cat");
+        let fish = kg.upsert_primitive("fish");
+        let sea = kg.upsert_primitive("sea");
+        let animal = kg.upsert_primitive("animal");
+        kg.add_edge(cat, fish, Edge::new(EdgeType::Action, "eats", 0.7, Source::Wikipedia));
+        kg.add_edge(fish, sea, Edge::new(EdgeType::Location, "lives in", 0.6, Source::ArXiv));
+        kg.add_edge(cat, animal, Edge::new(EdgeType::IsA, "is a", 0.9, Source::Wikipedia));
+
+        let answer = answer_question("What do cats eat?", &processor, &kg);
+        assert_eq!(answer.kind, Some(QuestionKind::Object));
+        assert_eq!(answer.answers[0].text, "fish");
+        assert_eq!(answer.answers[0].edges[0].label, "eats");
+
+        assert_eq!(answer_question("Who eats fish?", &processor, &kg).answers[0].text, "cat");
+        assert_eq!(answer_question("Where does the fish live?", &processor, &kg).answers[0].text, "sea");
+        assert_eq!(answer_question("What is a cat?", &processor, &kg).answers[0].text, "animal");
+        assert_eq!(answer_question("Does the cat eat fish?", &processor, &kg).answers[0].text, "yes");
+        assert!(!answer_question("Tell me something", &processor, &kg).understood);
+    }
+
+    #[test]
+    fn test_questions_from_pattern_files() {
+        // Шаблоны вопросов области действуют без изменения кода
+        let mut patterns = PatternSet::builtin();
+        patterns.upsert_domain(DomainPatterns {
+            name: "zoology".into(),
+            language: Language::Russian,
+            entities: vec![EntityRule { entity_type: EntityType::Animal, words: vec!["ёж".into()], pattern: None, confidence: None }],
+            actions: vec![],
+            properties: vec![],
+            questions: vec![QuestionRule { kind: QuestionKind::Object, pattern: r"^чем\s+(?P<verb>питается|питаются)\s+(?P<subject>\w+)$".into() }],
+        }).unwrap();
+        let processor = TextProcessor::new().with_patterns(patterns);
+        let mut kg = sample_graph();
+        let hedgehog = kg.upsert_primitive("ёж");
+        let worm = kg.upsert_primitive("червь");
+        kg.add_edge(hedgehog, worm, Edge::new(EdgeType::Action, "питается", 0.7, Source::Extracted));
+
+        assert!(parse_question("Чем питается ёж?", &TextProcessor::new()).is_none());
+        assert_eq!(answer_question("Чем питается ёж?", &processor, &kg).answers[0].text, "червь");
+    }
+}
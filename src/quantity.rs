@@ -0,0 +1,621 @@
+// quantity.rs
+// Числовые величины и единицы измерения для "Мыслящего Ядро"
+//
+// Находит числа (цифрами и словами: "5", "2,5 млн", "четырьмя", "twenty five"),
+// диапазоны ("3–5 кг", "от 3 до 5 м") и единицы после них, переводит значение
+// в каноничную единицу своей размерности (500 г → 0.5 кг, 36 км/ч → 10 м/с).
+// Число без единицы с существительным после него ("4 моторами") — количество.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Serialize, Deserialize};
+use crate::language::Language;
+
+/// Размерность величины
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Dimension {
+    Count,       // Количество предметов ("4 мотора")
+    Mass,        // кг
+    Length,      // м
+    Area,        // м²
+    Volume,      // л
+    Duration,    // с
+    Speed,       // м/с
+    Temperature, // °C
+    Power,       // Вт
+    Ratio,       // %
+}
+
+impl Dimension {
+    /// Каноничная единица размерности
+    pub fn canonical_unit(self) -> &'static str {
+        match self {
+            Dimension::Count => "",
+            Dimension::Mass => "кг",
+            Dimension::Length => "м",
+            Dimension::Area => "м²",
+            Dimension::Volume => "л",
+            Dimension::Duration => "с",
+            Dimension::Speed => "м/с",
+            Dimension::Temperature => "°C",
+            Dimension::Power => "Вт",
+            Dimension::Ratio => "%",
+        }
+    }
+
+    /// Название атрибута для свойства ("масса", "length"); для количества — None
+    pub fn attribute(self, language: Language) -> Option<&'static str> {
+        let (ru, en) = match self {
+            Dimension::Count => return None,
+            Dimension::Mass => ("масса", "mass"),
+            Dimension::Length => ("длина", "length"),
+            Dimension::Area => ("площадь", "area"),
+            Dimension::Volume => ("объём", "volume"),
+            Dimension::Duration => ("длительность", "duration"),
+            Dimension::Speed => ("скорость", "speed"),
+            Dimension::Temperature => ("температура", "temperature"),
+            Dimension::Power => ("мощность", "power"),
+            Dimension::Ratio => ("доля", "share"),
+        };
+        Some(match language {
+            Language::Russian => ru,
+            Language::English => en,
+        })
+    }
+}
+
+/// Величина в каноничной единице
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Quantity {
+    pub value: f64,         // Значение; для диапазона — нижняя граница
+    pub upper: Option<f64>, // Верхняя граница диапазона ("3–5 кг")
+    pub unit: String,       // Каноничная единица ("кг", "м/с"); у количества пустая
+    pub dimension: Dimension,
+}
+
+impl Quantity {
+    /// Сравнение с величиной той же размерности (по нижней границе)
+    pub fn compare(&self, other: &Quantity) -> Option<Ordering> {
+        if self.dimension != other.dimension {
+            return None;
+        }
+        self.value.partial_cmp(&other.value)
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format_number(self.value))?;
+        if let Some(upper) = self.upper {
+            write!(f, "–{}", format_number(upper))?;
+        }
+        if !self.unit.is_empty() {
+            write!(f, " {}", self.unit)?;
+        }
+        Ok(())
+    }
+}
+
+/// Найденная в тексте величина
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantityMatch {
+    pub range: Range<usize>,        // Фрагмент текста (число, диапазон и единица)
+    pub quantity: Quantity,
+    pub noun: Option<Range<usize>>, // Исчисляемое существительное для количества ("моторами")
+}
+
+/// Единицы: форма (со `*` — основа с любым окончанием), размерность, множитель и сдвиг
+/// до каноничной единицы. Основы, с которых начинаются другие слова ("час" — "часть",
+/// "sec" — "section", "mile" — "milestone"), перечислены всеми формами
+const UNITS: &[(&str, Dimension, f64, f64)] = &[
+    ("мг", Dimension::Mass, 1e-6, 0.0),
+    ("г", Dimension::Mass, 1e-3, 0.0),
+    ("гр", Dimension::Mass, 1e-3, 0.0),
+    ("грамм*", Dimension::Mass, 1e-3, 0.0),
+    ("кг", Dimension::Mass, 1.0, 0.0),
+    ("килограмм*", Dimension::Mass, 1.0, 0.0),
+    ("т", Dimension::Mass, 1e3, 0.0),
+    ("тонн*", Dimension::Mass, 1e3, 0.0),
+    ("mg", Dimension::Mass, 1e-6, 0.0),
+    ("g", Dimension::Mass, 1e-3, 0.0),
+    ("gram*", Dimension::Mass, 1e-3, 0.0),
+    ("kg", Dimension::Mass, 1.0, 0.0),
+    ("kilogram*", Dimension::Mass, 1.0, 0.0),
+    ("ton", Dimension::Mass, 1e3, 0.0),
+    ("tons", Dimension::Mass, 1e3, 0.0),
+    ("tonne", Dimension::Mass, 1e3, 0.0),
+    ("tonnes", Dimension::Mass, 1e3, 0.0),
+    ("lb", Dimension::Mass, 0.453_592_37, 0.0),
+    ("lbs", Dimension::Mass, 0.453_592_37, 0.0),
+    ("pound*", Dimension::Mass, 0.453_592_37, 0.0),
+    ("мм", Dimension::Length, 1e-3, 0.0),
+    ("миллиметр*", Dimension::Length, 1e-3, 0.0),
+    ("см", Dimension::Length, 1e-2, 0.0),
+    ("сантиметр*", Dimension::Length, 1e-2, 0.0),
+    ("м", Dimension::Length, 1.0, 0.0),
+    ("метр*", Dimension::Length, 1.0, 0.0),
+    ("км", Dimension::Length, 1e3, 0.0),
+    ("километр*", Dimension::Length, 1e3, 0.0),
+    ("mm", Dimension::Length, 1e-3, 0.0),
+    ("cm", Dimension::Length, 1e-2, 0.0),
+    ("m", Dimension::Length, 1.0, 0.0),
+    ("km", Dimension::Length, 1e3, 0.0),
+    ("millimet*", Dimension::Length, 1e-3, 0.0),
+    ("centimet*", Dimension::Length, 1e-2, 0.0),
+    ("meter*", Dimension::Length, 1.0, 0.0),
+    ("metre*", Dimension::Length, 1.0, 0.0),
+    ("kilomet*", Dimension::Length, 1e3, 0.0),
+    ("mile", Dimension::Length, 1609.344, 0.0),
+    ("miles", Dimension::Length, 1609.344, 0.0),
+    ("ft", Dimension::Length, 0.3048, 0.0),
+    ("feet", Dimension::Length, 0.3048, 0.0),
+    ("foot", Dimension::Length, 0.3048, 0.0),
+    ("inch*", Dimension::Length, 0.0254, 0.0),
+    ("м²", Dimension::Area, 1.0, 0.0),
+    ("м2", Dimension::Area, 1.0, 0.0),
+    ("км²", Dimension::Area, 1e6, 0.0),
+    ("км2", Dimension::Area, 1e6, 0.0),
+    ("га", Dimension::Area, 1e4, 0.0),
+    ("гектар*", Dimension::Area, 1e4, 0.0),
+    ("m²", Dimension::Area, 1.0, 0.0),
+    ("m2", Dimension::Area, 1.0, 0.0),
+    ("km²", Dimension::Area, 1e6, 0.0),
+    ("km2", Dimension::Area, 1e6, 0.0),
+    ("ha", Dimension::Area, 1e4, 0.0),
+    ("hectare*", Dimension::Area, 1e4, 0.0),
+    ("мл", Dimension::Volume, 1e-3, 0.0),
+    ("миллилитр*", Dimension::Volume, 1e-3, 0.0),
+    ("л", Dimension::Volume, 1.0, 0.0),
+    ("литр*", Dimension::Volume, 1.0, 0.0),
+    ("ml", Dimension::Volume, 1e-3, 0.0),
+    ("l", Dimension::Volume, 1.0, 0.0),
+    ("liter*", Dimension::Volume, 1.0, 0.0),
+    ("litre*", Dimension::Volume, 1.0, 0.0),
+    ("сек", Dimension::Duration, 1.0, 0.0),
+    ("секунд*", Dimension::Duration, 1.0, 0.0),
+    ("мин", Dimension::Duration, 60.0, 0.0),
+    ("минут*", Dimension::Duration, 60.0, 0.0),
+    ("ч", Dimension::Duration, 3600.0, 0.0),
+    ("час", Dimension::Duration, 3600.0, 0.0),
+    ("часа", Dimension::Duration, 3600.0, 0.0),
+    ("часу", Dimension::Duration, 3600.0, 0.0),
+    ("часом", Dimension::Duration, 3600.0, 0.0),
+    ("часе", Dimension::Duration, 3600.0, 0.0),
+    ("часов", Dimension::Duration, 3600.0, 0.0),
+    ("часам", Dimension::Duration, 3600.0, 0.0),
+    ("часами", Dimension::Duration, 3600.0, 0.0),
+    ("часах", Dimension::Duration, 3600.0, 0.0),
+    ("сут*", Dimension::Duration, 86_400.0, 0.0),
+    ("день", Dimension::Duration, 86_400.0, 0.0),
+    ("дн*", Dimension::Duration, 86_400.0, 0.0),
+    ("недел*", Dimension::Duration, 604_800.0, 0.0),
+    ("месяц*", Dimension::Duration, 2_629_800.0, 0.0),
+    ("год*", Dimension::Duration, 31_557_600.0, 0.0),
+    ("лет", Dimension::Duration, 31_557_600.0, 0.0),
+    ("s", Dimension::Duration, 1.0, 0.0),
+    ("sec", Dimension::Duration, 1.0, 0.0),
+    ("secs", Dimension::Duration, 1.0, 0.0),
+    ("second", Dimension::Duration, 1.0, 0.0),
+    ("seconds", Dimension::Duration, 1.0, 0.0),
+    ("min", Dimension::Duration, 60.0, 0.0),
+    ("minute*", Dimension::Duration, 60.0, 0.0),
+    ("h", Dimension::Duration, 3600.0, 0.0),
+    ("hour*", Dimension::Duration, 3600.0, 0.0),
+    ("day*", Dimension::Duration, 86_400.0, 0.0),
+    ("week*", Dimension::Duration, 604_800.0, 0.0),
+    ("month*", Dimension::Duration, 2_629_800.0, 0.0),
+    ("year*", Dimension::Duration, 31_557_600.0, 0.0),
+    ("км/ч", Dimension::Speed, 1.0 / 3.6, 0.0),
+    ("м/с", Dimension::Speed, 1.0, 0.0),
+    ("km/h", Dimension::Speed, 1.0 / 3.6, 0.0),
+    ("kph", Dimension::Speed, 1.0 / 3.6, 0.0),
+    ("mph", Dimension::Speed, 0.447_04, 0.0),
+    ("m/s", Dimension::Speed, 1.0, 0.0),
+    ("°c", Dimension::Temperature, 1.0, 0.0),
+    ("°с", Dimension::Temperature, 1.0, 0.0),
+    ("°f", Dimension::Temperature, 5.0 / 9.0, -160.0 / 9.0),
+    ("°", Dimension::Temperature, 1.0, 0.0),
+    ("градус*", Dimension::Temperature, 1.0, 0.0),
+    ("degree*", Dimension::Temperature, 1.0, 0.0),
+    ("вт", Dimension::Power, 1.0, 0.0),
+    ("ватт*", Dimension::Power, 1.0, 0.0),
+    ("квт", Dimension::Power, 1e3, 0.0),
+    ("киловатт*", Dimension::Power, 1e3, 0.0),
+    ("w", Dimension::Power, 1.0, 0.0),
+    ("watt*", Dimension::Power, 1.0, 0.0),
+    ("kw", Dimension::Power, 1e3, 0.0),
+    ("kilowatt*", Dimension::Power, 1e3, 0.0),
+    ("%", Dimension::Ratio, 1.0, 0.0),
+    ("процент*", Dimension::Ratio, 1.0, 0.0),
+    ("percent", Dimension::Ratio, 1.0, 0.0),
+];
+
+/// Числительные: формы через пробел и значение
+const RUSSIAN_NUMBERS: &[(&str, f64)] = &[
+    ("ноль нуль нуля ноля", 0.0),
+    ("один одна одно одного одной одному одним одном одну", 1.0),
+    ("два две двух двум двумя", 2.0),
+    ("три трёх трех трём трем тремя", 3.0),
+    ("четыре четырёх четырех четырём четырем четырьмя", 4.0),
+    ("пять пяти пятью", 5.0),
+    ("шесть шести шестью", 6.0),
+    ("семь семи семью", 7.0),
+    ("восемь восьми восемью восьмью", 8.0),
+    ("девять девяти девятью", 9.0),
+    ("десять десяти десятью", 10.0),
+    ("одиннадцать одиннадцати", 11.0),
+    ("двенадцать двенадцати", 12.0),
+    ("тринадцать тринадцати", 13.0),
+    ("четырнадцать четырнадцати", 14.0),
+    ("пятнадцать пятнадцати", 15.0),
+    ("шестнадцать шестнадцати", 16.0),
+    ("семнадцать семнадцати", 17.0),
+    ("восемнадцать восемнадцати", 18.0),
+    ("девятнадцать девятнадцати", 19.0),
+    ("двадцать двадцати", 20.0),
+    ("тридцать тридцати", 30.0),
+    ("сорок сорока", 40.0),
+    ("пятьдесят пятидесяти", 50.0),
+    ("шестьдесят шестидесяти", 60.0),
+    ("семьдесят семидесяти", 70.0),
+    ("восемьдесят восьмидесяти", 80.0),
+    ("девяносто девяноста", 90.0),
+    ("сто ста", 100.0),
+    ("двести двухсот", 200.0),
+    ("триста трёхсот трехсот", 300.0),
+    ("четыреста четырёхсот четырехсот", 400.0),
+    ("пятьсот пятисот", 500.0),
+    ("шестьсот шестисот", 600.0),
+    ("семьсот семисот", 700.0),
+    ("восемьсот восьмисот", 800.0),
+    ("девятьсот девятисот", 900.0),
+    ("полтора полторы полутора", 1.5),
+];
+
+const ENGLISH_NUMBERS: &[(&str, f64)] = &[
+    ("zero", 0.0), ("one", 1.0), ("two", 2.0), ("three", 3.0), ("four", 4.0), ("five", 5.0),
+    ("six", 6.0), ("seven", 7.0), ("eight", 8.0), ("nine", 9.0), ("ten", 10.0),
+    ("eleven", 11.0), ("twelve", 12.0), ("thirteen", 13.0), ("fourteen", 14.0), ("fifteen", 15.0),
+    ("sixteen", 16.0), ("seventeen", 17.0), ("eighteen", 18.0), ("nineteen", 19.0),
+    ("twenty", 20.0), ("thirty", 30.0), ("forty", 40.0), ("fifty", 50.0),
+    ("sixty", 60.0), ("seventy", 70.0), ("eighty", 80.0), ("ninety", 90.0),
+];
+
+/// Множители после числа ("5 тысяч", "2,5 млн", "two hundred")
+const MULTIPLIERS: &[(&str, f64)] = &[
+    ("тыс тысяча тысячи тысяч тысячу тысячей тысячами thousand thousands", 1e3),
+    ("млн миллион миллиона миллионов миллиону миллионом million millions", 1e6),
+    ("млрд миллиард миллиарда миллиардов billion billions", 1e9),
+    ("hundred hundreds", 1e2),
+];
+
+/// Слова, которые не бывают исчисляемыми существительными после числа
+const NOT_NOUNS: &[&str] = &[
+    "и", "или", "в", "во", "на", "с", "со", "по", "из", "до", "от", "за", "к", "у", "о", "об", "при", "раз",
+    "and", "or", "of", "in", "on", "to", "at", "by", "for", "from", "a", "an", "the", "times",
+    "около", "примерно", "почти", "более", "менее", "всего", "about", "approximately", "nearly", "almost",
+];
+
+/// Слова перед числом, при которых "г" — граммы, а не год ("масса 1500 г")
+const MASS_CONTEXT: &[&str] = &["масс", "вес", "mass", "weigh"];
+
+const MONTHS: &[&str] = &[
+    "января", "февраля", "марта", "апреля", "мая", "июня", "июля", "августа", "сентября", "октября", "ноября", "декабря",
+    "январь", "февраль", "март", "апрель", "май", "июнь", "июль", "август", "сентябрь", "октябрь", "ноябрь", "декабрь",
+    "january", "february", "march", "april", "may", "june", "july", "august", "september", "october", "november", "december",
+];
+
+lazy_static! {
+    static ref TOKEN: Regex = Regex::new(r"\d{1,3}(?:[ \u{a0}]\d{3})+(?:[.,]\d+)?|\d+(?:[.,]\d+)?|\p{L}+").unwrap();
+    static ref DATE_LIKE: Regex = Regex::new(r"\d{1,4}[./-]\d{1,2}[./-]\d{1,4}|\d{1,2}:\d{2}").unwrap();
+    static ref NUMBER_WORDS: HashMap<&'static str, f64> = RUSSIAN_NUMBERS
+        .iter()
+        .flat_map(|&(forms, value)| forms.split_whitespace().map(move |w| (w, value)))
+        .chain(ENGLISH_NUMBERS.iter().copied())
+        .collect();
+    static ref MULTIPLIER_WORDS: HashMap<&'static str, f64> = MULTIPLIERS
+        .iter()
+        .flat_map(|&(forms, value)| forms.split_whitespace().map(move |w| (w, value)))
+        .collect();
+    static ref SORTED_UNITS: Vec<&'static (&'static str, Dimension, f64, f64)> = {
+        let mut units: Vec<_> = UNITS.iter().collect();
+        units.sort_by_key(|u| std::cmp::Reverse(u.0.trim_end_matches('*').chars().count()));
+        units
+    };
+}
+
+/// Число без лишних нулей (0.5, 4, 1500)
+fn format_number(value: f64) -> String {
+    let formatted = format!("{:.6}", value);
+    formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Число, записанное цифрами ("1 500", "2,5")
+fn parse_digits(token: &str) -> Option<f64> {
+    token.chars().filter(|c| !c.is_whitespace()).collect::<String>().replace(',', ".").parse().ok()
+}
+
+/// Длина совпадения `form` в начале `rest` без учёта регистра
+fn match_form(rest: &str, form: &str) -> Option<usize> {
+    let (stem, open_ending) = match form.strip_suffix('*') {
+        Some(stem) => (stem, true),
+        None => (form, false),
+    };
+    let mut chars = rest.char_indices();
+    for expected in stem.chars() {
+        let (_, c) = chars.next()?;
+        if !c.to_lowercase().eq(expected.to_lowercase()) {
+            return None;
+        }
+    }
+    let mut end = rest.len();
+    for (i, c) in chars {
+        if !(open_ending && c.is_alphabetic()) {
+            end = i;
+            break;
+        }
+    }
+    // Единица не может быть началом другого слова ("м" в "моторами")
+    let next = rest[end..].chars().next();
+    if !open_ending && stem.chars().last().is_some_and(char::is_alphanumeric) && next.is_some_and(char::is_alphanumeric) {
+        return None;
+    }
+    Some(end)
+}
+
+/// Единица в начале `rest` (после пробелов): (длина с пробелами, размерность, множитель, сдвиг)
+fn match_unit(rest: &str) -> Option<(usize, Dimension, f64, f64)> {
+    let skipped = rest.len() - rest.trim_start().len();
+    let rest = &rest[skipped..];
+    SORTED_UNITS
+        .iter()
+        .find_map(|&&(form, dimension, factor, offset)| match_form(rest, form).map(|len| (skipped + len, dimension, factor, offset)))
+}
+
+/// Число в тексте: диапазон и значение
+#[derive(Debug, Clone)]
+struct Atom {
+    range: Range<usize>,
+    value: f64,
+    digits: bool, // Записано цифрами (а не словами)
+}
+
+/// Числа текста: цифрами (с множителем "тыс", "млн") и словами ("двадцать пять", "two hundred")
+fn atoms(text: &str) -> Vec<Atom> {
+    let masked: Vec<Range<usize>> = DATE_LIKE.find_iter(text).map(|m| m.range()).collect();
+    let tokens: Vec<(Range<usize>, &str)> = TOKEN.find_iter(text).map(|m| (m.range(), m.as_str())).collect();
+    let gap_is_space = |a: &Range<usize>, b: &Range<usize>| text[a.end..b.start].trim().is_empty();
+    let mut atoms = Vec::new();
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let (range, token) = tokens[i].clone();
+        let lowered = token.to_lowercase();
+
+        let mut atom = if token.starts_with(|c: char| c.is_ascii_digit()) {
+            let glued = text[..range.start].chars().next_back().is_some_and(char::is_alphabetic);
+            if glued || masked.iter().any(|m| m.start < range.end && range.start < m.end) {
+                i += 1;
+                continue;
+            }
+            let Some(value) = parse_digits(token) else {
+                i += 1;
+                continue;
+            };
+            Atom { range, value, digits: true }
+        } else if NUMBER_WORDS.contains_key(lowered.as_str()) || lowered == "hundred" {
+            // Сумма подряд идущих числительных; "hundred" умножает накопленное
+            let (mut total, mut current, mut end) = (0.0, 0.0, range.end);
+            let mut j = i;
+            while j < tokens.len() {
+                let word = tokens[j].1.to_lowercase();
+                if j > i && !gap_is_space(&tokens[j - 1].0, &tokens[j].0) {
+                    break;
+                }
+                if let Some(&value) = NUMBER_WORDS.get(word.as_str()) {
+                    current += value;
+                } else if let Some(&factor) = MULTIPLIER_WORDS.get(word.as_str()) {
+                    if factor < 1e3 {
+                        current = f64::max(current, 1.0) * factor;
+                    } else {
+                        total += f64::max(current, 1.0) * factor;
+                        current = 0.0;
+                    }
+                } else if !(word == "and" && tokens.get(j + 1).is_some_and(|t| NUMBER_WORDS.contains_key(t.1.to_lowercase().as_str()))) {
+                    break;
+                }
+                end = tokens[j].0.end;
+                j += 1;
+            }
+            atoms.push(Atom { range: range.start..end, value: total + current, digits: false });
+            i = j;
+            continue;
+        } else {
+            i += 1;
+            continue;
+        };
+
+        // Множитель после цифр: "5 тысяч", "2,5 млн"
+        if let Some((next, word)) = tokens.get(i + 1)
+            && gap_is_space(&atom.range, next)
+            && let Some(&factor) = MULTIPLIER_WORDS.get(word.to_lowercase().as_str())
+        {
+            atom.value *= factor;
+            // Точка сокращения ("2,5 тыс. м") входит в число
+            atom.range.end = if text[next.end..].starts_with('.') { next.end + 1 } else { next.end };
+            i += 1;
+        }
+        atoms.push(atom);
+        i += 1;
+    }
+    atoms
+}
+
+/// Слово, следующее за позицией `at` (после пробелов)
+fn next_word(text: &str, at: usize) -> Option<Range<usize>> {
+    let rest = &text[at..];
+    let start = at + (rest.len() - rest.trim_start().len());
+    if start == at && at > 0 {
+        return None;
+    }
+    let len = text[start..].find(|c: char| !c.is_alphabetic() && c != '-').unwrap_or(text.len() - start);
+    (len > 0).then(|| start..start + len)
+}
+
+/// Предыдущее слово перед позицией `at`
+fn previous_word(text: &str, at: usize) -> Option<&str> {
+    text[..at].split(|c: char| !c.is_alphabetic()).rfind(|w| !w.is_empty())
+}
+
+/// Слово перед величиной, которое не может быть её сущностью: служебное ("с", "of"),
+/// о массе ("весом", "weighs") или однокоренное с атрибутом ("длиной" при "длина")
+pub fn is_measure_word(word: &str, attribute: &str) -> bool {
+    let word = word.to_lowercase();
+    let stem = |w: &str| w.chars().take(4).collect::<String>();
+    NOT_NOUNS.contains(&word.as_str()) || MASS_CONTEXT.iter().any(|p| word.starts_with(p)) || stem(&word) == stem(attribute)
+}
+
+/// Перед позицией `at` (в пределах трёх слов) говорится о массе: "масса", "весит", "weighs"
+fn mass_context(text: &str, at: usize) -> bool {
+    text[..at]
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .rev()
+        .take(3)
+        .any(|w| {
+            let w = w.to_lowercase();
+            MASS_CONTEXT.iter().any(|prefix| w.starts_with(prefix))
+        })
+}
+
+/// Найти величины в тексте
+pub fn find_quantities(text: &str) -> Vec<QuantityMatch> {
+    let atoms = atoms(text);
+    let mut found = Vec::new();
+    let mut i = 0;
+
+    while i < atoms.len() {
+        let first = &atoms[i];
+        let mut start = first.range.start;
+        let mut last = first;
+
+        // Диапазон: "3–5", "от 3 до 5", "from 3 to 5", "between 3 and 5"
+        if let Some(second) = atoms.get(i + 1) {
+            let between = text[first.range.end..second.range.start].trim().to_lowercase();
+            let before = previous_word(text, first.range.start).map(str::to_lowercase);
+            let opener = before.as_deref().filter(|w| matches!(*w, "от" | "from" | "between"));
+            let paired = matches!(between.as_str(), "-" | "–" | "—")
+                || (opener.is_some() && matches!(between.as_str(), "до" | "to" | "and"));
+            if paired && second.range.start > first.range.end {
+                if opener.is_some() {
+                    start = text[..start].trim_end().len() - before.as_ref().unwrap().len();
+                }
+                last = second;
+                i += 1;
+            }
+        }
+        i += 1;
+
+        // Год, а не длительность: "2023 года", "мая 2023", "в 1500 г.". "Г" (с точкой или без) —
+        // год, только если перед числом не говорится о массе: "масса 1500 г." — граммы
+        let is_year = last.digits && last.value.fract() == 0.0 && (1000.0..=2100.0).contains(&last.value) && start == last.range.start;
+        let after = next_word(text, last.range.end).map(|r| text[r].to_lowercase());
+        let before = previous_word(text, start).map(str::to_lowercase);
+        if is_year
+            && (after.as_deref().is_some_and(|w| w.starts_with("год") || w.starts_with("year"))
+                || (after.as_deref() == Some("г") && !mass_context(text, start))
+                || before.as_deref().is_some_and(|w| MONTHS.contains(&w)))
+        {
+            continue;
+        }
+        // День месяца, а не количество: "5 мая", "31 december"
+        let is_day = last.digits && last.value.fract() == 0.0 && (1.0..=31.0).contains(&last.value) && start == last.range.start;
+        if is_day && after.as_deref().is_some_and(|w| MONTHS.contains(&w)) {
+            continue;
+        }
+
+        let (lower, upper) = (first.value, (last.range != first.range).then_some(last.value));
+        let (end, quantity, noun) = match match_unit(&text[last.range.end..]) {
+            Some((len, dimension, factor, offset)) => {
+                let convert = |v: f64| ((v * factor + offset) * 1e9).round() / 1e9;
+                let quantity = Quantity {
+                    value: convert(lower),
+                    upper: upper.map(convert),
+                    unit: dimension.canonical_unit().to_string(),
+                    dimension,
+                };
+                (last.range.end + len, quantity, None)
+            },
+            None => {
+                // Количество: число и существительное после него ("4 моторами")
+                let noun = next_word(text, last.range.end)
+                    .filter(|r| {
+                        let word = text[r.clone()].to_lowercase();
+                        !NOT_NOUNS.contains(&word.as_str()) && !MONTHS.contains(&word.as_str()) && !NUMBER_WORDS.contains_key(word.as_str())
+                    })
+                    // "одна из", "one of": единица словами — чаще местоимение, чем количество
+                    .filter(|_| last.digits || lower != 1.0);
+                let quantity = Quantity { value: lower, upper, unit: String::new(), dimension: Dimension::Count };
+                (noun.as_ref().map_or(last.range.end, |r| r.end), quantity, noun)
+            },
+        };
+
+        found.push(QuantityMatch { range: start..end, quantity, noun });
+    }
+
+    found
+}
+
+/// Разобрать величину из строки ("3 кг", "500 г", "4")
+pub fn parse_quantity(text: &str) -> Option<Quantity> {
+    find_quantities(text).into_iter().next().map(|m| m.quantity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_units_and_normalization() {
+        let found = find_quantities("масса 500 г, скорость 36 км/ч и 2,5 тыс. м");
+        let values: Vec<String> = found.iter().map(|m| m.quantity.to_string()).collect();
+        assert_eq!(values, vec!["0.5 кг", "10 м/с", "2500 м"]);
+        assert_eq!(parse_quantity("3 kg").unwrap().compare(&found[0].quantity), Some(Ordering::Greater));
+        assert_eq!(parse_quantity("212 °F").unwrap().value, 100.0);
+    }
+
+    #[test]
+    fn test_words_ranges_and_counts() {
+        let text = "аппарат с четырьмя моторами весит от 3 до 5 кг";
+        let found = find_quantities(text);
+        assert_eq!(found[0].quantity.value, 4.0);
+        assert_eq!(&text[found[0].noun.clone().unwrap()], "моторами");
+        assert_eq!(found[1].quantity.to_string(), "3–5 кг");
+        assert_eq!(&text[found[1].range.clone()], "от 3 до 5 кг");
+
+        assert_eq!(parse_quantity("twenty five thousand dollars").unwrap().value, 25_000.0);
+        assert_eq!(parse_quantity("two hundred and five km").unwrap().value, 205_000.0);
+        // Даты и годы — не величины
+        assert!(find_quantities("5 мая 2023 года, 12.05.2024").is_empty());
+        assert!(find_quantities("on 31 December 2023").is_empty());
+        assert!(find_quantities("замок построен в 1500 г. и перестроен в 1600 г").is_empty());
+        // Граммы при упоминании массы
+        assert_eq!(parse_quantity("масса 1500 г").unwrap().to_string(), "1.5 кг");
+        assert_eq!(parse_quantity("кот весит 1500 г").unwrap().to_string(), "1.5 кг");
+        assert_eq!(parse_quantity("Масса кота 1500 г.").unwrap().to_string(), "1.5 кг");
+    }
+
+    #[test]
+    fn test_units_do_not_swallow_words() {
+        // "час" не начало "части", "sec" — не "section", "mile" — не "milestone"
+        assert_eq!(parse_quantity("2 часа").unwrap().to_string(), "7200 с");
+        assert_eq!(parse_quantity("5 частей").unwrap().dimension, Dimension::Count);
+        assert_eq!(parse_quantity("3 sections").unwrap().dimension, Dimension::Count);
+        assert_eq!(parse_quantity("2 milestones").unwrap().dimension, Dimension::Count);
+        assert_eq!(parse_quantity("2 miles").unwrap().value, 3218.688);
+    }
+}
@@ -0,0 +1,330 @@
+// resilience.rs
+// Вежливые и устойчивые запросы к внешним источникам для "Мыслящего Ядро"
+//
+// - HttpPolicy: таймаут запроса, число повторов и экспоненциальная задержка между ними
+//   (500 мс, 1 с, 2 с… не больше max_backoff; Retry-After сервера важнее)
+// - RateLimiter: не чаще одного запроса в min_interval на источник (arXiv просит 3 с)
+// - PoliteClient: GET с ограничением частоты и повторами при 429/5xx, таймаутах и обрывах связи
+// - CircuitBreaker: после failure_threshold неудач подряд источник отключается на cooldown,
+//   затем пропускается один пробный запрос (HalfOpen): успех — включить, неудача — снова отключить
+// - BreakerPermit: разрешение на опрос; прерванный опрос (задача отменена или упала)
+//   засчитывается при сбросе как неудача, чтобы пробный запрос не занимал предохранитель навсегда
+
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use reqwest::{Client, StatusCode};
+use serde::{Serialize, Deserialize};
+use tokio::time::Instant;
+use crate::fact_source::SourceStatus;
+
+/// Ошибка запроса к источнику (сеть, HTTP-статус или формат ответа)
+pub type FetchError = Box<dyn Error + Send + Sync>;
+
+/// Параметры запросов к одному источнику
+#[derive(Debug, Clone)]
+pub struct HttpPolicy {
+    pub timeout: Duration,         // Таймаут одного запроса
+    pub max_retries: u32,          // Повторов после первой попытки
+    pub initial_backoff: Duration, // Задержка перед первым повтором (дальше — вдвое больше)
+    pub max_backoff: Duration,
+    pub min_interval: Duration,    // Минимальный интервал между запросами к источнику
+}
+
+impl Default for HttpPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+            min_interval: Duration::ZERO,
+        }
+    }
+}
+
+impl HttpPolicy {
+    /// Задержка перед повтором номер `retry` (с нуля)
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff.saturating_mul(1u32 << retry.min(16)).min(self.max_backoff)
+    }
+}
+
+/// Ограничитель частоты: запросы к источнику не чаще одного в `interval`
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    next: tokio::sync::Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(interval: Duration) -> Self {
+        Self { interval, next: tokio::sync::Mutex::new(None) }
+    }
+
+    /// Дождаться своей очереди; возвращает время ожидания
+    pub async fn acquire(&self) -> Duration {
+        let mut next = self.next.lock().await;
+        let now = Instant::now();
+        let slot = next.map_or(now, |n| n.max(now));
+        tokio::time::sleep_until(slot).await;
+        *next = Some(slot + self.interval);
+        slot - now
+    }
+}
+
+/// HTTP-клиент источника: ограничение частоты, таймауты и повторы
+#[derive(Debug, Clone)]
+pub struct PoliteClient {
+    client: Client,
+    policy: HttpPolicy,
+    limiter: Arc<RateLimiter>,
+}
+
+/// Стоит ли повторить запрос с таким статусом (перегрузка или ошибка сервера)
+fn retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+impl PoliteClient {
+    pub fn new(client: Client, policy: HttpPolicy) -> Self {
+        let limiter = Arc::new(RateLimiter::new(policy.min_interval));
+        Self { client, policy, limiter }
+    }
+
+    pub fn policy(&self) -> &HttpPolicy {
+        &self.policy
+    }
+
+    /// GET с параметрами (кодируются reqwest); число запросов, повторов и ожидания —
+    /// в `status`
+    pub async fn get_text(&self, url: &str, params: &[(&str, &str)], status: &mut SourceStatus) -> Result<String, FetchError> {
+        let mut retry = 0;
+        loop {
+            status.throttled_ms += self.limiter.acquire().await.as_millis() as u64;
+            status.requests += 1;
+
+            let (error, retry_after): (FetchError, Option<Duration>) =
+                match self.client.get(url).query(params).timeout(self.policy.timeout).send().await {
+                    Ok(resp) if retryable(resp.status()) => {
+                        let retry_after = resp
+                            .headers()
+                            .get(reqwest::header::RETRY_AFTER)
+                            .and_then(|v| v.to_str().ok())
+                            .and_then(|v| v.trim().parse().ok())
+                            .map(Duration::from_secs);
+                        (format!("HTTP {} от {}", resp.status(), url).into(), retry_after)
+                    },
+                    Ok(resp) => return Ok(resp.error_for_status()?.text().await?),
+                    Err(e) if e.is_timeout() || e.is_connect() => (e.into(), None),
+                    Err(e) => return Err(e.into()),
+                };
+
+            if retry >= self.policy.max_retries {
+                return Err(error);
+            }
+            // Retry-After не ограничивается max_backoff, но и не превышает таймаут запроса
+            let delay = retry_after.map_or(self.policy.backoff(retry), |d| d.min(self.policy.timeout).max(self.policy.backoff(retry)));
+            tokio::time::sleep(delay).await;
+            retry += 1;
+            status.retries += 1;
+        }
+    }
+}
+
+/// Состояние предохранителя источника
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CircuitState {
+    #[default]
+    Closed,   // Источник опрашивается
+    Open,     // Отключён после серии неудач
+    HalfOpen, // Пробный запрос после паузы
+}
+
+/// Параметры предохранителя
+#[derive(Debug, Clone, Copy)]
+pub struct BreakerConfig {
+    pub failure_threshold: u32, // Неудач подряд до отключения
+    pub cooldown: Duration,     // Пауза до пробного запроса
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        Self { failure_threshold: 3, cooldown: Duration::from_secs(60) }
+    }
+}
+
+/// Предохранитель: отключает источник, который раз за разом не отвечает
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    config: BreakerConfig,
+    failures: u32,
+    opened_at: Option<Instant>,
+    trial: bool, // Пробный запрос уже идёт
+}
+
+impl CircuitBreaker {
+    pub fn new(config: BreakerConfig) -> Self {
+        Self { config, failures: 0, opened_at: None, trial: false }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        match self.opened_at {
+            None => CircuitState::Closed,
+            Some(at) if at.elapsed() < self.config.cooldown => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Можно ли опросить источник сейчас (в HalfOpen — только один запрос)
+    pub fn try_acquire(&mut self) -> bool {
+        match self.state() {
+            CircuitState::Closed => true,
+            CircuitState::Open => false,
+            CircuitState::HalfOpen if self.trial => false,
+            CircuitState::HalfOpen => {
+                self.trial = true;
+                true
+            },
+        }
+    }
+
+    /// Учесть результат опроса
+    pub fn record(&mut self, success: bool) {
+        if success {
+            self.failures = 0;
+            self.opened_at = None;
+        } else {
+            self.failures += 1;
+            if self.trial || self.failures >= self.config.failure_threshold {
+                self.opened_at = Some(Instant::now());
+            }
+        }
+        self.trial = false;
+    }
+}
+
+/// Разрешение на опрос источника через предохранитель. Без `record` (опрос отменён
+/// вместе с задачей или упал) при сбросе засчитывается неудача
+#[derive(Debug)]
+pub struct BreakerPermit {
+    breaker: Arc<Mutex<CircuitBreaker>>,
+    armed: bool,
+}
+
+impl BreakerPermit {
+    /// Разрешение, если предохранитель пропускает запрос
+    pub fn acquire(breaker: &Arc<Mutex<CircuitBreaker>>) -> Option<Self> {
+        let allowed = breaker.lock().unwrap_or_else(|e| e.into_inner()).try_acquire();
+        allowed.then(|| Self { breaker: breaker.clone(), armed: true })
+    }
+
+    /// Учесть результат опроса; возвращает новое состояние предохранителя
+    pub fn record(mut self, success: bool) -> CircuitState {
+        self.armed = false;
+        let mut breaker = self.breaker.lock().unwrap_or_else(|e| e.into_inner());
+        breaker.record(success);
+        breaker.state()
+    }
+}
+
+impl Drop for BreakerPermit {
+    fn drop(&mut self) {
+        if self.armed {
+            self.breaker.lock().unwrap_or_else(|e| e.into_inner()).record(false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_backoff_and_breaker() {
+        let policy = HttpPolicy { initial_backoff: Duration::from_millis(100), max_backoff: Duration::from_millis(300), ..HttpPolicy::default() };
+        let delays: Vec<_> = (0..4).map(|r| policy.backoff(r).as_millis()).collect();
+        assert_eq!(delays, vec![100, 200, 300, 300]);
+
+        let mut breaker = CircuitBreaker::new(BreakerConfig { failure_threshold: 2, cooldown: Duration::ZERO });
+        breaker.record(false);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        breaker.record(false);
+        // Пауза нулевая: сразу пробный запрос, второй параллельный не пропускается
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.try_acquire());
+        assert!(!breaker.try_acquire());
+        breaker.record(true);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        let mut breaker = CircuitBreaker::new(BreakerConfig { failure_threshold: 1, cooldown: Duration::from_secs(60) });
+        breaker.record(false);
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.try_acquire());
+    }
+
+    #[tokio::test]
+    async fn test_dropped_trial_releases_breaker() {
+        let mut breaker = CircuitBreaker::new(BreakerConfig { failure_threshold: 1, cooldown: Duration::from_millis(50) });
+        breaker.record(false);
+        let breaker = Arc::new(Mutex::new(breaker));
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        // Пробный запрос отменяется вместе с задачей, не успев вызвать record
+        let permit = BreakerPermit::acquire(&breaker).unwrap();
+        assert!(BreakerPermit::acquire(&breaker).is_none());
+        let task = tokio::spawn(async move {
+            let _permit = permit;
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        task.abort();
+        assert!(task.await.unwrap_err().is_cancelled());
+
+        // Отмена засчитана неудачей: источник снова отключён, после паузы — новая проба
+        assert_eq!(breaker.lock().unwrap().state(), CircuitState::Open);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        let permit = BreakerPermit::acquire(&breaker).unwrap();
+        assert_eq!(permit.record(true), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_and_retries() {
+        let limiter = RateLimiter::new(Duration::from_millis(40));
+        let started = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        assert!(started.elapsed() >= Duration::from_millis(80));
+
+        // Сервер отвечает 503, 429 и только потом 200
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/search", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let responses = [
+                "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+            ];
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 1024];
+                let _ = socket.read(&mut request).await;
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let policy = HttpPolicy { initial_backoff: Duration::from_millis(10), ..HttpPolicy::default() };
+        let client = PoliteClient::new(Client::new(), policy);
+        let mut status = SourceStatus::default();
+        assert_eq!(client.get_text(&url, &[("q", "кот ест рыбу")], &mut status).await.unwrap(), "ok");
+        assert_eq!((status.requests, status.retries), (3, 2));
+
+        let strict = PoliteClient::new(Client::new(), HttpPolicy { max_retries: 0, timeout: Duration::from_millis(200), ..HttpPolicy::default() });
+        let mut status = SourceStatus::default();
+        assert!(strict.get_text(&url, &[], &mut status).await.is_err());
+        assert_eq!(status.retries, 0);
+    }
+}
@@ -3,12 +3,17 @@ use std::fmt;
 use uuid::Uuid;
 use std::error::Error;
 use bumpalo::Bump;
+use serde::{Serialize, Deserialize};
+use chrono::{DateTime, Utc};
+use crate::core::{EdgeType, Modality, Polarity, Source};
+use crate::quantity::Quantity;
+use crate::temporal::TimeValue;
 
 /// Максимальная глубина вложенности для защиты от переполнения
 const MAX_DEPTH: usize = 100;
 
 /// Рекурсивный enum для вложенных сегментов
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Segment {
     Primitive(String), // Текст, числа
     Composite(Vec<Segment>), // Вложенные сегменты
@@ -16,7 +21,7 @@ pub enum Segment {
 }
 
 /// Узел графа знаний с вложенными сегментами
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct KnowledgeNode {
     pub id: Uuid,
     pub data: Segment, // Может быть вложенным!
@@ -25,9 +30,53 @@ pub struct KnowledgeNode {
     pub tags: Vec<String>, // Новое поле: теги (например, "животное", "биология")
 }
 
-/// Тип связи (заглушка)
-#[derive(Debug, Clone, PartialEq)]
-pub struct Edge;
+/// Связь между узлами графа: тип отношения, уверенность и происхождение факта
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Edge {
+    pub kind: EdgeType,
+    pub label: String,        // Исходная метка: глагол или атрибут ("ест", "водится")
+    pub confidence: f32,
+    pub source: Source,
+    pub span: Option<String>, // Фрагмент текста, из которого извлечён факт
+    pub last_verified: DateTime<Utc>,
+    #[serde(default)]
+    pub polarity: Polarity,   // Отрицательные рёбра хранят опровергнутые факты ("кот не ест рыбу")
+    #[serde(default)]
+    pub modality: Modality,
+    #[serde(default)]
+    pub quantity: Option<Quantity>, // Числовое значение свойства ("масса" -> "5 кг")
+    #[serde(default)]
+    pub time: Option<TimeValue>, // Когда факт имел место: дата из того же предложения
+}
+
+impl Edge {
+    /// Создать связь, проверенную в текущий момент
+    pub fn new(kind: EdgeType, label: impl Into<String>, confidence: f32, source: Source) -> Self {
+        Self {
+            kind,
+            label: label.into(),
+            confidence,
+            source,
+            span: None,
+            last_verified: Utc::now(),
+            polarity: Polarity::Positive,
+            modality: Modality::Asserted,
+            quantity: None,
+            time: None,
+        }
+    }
+}
+
+impl Segment {
+    /// Текстовое представление сегмента (вложенные части через пробел)
+    pub fn text(&self) -> String {
+        match self {
+            Segment::Primitive(s) => s.clone(),
+            Segment::Composite(children) => children.iter().map(Segment::text).collect::<Vec<_>>().join(" "),
+            Segment::Link(node) => node.data.text(),
+        }
+    }
+}
 
 /// Ошибка переполнения рекурсии
 #[derive(Debug)]
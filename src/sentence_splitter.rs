@@ -0,0 +1,261 @@
+// sentence_splitter.rs
+// Разбиение текста на предложения для "Мыслящего Ядро"
+//
+// Правила:
+// - конец предложения: . ! ? … ; и их серии ("?!", "..."), а также пустая строка
+// - точка между цифрами — часть числа или даты (3.14, 12.05.2024)
+// - точка внутри слова без пробела — не граница (т.е, сайт.рф)
+// - сокращения из списка (г., т.е., см.) и инициалы (А. С. Пушкин) не завершают предложение;
+//   "т.д.", "т.п.", "др." завершают его, если дальше идёт заглавная буква
+// - единицы после числа ("5 мин.", "3 см.", "в 1500 г.") завершают предложение перед
+//   заглавной буквой; без числа ("г. Москва") остаются сокращениями
+// - внутри парных кавычек граница не ставится; после "!»" или "?»" с тире
+//   или строчной буквой предложение продолжается («Стой!» — крикнул он)
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// Сокращения, после которых предложение не заканчивается
+const ABBREVIATIONS: &[&str] = &[
+    "г", "гг", "в", "вв", "ул", "пр", "пер", "д", "кв", "обл", "р", "оз", "им",
+    "т.е", "т.к", "т.н", "т.о", "см", "ср", "стр", "рис", "табл", "гл", "разд", "п", "пп", "с",
+    "проф", "акад", "доц", "канд", "докт", "напр", "тыс", "млн", "млрд", "руб", "коп",
+    "мин", "сек", "ок", "прим", "англ", "лат", "рус", "e.g", "i.e", "etc", "vs", "dr", "mr", "mrs", "prof", "fig",
+];
+
+/// Сокращения, которыми предложение может заканчиваться (если дальше заглавная буква)
+const FINAL_ABBREVIATIONS: &[&str] = &["т.д", "т.п", "др", "пр", "etc"];
+
+/// Сокращения единиц и порядков, которые после числа могут завершать предложение
+const UNIT_ABBREVIATIONS: &[&str] = &[
+    "г", "гг", "в", "вв", "с", "см", "мин", "сек", "тыс", "млн", "млрд", "руб", "коп",
+];
+
+/// Открывающие и закрывающие кавычки и скобки
+const OPENING: &[char] = &['«', '„', '“', '(', '['];
+const CLOSING: &[char] = &['»', '“', '”', ')', ']', '"', '\''];
+
+fn is_terminator(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '…' | ';')
+}
+
+/// Разбиение текста на предложения по правилам
+#[derive(Debug, Clone)]
+pub struct SentenceSplitter {
+    abbreviations: HashSet<String>,
+    final_abbreviations: HashSet<String>,
+}
+
+impl Default for SentenceSplitter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SentenceSplitter {
+    /// Разделитель со встроенным списком сокращений
+    pub fn new() -> Self {
+        Self {
+            abbreviations: ABBREVIATIONS.iter().map(|a| a.to_string()).collect(),
+            final_abbreviations: FINAL_ABBREVIATIONS.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    /// Добавить сокращения (без завершающей точки: "акад", "т.е")
+    pub fn with_abbreviations<I: IntoIterator<Item = S>, S: Into<String>>(mut self, abbreviations: I) -> Self {
+        self.abbreviations.extend(abbreviations.into_iter().map(|a| a.into().to_lowercase()));
+        self
+    }
+
+    /// Предложения текста: диапазоны в байтах без начальных и конечных пробелов
+    pub fn split(&self, text: &str) -> Vec<Range<usize>> {
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+        let quoted = quoted_regions(&chars);
+        let mut sentences = Vec::new();
+        let mut start = 0;
+        let mut i = 0;
+
+        while i < chars.len() {
+            let (offset, c) = chars[i];
+
+            // Пустая строка — всегда граница (абзац)
+            if c == '\n' {
+                let mut j = i + 1;
+                while j < chars.len() && chars[j].1 != '\n' && chars[j].1.is_whitespace() {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].1 == '\n' {
+                    push_trimmed(text, start..offset, &mut sentences);
+                    start = chars[j].0;
+                    i = j;
+                    continue;
+                }
+            }
+
+            if !is_terminator(c) || quoted.contains(&i) {
+                i += 1;
+                continue;
+            }
+
+            // Серия знаков конца ("?!", "...") и закрывающие кавычки/скобки после неё
+            let mut end = i + 1;
+            while end < chars.len() && is_terminator(chars[end].1) {
+                end += 1;
+            }
+            while end < chars.len() && CLOSING.contains(&chars[end].1) {
+                end += 1;
+            }
+
+            if self.is_boundary(&chars, i, end) {
+                let boundary = chars.get(end).map_or(text.len(), |&(o, _)| o);
+                push_trimmed(text, start..boundary, &mut sentences);
+                start = boundary;
+            }
+            i = end;
+        }
+
+        push_trimmed(text, start..text.len(), &mut sentences);
+        sentences
+    }
+
+    /// Предложения текста как подстроки
+    pub fn split_str<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        self.split(text).into_iter().map(|r| &text[r]).collect()
+    }
+
+    /// Является ли серия знаков chars[at..end] концом предложения
+    fn is_boundary(&self, chars: &[(usize, char)], at: usize, end: usize) -> bool {
+        let prev = at.checked_sub(1).map(|p| chars[p].1);
+        let next = chars.get(end).map(|&(_, c)| c);
+        let single_period = chars[at].1 == '.' && (end == at + 1 || !is_terminator(chars[at + 1].1));
+
+        // Сразу за знаком нет пробела: число, дата, сокращение внутри слова, адрес
+        if let Some(next) = next
+            && !next.is_whitespace()
+        {
+            return false;
+        }
+        // Первое значимое после пробелов
+        let following = chars[end..].iter().map(|&(_, c)| c).find(|c| !c.is_whitespace());
+        let Some(following) = following else { return true };
+
+        let mark = chars[at].1;
+        if mark == ';' {
+            return true;
+        }
+        // Прямая речь и многоточие: «Стой!» — крикнул он; "Да... конечно"
+        let closes_quote = end > at + 1 && CLOSING.contains(&chars[end - 1].1);
+        let continues = matches!(following, '—' | '–' | '-' | ',') || following.is_lowercase();
+        if continues && (closes_quote || mark != '.' || !single_period) {
+            return false;
+        }
+        if mark != '.' || !single_period || prev.is_none() {
+            return true;
+        }
+
+        // Слово перед точкой: от последнего пробела или открывающей кавычки
+        let mut begin = at;
+        while begin > 0 && !chars[begin - 1].1.is_whitespace() && !OPENING.contains(&chars[begin - 1].1) {
+            begin -= 1;
+        }
+        let word: String = chars[begin..at].iter().map(|&(_, c)| c).collect();
+        let lowered = word.to_lowercase();
+
+        // Инициалы: одна заглавная буква
+        let mut letters = word.chars();
+        if let (Some(first), None) = (letters.next(), letters.next())
+            && first.is_uppercase()
+        {
+            return false;
+        }
+        if UNIT_ABBREVIATIONS.contains(&lowered.as_str()) && is_number(&previous_word(chars, begin)) {
+            return following.is_uppercase();
+        }
+        if self.final_abbreviations.contains(&lowered) {
+            return following.is_uppercase() || !following.is_alphabetic();
+        }
+        if self.abbreviations.contains(&lowered) {
+            return false;
+        }
+        true
+    }
+}
+
+/// Слово, предшествующее позиции `begin` (через пробелы)
+fn previous_word(chars: &[(usize, char)], begin: usize) -> String {
+    let mut end = begin;
+    while end > 0 && chars[end - 1].1.is_whitespace() {
+        end -= 1;
+    }
+    let mut start = end;
+    while start > 0 && !chars[start - 1].1.is_whitespace() {
+        start -= 1;
+    }
+    chars[start..end].iter().map(|&(_, c)| c).collect()
+}
+
+/// Число ("1500", "3,5", "12.05") или римская цифра века ("XIX")
+fn is_number(word: &str) -> bool {
+    let arabic = word.chars().any(|c| c.is_ascii_digit()) && word.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | ',' | '-'));
+    let roman = !word.is_empty() && word.chars().all(|c| matches!(c, 'I' | 'V' | 'X' | 'L' | 'C' | 'D' | 'M'));
+    arabic || roman
+}
+
+/// Позиции (индексы символов) внутри парных кавычек; непарные кавычки игнорируются
+fn quoted_regions(chars: &[(usize, char)]) -> HashSet<usize> {
+    let mut inside = HashSet::new();
+    let mut stack: Vec<(usize, char)> = Vec::new();
+    let mut straight_open: Option<usize> = None;
+
+    for (i, &(_, c)) in chars.iter().enumerate() {
+        match c {
+            '«' | '„' => stack.push((i, c)),
+            // “ закрывает „ (русская типографика) и открывает в английской
+            '“' if stack.last().is_some_and(|&(_, open)| open == '„') => close(&mut stack, i, &mut inside),
+            '“' => stack.push((i, c)),
+            '»' | '”' => close(&mut stack, i, &mut inside),
+            '"' => match straight_open.take() {
+                Some(open) => inside.extend(open + 1..i.saturating_sub(1).max(open + 1)),
+                None => straight_open = Some(i),
+            },
+            _ => {},
+        }
+    }
+    inside
+}
+
+fn close(stack: &mut Vec<(usize, char)>, at: usize, inside: &mut HashSet<usize>) {
+    if let Some((open, _)) = stack.pop() {
+        // Знак перед закрывающей кавычкой оценивается вместе с ней («Стой!» — ...)
+        inside.extend(open + 1..at.saturating_sub(1).max(open + 1));
+    }
+}
+
+fn push_trimmed(text: &str, range: Range<usize>, sentences: &mut Vec<Range<usize>>) {
+    let chunk = &text[range.clone()];
+    let trimmed = chunk.trim();
+    if !trimmed.is_empty() {
+        let start = range.start + (chunk.len() - chunk.trim_start().len());
+        sentences.push(start..start + trimmed.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_basic_boundaries() {
+        let splitter = SentenceSplitter::new();
+        assert_eq!(splitter.split_str("Кот ест рыбу. Рыба водится в море!"), vec!["Кот ест рыбу.", "Рыба водится в море!"]);
+        assert_eq!(splitter.split_str("Правда?! Да... Конечно"), vec!["Правда?!", "Да...", "Конечно"]);
+    }
+
+    #[test]
+    fn test_quote_regions_ignore_unpaired() {
+        let chars: Vec<(usize, char)> = "«а. б» в. «г".char_indices().collect();
+        let quoted = quoted_regions(&chars);
+        assert!(quoted.contains(&2));
+        assert!(!quoted.contains(&8));
+    }
+}
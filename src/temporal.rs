@@ -0,0 +1,375 @@
+// temporal.rs
+// Распознавание и нормализация дат для "Мыслящего Ядро"
+//
+// Абсолютные ("5 мая 2023 года", "12.05.2024", "May 5, 2023", "в 1990-х", "в XIX веке")
+// и относительные ("вчера", "3 дня назад", "в прошлом году", "last week") выражения
+// переводятся в интервал дат [start, end] относительно даты документа. Интервал
+// записывается в ISO 8601 ("2023-05-05", "2023-05", "2023", "1801-01-01/1900-12-31"),
+// поэтому узлы-даты в графе можно сравнивать и упорядочивать.
+
+use std::fmt;
+use std::ops::Range;
+use chrono::{Datelike, Duration, Months, NaiveDate};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde::{Serialize, Deserialize};
+use crate::language::Language;
+
+/// Точность выражения
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Granularity {
+    Day,
+    Week,
+    Month,
+    Year,
+    Decade,
+    Century,
+}
+
+/// Интервал дат (границы включительно)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct TimeValue {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub granularity: Granularity,
+}
+
+impl TimeValue {
+    pub fn day(date: NaiveDate) -> Self {
+        Self { start: date, end: date, granularity: Granularity::Day }
+    }
+
+    /// Неделя (с понедельника), содержащая дату; None — у границы диапазона дат
+    pub fn week(date: NaiveDate) -> Option<Self> {
+        let start = date.checked_sub_signed(Duration::days(date.weekday().num_days_from_monday() as i64))?;
+        let end = start.checked_add_signed(Duration::days(6))?;
+        Some(Self { start, end, granularity: Granularity::Week })
+    }
+
+    pub fn month(year: i32, month: u32) -> Option<Self> {
+        let start = NaiveDate::from_ymd_opt(year, month, 1)?;
+        let end = start.checked_add_months(Months::new(1))?.pred_opt()?;
+        Some(Self { start, end, granularity: Granularity::Month })
+    }
+
+    pub fn year(year: i32) -> Option<Self> {
+        Self::years(year, year, Granularity::Year)
+    }
+
+    /// Десятилетие: 1990 → 1990–1999
+    pub fn decade(first_year: i32) -> Option<Self> {
+        Self::years(first_year, first_year + 9, Granularity::Decade)
+    }
+
+    /// Век: XIX → 1801–1900
+    pub fn century(number: i32) -> Option<Self> {
+        Self::years((number - 1) * 100 + 1, number * 100, Granularity::Century)
+    }
+
+    fn years(first: i32, last: i32, granularity: Granularity) -> Option<Self> {
+        Some(Self {
+            start: NaiveDate::from_ymd_opt(first, 1, 1)?,
+            end: NaiveDate::from_ymd_opt(last, 12, 31)?,
+            granularity,
+        })
+    }
+
+    /// Попадает ли дата в интервал
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.start <= date && date <= self.end
+    }
+
+    /// Разобрать запись ISO 8601, которую выдаёт `Display`
+    pub fn parse_iso(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if let Some((from, to)) = text.split_once('/') {
+            let (from, to) = (Self::parse_iso(from)?, Self::parse_iso(to)?);
+            let days = (to.end - from.start).num_days();
+            let granularity = match days {
+                6 => Granularity::Week,
+                3600..=3660 => Granularity::Decade,
+                36_000..=36_600 => Granularity::Century,
+                _ => from.granularity.max(to.granularity),
+            };
+            return Some(Self { start: from.start, end: to.end, granularity });
+        }
+        let parts: Vec<&str> = text.split('-').collect();
+        let number = |i: usize| parts.get(i).filter(|p| p.chars().all(|c| c.is_ascii_digit())).and_then(|p| p.parse().ok());
+        match (parts.len(), parts[0].len()) {
+            (1, 4) => Self::year(number(0)?),
+            (2, 4) => Self::month(number(0)?, number(1)? as u32),
+            (3, 4) => Some(Self::day(NaiveDate::from_ymd_opt(number(0)?, number(1)? as u32, number(2)? as u32)?)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TimeValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.granularity {
+            Granularity::Day => write!(f, "{}", self.start.format("%Y-%m-%d")),
+            Granularity::Month => write!(f, "{}", self.start.format("%Y-%m")),
+            Granularity::Year => write!(f, "{}", self.start.format("%Y")),
+            _ => write!(f, "{}/{}", self.start.format("%Y-%m-%d"), self.end.format("%Y-%m-%d")),
+        }
+    }
+}
+
+/// Найденное в тексте выражение даты
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateMatch {
+    pub range: Range<usize>,
+    pub value: TimeValue,
+    pub relative: bool, // Разрешено относительно даты документа ("вчера", "last year")
+}
+
+type Resolver = fn(&Captures, NaiveDate) -> Option<TimeValue>;
+
+/// Правило: выражение, признак относительности и разрешение в интервал
+struct Rule {
+    regex: Regex,
+    relative: bool,
+    resolve: Resolver,
+}
+
+fn rule(pattern: &str, relative: bool, resolve: Resolver) -> Rule {
+    Rule { regex: Regex::new(&format!("(?i){}", pattern)).unwrap(), relative, resolve }
+}
+
+const RU_MONTH: &str = r"(январ[ьяе]|феврал[ьяе]|март[ае]?|апрел[ьяе]|ма[йяе]|июн[ьяе]|июл[ьяе]|август[ае]?|сентябр[ьяе]|октябр[ьяе]|ноябр[ьяе]|декабр[ьяе])\b";
+const EN_MONTH: &str = r"(january|february|march|april|may|june|july|august|september|october|november|december|jan|feb|mar|apr|jun|jul|aug|sept|sep|oct|nov|dec)\b\.?";
+
+/// Номер месяца по названию (в любом падеже, полному или сокращённому)
+fn month_number(word: &str) -> Option<u32> {
+    const PREFIXES: [&[&str]; 12] = [
+        &["янв", "jan"], &["фев", "feb"], &["мар", "mar"], &["апр", "apr"], &["ма", "may"], &["июн", "jun"],
+        &["июл", "jul"], &["авг", "aug"], &["сен", "sep"], &["окт", "oct"], &["ноя", "nov"], &["дек", "dec"],
+    ];
+    let word = word.to_lowercase();
+    // "мар" проверяется раньше "ма" (май)
+    PREFIXES.iter().position(|p| p.iter().any(|p| word.starts_with(p))).map(|i| i as u32 + 1)
+}
+
+/// Римское число (XIX → 19)
+fn roman(text: &str) -> Option<i32> {
+    let mut total = 0;
+    let mut previous = 0;
+    for c in text.to_uppercase().chars().rev() {
+        let value = match c {
+            'I' => 1,
+            'V' => 5,
+            'X' => 10,
+            'L' => 50,
+            'C' => 100,
+            _ => return None,
+        };
+        if value < previous { total -= value } else { total += value; previous = value }
+    }
+    (total > 0).then_some(total)
+}
+
+fn number(cap: &Captures, i: usize) -> Option<i32> {
+    cap.get(i)?.as_str().parse().ok()
+}
+
+/// Год: указан в выражении или год документа
+fn year_or(cap: &Captures, i: usize, anchor: NaiveDate) -> i32 {
+    number(cap, i).unwrap_or(anchor.year())
+}
+
+fn day_of(year: i32, month: u32, day: i32) -> Option<TimeValue> {
+    NaiveDate::from_ymd_opt(year, month, day as u32).map(TimeValue::day)
+}
+
+/// Сдвиг относительно даты документа на `amount` единиц (`unit` — начало слова);
+/// None — результат вне диапазона дат ("99999999 дней назад")
+fn shifted(anchor: NaiveDate, amount: i32, unit: &str) -> Option<TimeValue> {
+    let unit = unit.to_lowercase();
+    let months = |n: i32| match n {
+        n if n >= 0 => anchor.checked_add_months(Months::new(n as u32)),
+        n => anchor.checked_sub_months(Months::new(n.unsigned_abs())),
+    };
+    if unit.starts_with("дн") || unit.starts_with("ден") || unit.starts_with("day") {
+        Some(TimeValue::day(anchor.checked_add_signed(Duration::try_days(amount as i64)?)?))
+    } else if unit.starts_with("нед") || unit.starts_with("week") {
+        TimeValue::week(anchor.checked_add_signed(Duration::try_weeks(amount as i64)?)?)
+    } else if unit.starts_with("мес") || unit.starts_with("month") {
+        let date = months(amount)?;
+        TimeValue::month(date.year(), date.month())
+    } else if unit.starts_with("год") || unit.starts_with("лет") || unit.starts_with("year") {
+        TimeValue::year(anchor.year().checked_add(amount)?)
+    } else {
+        None
+    }
+}
+
+/// Количество в относительном выражении: число, "a"/"one" или отсутствует (= 1)
+fn amount(cap: &Captures, i: usize) -> i32 {
+    number(cap, i).unwrap_or(1)
+}
+
+/// "прошлый" / "этот" / "следующий" → -1 / 0 / 1
+fn direction(word: &str) -> i32 {
+    let word = word.to_lowercase();
+    if word.starts_with("прошл") || word.starts_with("last") || word.starts_with("previous") {
+        -1
+    } else if word.starts_with("следующ") || word.starts_with("next") || word.starts_with("будущ") {
+        1
+    } else {
+        0
+    }
+}
+
+lazy_static! {
+    /// Правила в порядке приоритета: более длинные и точные выражения раньше
+    static ref RUSSIAN_RULES: Vec<Rule> = vec![
+        rule(r"\b(\d{4})-(\d{2})-(\d{2})\b", false, |c, _| day_of(number(c, 1)?, number(c, 2)? as u32, number(c, 3)?)),
+        rule(r"\b(\d{1,2})[./](\d{1,2})[./](\d{4})\b", false, |c, _| day_of(number(c, 3)?, number(c, 2)? as u32, number(c, 1)?)),
+        rule(&format!(r"\b(\d{{1,2}})\s+{}(?:\s+(\d{{4}})(?:\s*(?:года|году|год|г\.))?)?", RU_MONTH), false, |c, a| {
+            day_of(year_or(c, 3, a), month_number(&c[2])?, number(c, 1)?)
+        }),
+        rule(&format!(r"\b(?:в\s+)?{}\s+(\d{{4}})(?:\s*(?:года|году|г\.))?", RU_MONTH), false, |c, _| {
+            TimeValue::month(number(c, 2)?, month_number(&c[1])?)
+        }),
+        rule(r"\b(?:в\s+)?(\d{3}0)-?х(?:\s+(?:годах|годов|годы|гг\.))?", false, |c, _| TimeValue::decade(number(c, 1)?)),
+        rule(r"\b(?:в\s+)?([ivxlc]+|\d{1,2})(?:-м|-го)?\s+(?:век[аеу]?|веке|столети[еия])\b", false, |c, _| {
+            TimeValue::century(roman(&c[1]).or_else(|| number(c, 1))?)
+        }),
+        // "4500 г." — граммы, а не год
+        rule(r"\b(?:в\s+)?([12]\d{3})\s*(?:году|года|год|г\.)", false, |c, _| TimeValue::year(number(c, 1)?)),
+        rule(r"\b(позавчера|вчера|сегодня|послезавтра|завтра)\b", true, |c, a| {
+            let offset = match c[1].to_lowercase().as_str() {
+                "позавчера" => -2,
+                "вчера" => -1,
+                "завтра" => 1,
+                "послезавтра" => 2,
+                _ => 0,
+            };
+            Some(TimeValue::day(a + Duration::days(offset)))
+        }),
+        rule(r"\b(?:(\d+)\s+)?(дн[еяй]\w*|день|недел\w*|месяц\w*|год\w*|лет)\s+назад\b", true, |c, a| shifted(a, -amount(c, 1), &c[2])),
+        rule(r"\bчерез\s+(?:(\d+)\s+)?(дн[еяй]\w*|день|недел\w*|месяц\w*|год\w*|лет)\b", true, |c, a| shifted(a, amount(c, 1), &c[2])),
+        rule(r"\b(?:на|в)\s+(прошлой|этой|следующей)\s+неделе\b", true, |c, a| shifted(a, direction(&c[1]), "неделя")),
+        rule(r"\bв\s+(прошлом|этом|текущем|следующем)\s+(месяце|году)\b", true, |c, a| shifted(a, direction(&c[1]), &c[2])),
+    ];
+
+    static ref ENGLISH_RULES: Vec<Rule> = vec![
+        rule(r"\b(\d{4})-(\d{2})-(\d{2})\b", false, |c, _| day_of(number(c, 1)?, number(c, 2)? as u32, number(c, 3)?)),
+        // Американский порядок: месяц/день/год
+        rule(r"\b(\d{1,2})/(\d{1,2})/(\d{4})\b", false, |c, _| day_of(number(c, 3)?, number(c, 1)? as u32, number(c, 2)?)),
+        rule(r"\b(\d{1,2})\.(\d{1,2})\.(\d{4})\b", false, |c, _| day_of(number(c, 3)?, number(c, 2)? as u32, number(c, 1)?)),
+        rule(&format!(r"\b{}\s+(\d{{1,2}})(?:st|nd|rd|th)?\b(?:,?\s+(\d{{4}}))?", EN_MONTH), false, |c, a| {
+            day_of(year_or(c, 3, a), month_number(&c[1])?, number(c, 2)?)
+        }),
+        rule(&format!(r"\b(\d{{1,2}})(?:st|nd|rd|th)?\s+(?:of\s+)?{}(?:,?\s+(\d{{4}}))?", EN_MONTH), false, |c, a| {
+            day_of(year_or(c, 3, a), month_number(&c[2])?, number(c, 1)?)
+        }),
+        rule(&format!(r"\b(?:in\s+)?{}\s+(\d{{4}})\b", EN_MONTH), false, |c, _| TimeValue::month(number(c, 2)?, month_number(&c[1])?)),
+        rule(r"\b(?:in\s+the\s+)?(\d{3}0)'?s\b", false, |c, _| TimeValue::decade(number(c, 1)?)),
+        rule(r"\b(?:in\s+the\s+)?(?:(\d{1,2})(?:st|nd|rd|th)|([ivxlc]+))\s+century\b", false, |c, _| {
+            TimeValue::century(number(c, 1).or_else(|| roman(c.get(2)?.as_str()))?)
+        }),
+        rule(r"\b(?:in|year)\s+(\d{4})\b", false, |c, _| TimeValue::year(number(c, 1)?)),
+        rule(r"\b(the\s+day\s+before\s+yesterday|the\s+day\s+after\s+tomorrow|yesterday|today|tomorrow)\b", true, |c, a| {
+            let phrase = c[1].to_lowercase();
+            let offset = if phrase.contains("before") {
+                -2
+            } else if phrase.contains("after") {
+                2
+            } else {
+                match phrase.as_str() {
+                    "yesterday" => -1,
+                    "tomorrow" => 1,
+                    _ => 0,
+                }
+            };
+            Some(TimeValue::day(a + Duration::days(offset)))
+        }),
+        rule(r"\b(\d+|an?|one)\s+(day|week|month|year)s?\s+ago\b", true, |c, a| shifted(a, -number(c, 1).unwrap_or(1), &c[2])),
+        rule(r"\bin\s+(\d+|an?|one)\s+(day|week|month|year)s?\b", true, |c, a| shifted(a, number(c, 1).unwrap_or(1), &c[2])),
+        rule(r"\b(last|this|next|previous)\s+(week|month|year)\b", true, |c, a| shifted(a, direction(&c[1]), &c[2])),
+    ];
+}
+
+/// Найти выражения дат в тексте; `anchor` — дата документа для относительных выражений
+pub fn find_dates(text: &str, language: Language, anchor: NaiveDate) -> Vec<DateMatch> {
+    let rules: &[Rule] = match language {
+        Language::Russian => &RUSSIAN_RULES,
+        Language::English => &ENGLISH_RULES,
+    };
+    let mut found: Vec<DateMatch> = Vec::new();
+
+    for rule in rules {
+        for cap in rule.regex.captures_iter(text) {
+            let range = cap.get(0).unwrap().range();
+            // Фрагмент уже разобран более точным правилом
+            if found.iter().any(|m| m.range.start < range.end && range.start < m.range.end) {
+                continue;
+            }
+            if let Some(value) = (rule.resolve)(&cap, anchor) {
+                let trimmed = text[range.clone()].trim_end().len();
+                found.push(DateMatch { range: range.start..range.start + trimmed, value, relative: rule.relative });
+            }
+        }
+    }
+
+    found.sort_by_key(|m| m.range.start);
+    found
+}
+
+/// Разобрать дату из строки: ISO 8601 или выражение на любом языке
+pub fn parse_date(text: &str, anchor: NaiveDate) -> Option<TimeValue> {
+    TimeValue::parse_iso(text).or_else(|| {
+        Language::ALL
+            .iter()
+            .find_map(|&language| find_dates(text, language, anchor).into_iter().next().map(|m| m.value))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anchor() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 15).unwrap()
+    }
+
+    fn resolved(text: &str, language: Language) -> Vec<String> {
+        find_dates(text, language, anchor()).iter().map(|m| m.value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_absolute_dates() {
+        assert_eq!(resolved("5 мая 2023 года и 12.05.2024", Language::Russian), vec!["2023-05-05", "2024-05-12"]);
+        assert_eq!(resolved("в мае 2023 года, в 1990-х, в XIX веке", Language::Russian), vec!["2023-05", "1990-01-01/1999-12-31", "1801-01-01/1900-12-31"]);
+        assert_eq!(resolved("On May 5, 2023 and 03/04/2020, in the 19th century", Language::English), vec!["2023-05-05", "2020-03-04", "1801-01-01/1900-12-31"]);
+        // Без года — год документа
+        assert_eq!(resolved("8 марта", Language::Russian), vec!["2024-03-08"]);
+    }
+
+    #[test]
+    fn test_relative_dates() {
+        assert_eq!(resolved("вчера, 3 дня назад, в прошлом году", Language::Russian), vec!["2024-03-14", "2024-03-12", "2023"]);
+        assert_eq!(resolved("yesterday and 2 years ago; last month", Language::English), vec!["2024-03-14", "2022", "2024-02"]);
+        let week = find_dates("на прошлой неделе", Language::Russian, anchor())[0].value;
+        assert_eq!((week.start.to_string(), week.end.to_string()), ("2024-03-04".into(), "2024-03-10".into()));
+        assert!(find_dates("завтра", Language::Russian, anchor())[0].relative);
+
+        // Сдвиг за пределы диапазона дат не разрешается (и не паникует)
+        for text in ["99999999 дней назад", "через 99999999 недель", "через 2000000000 лет", "99999999 months ago"] {
+            let language = crate::language::detect_language(text);
+            assert!(find_dates(text, language, anchor()).is_empty(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_iso_roundtrip_and_order() {
+        for text in ["2023-05-05", "2023-05", "2023", "1801-01-01/1900-12-31", "2024-03-04/2024-03-10"] {
+            assert_eq!(TimeValue::parse_iso(text).unwrap().to_string(), text);
+        }
+        let mut values: Vec<TimeValue> = ["2023", "1801-01-01/1900-12-31", "2023-05-05"].iter().map(|t| TimeValue::parse_iso(t).unwrap()).collect();
+        values.sort();
+        assert_eq!(values[0].granularity, Granularity::Century);
+        assert!(values[1].contains(values[2].start));
+    }
+}
@@ -3,17 +3,45 @@
 // Подробные комментарии для понимания архитектуры
 
 use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
 use lazy_static::lazy_static;
 use regex::Regex;
-use crate::segment::{Segment, KnowledgeNode};
-use crate::knowledge_graph::KnowledgeGraph;
+use crate::knowledge_graph::{KnowledgeGraph, GraphError};
+use crate::core::{EdgeType, Modality, Polarity, Source};
+use crate::segment::Edge;
 use crate::trusted_scraper::TrustedScraper;
+use crate::verification::VerificationReport;
+use crate::patterns::PatternSet;
+use crate::lemmatizer::{LemmatizedText, Lemmatizer, WordClass};
+use crate::language::{self, Language};
+use crate::modality::MarkedText;
+use crate::quantity::{self, Quantity};
+use crate::temporal::{self, TimeValue};
+use crate::coreference::Coreference;
+use crate::pipeline::{Pipeline, StageSelection, StageTiming};
+use crate::ner::NerStage;
+use chrono::{NaiveDate, Utc};
+use crate::sentence_splitter::SentenceSplitter;
 use serde::{Serialize, Deserialize};
 
+lazy_static! {
+    static ref HTML_TAG: Regex = Regex::new(r"<[^>]*>").unwrap();
+    static ref WORD: Regex = Regex::new(r"\p{L}[\p{L}-]*").unwrap();
+}
+
+/// Уверенность свойств-величин ("масса 5 кг")
+const QUANTITY_CONFIDENCE: f32 = 0.8;
+
+/// Уверенность дат, распознанных temporal ("5 мая 2023 года", "вчера")
+const DATE_CONFIDENCE: f32 = 0.9;
+
 /// Типы сущностей для семантической сегментации
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum EntityType {
     Person,
+    Animal,
+    Plant,
     Date,
     Location,
     Organization,
@@ -23,12 +51,44 @@ pub enum EntityType {
     Unknown,
 }
 
+/// Фрагмент исходного текста, из которого извлечён факт
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TextSpan {
+    pub start: usize,       // Смещение в байтах (UTF-8)
+    pub end: usize,
+    pub char_start: usize,  // Смещение в символах (для подсветки в UI)
+    pub char_end: usize,
+    pub sentence_id: usize, // Номер предложения в результате обработки
+    pub text: String,       // Фрагмент как в исходном тексте (с регистром, без HTML-разметки)
+}
+
+impl TextSpan {
+    /// Фрагмент `range` (в байтах) исходного текста `source`; смещения указывают
+    /// в исходный текст, а теги внутри фрагмента в его текст не попадают
+    pub fn new(source: &str, range: Range<usize>, sentence_id: usize) -> Self {
+        let char_start = source[..range.start].chars().count();
+        let char_end = char_start + source[range.clone()].chars().count();
+        Self {
+            start: range.start,
+            end: range.end,
+            char_start,
+            char_end,
+            sentence_id,
+            text: HTML_TAG.replace_all(&source[range], "").into_owned(),
+        }
+    }
+}
+
 /// Структура для извлеченной сущности
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Entity {
     pub text: String,
     pub entity_type: EntityType,
     pub confidence: f32,
+    #[serde(default)]
+    pub time: Option<TimeValue>, // Для дат: интервал, разрешённый относительно даты документа
+    #[serde(default)]
+    pub span: TextSpan,
 }
 
 /// Структура для действия (субъект-глагол-объект)
@@ -38,6 +98,12 @@ pub struct Action {
     pub verb: String,
     pub object: String,
     pub confidence: f32,
+    #[serde(default)]
+    pub polarity: Polarity, // "кот не ест рыбу" — отрицание
+    #[serde(default)]
+    pub modality: Modality, // "возможно, кот ест рыбу" — уверенность снижена
+    #[serde(default)]
+    pub span: TextSpan,
 }
 
 /// Структура для свойства (сущность-атрибут-значение)
@@ -47,117 +113,362 @@ pub struct Property {
     pub attribute: String,
     pub value: String,
     pub confidence: f32,
+    #[serde(default)]
+    pub polarity: Polarity,
+    #[serde(default)]
+    pub modality: Modality,
+    #[serde(default)]
+    pub quantity: Option<Quantity>, // Числовое значение в каноничной единице ("5 кг", "4")
+    #[serde(default)]
+    pub span: TextSpan,
 }
 
-/// Результат обработки текста
+/// Предложение исходного текста (для показа контекста модераторам)
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sentence {
+    pub id: usize,
+    pub text: String, // Нормализованный текст, по которому идёт извлечение
+    pub span: TextSpan,
+}
+
+/// Результат обработки текста
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ProcessingResult {
+    #[serde(default)]
+    pub language: Language, // Определённый язык документа ("ru", "en")
+    #[serde(default)]
+    pub document_date: Option<NaiveDate>, // Дата, от которой отсчитаны "вчера", "в прошлом году"
+    #[serde(default)]
+    pub sentences: Vec<Sentence>,
     pub entities: Vec<Entity>,
     pub actions: Vec<Action>,
     pub properties: Vec<Property>,
     pub segments: Vec<String>, // Изменено с Vec<Segment> на Vec<String> для сериализации
     pub graph_nodes: Vec<String>,
     pub graph_edges: Vec<(String, String, String)>,
+    #[serde(default)]
+    pub coreferences: Vec<Coreference>, // Местоимения, заменённые на антецеденты
+    #[serde(default)]
+    pub timings: Vec<StageTiming>, // Время этапов конвейера
+}
+
+impl ProcessingResult {
+    /// Сводка для ответа: сегменты (как строки для сериализации), узлы и связи
+    pub fn summarize(&mut self) {
+        self.segments = self.entities.iter().map(|e| e.text.clone()).collect();
+        self.segments.extend(self.actions.iter().map(|a| format!("{} {} {}", a.subject, a.verb, a.object)));
+        self.graph_nodes = self.entities.iter().map(|e| e.text.clone()).collect();
+        self.graph_edges = self.actions.iter().map(|a| (a.subject.clone(), a.verb.clone(), a.object.clone())).collect();
+    }
+}
+
+/// Итог записи результата обработки в граф знаний
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GraphUpdate {
+    pub nodes_added: usize,
+    pub edges_added: usize,
+}
+
+/// Текст после очистки от HTML (и, для предложений, приведения к нижнему регистру)
+/// с привязкой каждого байта к символу исходного текста
+pub(crate) struct NormalizedText {
+    pub(crate) text: String,
+    source: Vec<Range<usize>>, // Для каждого байта `text` — байты исходного символа
+    pub(crate) lemmas: LemmatizedText, // Леммы предложения (заполняются при предобработке)
+}
+
+impl NormalizedText {
+    /// Исходный текст без HTML-тегов (регистр сохраняется: он нужен для разбиения на предложения)
+    fn new(original: &str) -> Self {
+        let mut text = String::with_capacity(original.len());
+        let mut source = Vec::with_capacity(original.len());
+        let mut tags = HTML_TAG.find_iter(original).map(|m| m.range()).peekable();
+
+        for (i, c) in original.char_indices() {
+            while tags.peek().is_some_and(|tag| tag.end <= i) {
+                tags.next();
+            }
+            if tags.peek().is_some_and(|tag| tag.start <= i) {
+                continue;
+            }
+            text.push(c);
+            source.extend(std::iter::repeat_n(i..i + c.len_utf8(), c.len_utf8()));
+        }
+        Self { text, source, lemmas: LemmatizedText::default() }
+    }
+
+    /// Фрагмент в нижнем регистре с привязкой к тому же исходному тексту
+    fn lowercase(&self, range: Range<usize>) -> Self {
+        let mut text = String::with_capacity(range.len());
+        let mut source = Vec::with_capacity(range.len());
+        for (i, c) in self.text[range.clone()].char_indices() {
+            let before = text.len();
+            text.extend(c.to_lowercase());
+            source.extend(std::iter::repeat_n(self.source[range.start + i].clone(), text.len() - before));
+        }
+        Self { text, source, lemmas: LemmatizedText::default() }
+    }
+
+    /// Диапазон исходного текста для диапазона нормализованного
+    pub(crate) fn source_range(&self, range: Range<usize>) -> Range<usize> {
+        if range.is_empty() {
+            let at = self.source.get(range.start).map_or_else(|| self.source.last().map_or(0, |r| r.end), |r| r.start);
+            return at..at;
+        }
+        self.source[range.start].start..self.source[range.end - 1].end
+    }
 }
 
 /// Обработчик текста с полным пайплайном
 pub struct TextProcessor {
     scraper: TrustedScraper,
-    entity_patterns: HashMap<String, EntityType>,
-    action_patterns: Vec<Regex>,
-    property_patterns: Vec<Regex>,
+    patterns: Arc<Mutex<PatternSet>>, // Активный набор шаблонов (перезагружается при изменении файлов)
+    lemmatizers: HashMap<Language, Arc<Lemmatizer>>, // Приведение словоформ к начальной форме (по языкам)
+    splitter: SentenceSplitter,       // Разбиение на предложения с учётом сокращений и кавычек
+    graph: Arc<Mutex<KnowledgeGraph>>, // Общий граф знаний (тот же, что экспортирует /graph)
+    pipeline: Pipeline,               // Этапы обработки после предобработки
 }
 
 impl TextProcessor {
-    /// Создать новый обработчик текста
+    /// Создать новый обработчик текста (с временным графом знаний в памяти)
     pub fn new() -> Self {
-        let mut entity_patterns = HashMap::new();
-        entity_patterns.insert("кот|кошка|собака|лошадь".to_string(), EntityType::Person);
-        entity_patterns.insert("рыба|акула|кит".to_string(), EntityType::Person);
-        entity_patterns.insert("дерево|цветок|трава".to_string(), EntityType::Person);
-        entity_patterns.insert("\\d{4}-\\d{2}-\\d{2}|\\d{1,2}\\.\\d{1,2}\\.\\d{4}".to_string(), EntityType::Date);
-        entity_patterns.insert("москва|санкт-петербург|новосибирск".to_string(), EntityType::Location);
-        entity_patterns.insert("инфаркт|диабет|рак".to_string(), EntityType::ScientificTerm);
-
-        let action_patterns = vec![
-            Regex::new(r"(\w+)\s+(ест|пьет|идет|бежит|летает|плавает)\s+(\w+)").unwrap(),
-            Regex::new(r"(\w+)\s+(имеет|содержит|включает)\s+(\w+)").unwrap(),
-            Regex::new(r"(\w+)\s+(находится|расположен)\s+(\w+)").unwrap(),
-        ];
-
-        let property_patterns = vec![
-            Regex::new(r"(\w+)\s+(водится|живет|обитает)\s+(\w+)").unwrap(),
-            Regex::new(r"(\w+)\s+(состоит из|содержит)\s+(\w+)").unwrap(),
-            Regex::new(r"(\w+)\s+(является|это)\s+(\w+)").unwrap(),
-        ];
+        let db = sled::Config::new().temporary(true).open().expect("sled db");
+        Self::with_graph(Arc::new(Mutex::new(KnowledgeGraph::new(db))))
+    }
 
+    /// Создать обработчик, пополняющий общий граф знаний
+    pub fn with_graph(graph: Arc<Mutex<KnowledgeGraph>>) -> Self {
         Self {
             scraper: TrustedScraper::new(),
-            entity_patterns,
-            action_patterns,
-            property_patterns,
+            patterns: Arc::new(Mutex::new(PatternSet::builtin())),
+            lemmatizers: Language::ALL.iter().map(|&l| (l, Arc::new(Lemmatizer::for_language(l)))).collect(),
+            splitter: SentenceSplitter::new(),
+            graph,
+            pipeline: Pipeline::standard(),
         }
     }
 
-    /// Предобработка текста: очистка и нормализация
-    pub fn preprocess_text(&self, text: &str) -> Vec<String> {
-        // Очистка от HTML тегов и спецсимволов
-        let cleaned = self.remove_html_tags(text);
-        
-        // Нормализация: приведение к нижнему регистру
-        let normalized = cleaned.to_lowercase();
-        
-        // Разбиение на предложения
-        let sentences: Vec<String> = normalized
-            .split(|c: char| ['.', '!', '?', ';'].contains(&c))
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty() && s.len() > 2)
-            .collect();
+    /// Заменить набор шаблонов (например, загруженный из каталога)
+    pub fn with_patterns(mut self, patterns: PatternSet) -> Self {
+        self.patterns = Arc::new(Mutex::new(patterns));
+        self
+    }
+
+    /// Заменить скрапер (например, с локальным индексом Wikipedia вместо сетевых источников)
+    pub fn with_scraper(mut self, scraper: TrustedScraper) -> Self {
+        self.scraper = scraper;
+        self
+    }
+
+    /// Заменить лемматизатор языка (например, с дополнительным словарём)
+    pub fn with_lemmatizer(mut self, language: Language, lemmatizer: Lemmatizer) -> Self {
+        self.lemmatizers.insert(language, Arc::new(lemmatizer));
+        self
+    }
 
-        sentences
+    /// Заменить конвейер этапов (например, с доменным NER или записью в граф)
+    pub fn with_pipeline(mut self, pipeline: Pipeline) -> Self {
+        self.pipeline = pipeline;
+        self
     }
 
-    /// Удаление HTML тегов
-    fn remove_html_tags(&self, text: &str) -> String {
-        lazy_static! {
-            static ref HTML_TAG: Regex = Regex::new(r"<[^>]*>").unwrap();
+    /// Активный конвейер этапов
+    pub fn pipeline(&self) -> &Pipeline {
+        &self.pipeline
+    }
+
+    /// Подключить статистическую модель NER сразу после словарных сущностей
+    pub fn with_ner(mut self, stage: NerStage) -> Self {
+        if let Err(e) = self.pipeline.insert_after("entities", Arc::new(stage)) {
+            println!("⚠️ Модель NER не подключена: {}", e);
         }
-        HTML_TAG.replace_all(text, "").to_string()
+        self
     }
 
-    /// Семантическая сегментация: извлечение сущностей
+    /// Конвейер для вставки и удаления этапов
+    pub fn pipeline_mut(&mut self) -> &mut Pipeline {
+        &mut self.pipeline
+    }
+
+    pub(crate) fn lemmatizer(&self, language: Language) -> &Lemmatizer {
+        self.lemmatizers.get(&language).map_or_else(|| Lemmatizer::shared_for(language), |l| l.as_ref())
+    }
+
+    /// Начальная форма существительного (субъекта, объекта, сущности);
+    /// слова словарей сущностей считаются известными леммами
+    pub(crate) fn lemma(&self, patterns: &PatternSet, language: Language, word: &str) -> String {
+        self.lemmatizer(language).lemmatize_as(word, WordClass::Noun, |w| patterns.vocabulary.contains(w))
+    }
+
+    /// Лемматизация текста с привязкой к словоформам — единственное место, где текст
+    /// приводится к начальным формам (предобработка и словарный поиск сущностей)
+    fn lemmatize_mapped(&self, patterns: &PatternSet, language: Language, text: &str) -> LemmatizedText {
+        self.lemmatizer(language).lemmatize_mapped(text, |w| patterns.vocabulary.contains(w))
+    }
+
+    /// Начальная форма существительного по лемматизатору языка и словарям шаблонов
+    pub fn lemmatize(&self, language: Language, word: &str) -> String {
+        let patterns = self.patterns.lock().unwrap();
+        self.lemma(&patterns, language, word)
+    }
+
+    /// Активный набор шаблонов
+    pub fn patterns(&self) -> Arc<Mutex<PatternSet>> {
+        self.patterns.clone()
+    }
+
+    /// Перезагрузить шаблоны, если их файлы изменились; при ошибке остаются прежние
+    pub fn refresh_patterns(&self) {
+        match self.patterns.lock().unwrap().reload_if_changed() {
+            Ok(true) => println!("🔄 Шаблоны извлечения перезагружены"),
+            Ok(false) => {},
+            Err(e) => println!("⚠️ Не удалось перезагрузить шаблоны: {}", e),
+        }
+    }
+
+    /// Общий граф знаний, в который попадают выученные факты
+    pub fn graph(&self) -> Arc<Mutex<KnowledgeGraph>> {
+        self.graph.clone()
+    }
+
+    /// Предобработка текста: очистка, нормализация и лемматизация
+    /// ("Кот ест рыбу." → "кот ест рыба.")
+    pub fn preprocess_text(&self, text: &str) -> Vec<String> {
+        self.normalized_sentences(text)
+            .into_iter()
+            .map(|sentence| sentence.lemmas.text)
+            .collect()
+    }
+
+    /// Очистка от HTML, разбиение на предложения (по тексту с исходным регистром:
+    /// заглавные буквы отличают конец предложения от сокращения), приведение к нижнему
+    /// регистру и лемматизация (язык определяется по всему тексту)
+    pub(crate) fn normalized_sentences(&self, text: &str) -> Vec<NormalizedText> {
+        let cleaned = NormalizedText::new(text);
+        let language = language::detect_language(&cleaned.text);
+        let patterns = self.patterns.lock().unwrap();
+        self.splitter
+            .split(&cleaned.text)
+            .into_iter()
+            .filter(|range| range.len() > 2)
+            .map(|range| {
+                let mut sentence = cleaned.lowercase(range);
+                sentence.lemmas = self.lemmatize_mapped(&patterns, language, &sentence.text);
+                sentence
+            })
+            .collect()
+    }
+
+    /// Предложения с привязкой к исходному тексту
+    pub fn sentences(&self, text: &str) -> Vec<Sentence> {
+        self.normalized_sentences(text)
+            .into_iter()
+            .enumerate()
+            .map(|(id, sentence)| Sentence {
+                id,
+                span: TextSpan::new(text, sentence.source_range(0..sentence.text.len()), id),
+                text: sentence.text,
+            })
+            .collect()
+    }
+
+    /// Семантическая сегментация: извлечение сущностей (язык определяется по тексту).
+    /// Словари сопоставляются с леммами ("в Москве" → "москва"), выражения — с исходным текстом.
+    /// Фрагменты (span) считаются от начала `text`.
     pub fn extract_entities(&self, text: &str) -> Vec<Entity> {
+        self.extract_entities_in(text, language::detect_language(text))
+    }
+
+    /// Извлечение сущностей правилами и словарями заданного языка
+    pub fn extract_entities_in(&self, text: &str, language: Language) -> Vec<Entity> {
+        self.extract_entities_at(text, language, Utc::now().date_naive())
+    }
+
+    /// Извлечение сущностей; даты ("5 мая 2023 года", "вчера") разрешаются относительно
+    /// `document_date`, текстом сущности становится запись ISO 8601 ("2023-05-05")
+    pub fn extract_entities_at(&self, text: &str, language: Language, document_date: NaiveDate) -> Vec<Entity> {
+        let lemmas = {
+            let patterns = self.patterns.lock().unwrap();
+            self.lemmatize_mapped(&patterns, language, text)
+        };
+        self.extract_entities_lemmatized(text, &lemmas, language, document_date)
+    }
+
+    /// Извлечение сущностей по тексту и его леммам, уже полученным при предобработке
+    pub(crate) fn extract_entities_lemmatized(&self, text: &str, lemmas: &LemmatizedText, language: Language, document_date: NaiveDate) -> Vec<Entity> {
+        let mut entities = self.extract_pattern_entities(text, lemmas, language);
+
+        for entity in entities.iter_mut().filter(|e| e.entity_type == EntityType::Date) {
+            entity.time = temporal::parse_date(&entity.text, document_date);
+            if let Some(time) = entity.time {
+                entity.text = time.to_string();
+            }
+        }
+        for found in temporal::find_dates(text, language, document_date) {
+            let overlaps = entities
+                .iter()
+                .any(|e| e.entity_type == EntityType::Date && e.span.start < found.range.end && found.range.start < e.span.end);
+            if !overlaps {
+                entities.push(Entity {
+                    text: found.value.to_string(),
+                    entity_type: EntityType::Date,
+                    confidence: DATE_CONFIDENCE,
+                    time: Some(found.value),
+                    span: TextSpan::new(text, found.range, 0),
+                });
+            }
+        }
+
+        entities
+    }
+
+    fn extract_pattern_entities(&self, text: &str, lemmatized: &LemmatizedText, language: Language) -> Vec<Entity> {
+        let patterns = self.patterns.lock().unwrap();
         let mut entities = Vec::new();
-        
-        for (pattern, entity_type) in &self.entity_patterns {
-            if let Ok(regex) = Regex::new(pattern) {
-                for cap in regex.captures_iter(text) {
-                    if let Some(matched) = cap.get(0) {
-                        entities.push(Entity {
-                            text: matched.as_str().to_string(),
-                            entity_type: entity_type.clone(),
-                            confidence: 0.8, // Базовая уверенность
-                        });
-                    }
-                }
+
+        for pattern in patterns.entities_for(language) {
+            let haystack = if pattern.gazetteer { &lemmatized.text } else { text };
+            for matched in pattern.regex.find_iter(haystack) {
+                let range = if pattern.gazetteer { lemmatized.source_range(matched.range()) } else { matched.range() };
+                entities.push(Entity {
+                    text: matched.as_str().to_string(),
+                    entity_type: pattern.entity_type.clone(),
+                    confidence: pattern.confidence,
+                    time: None,
+                    span: TextSpan::new(text, range, 0),
+                });
             }
         }
 
         entities
     }
 
-    /// Извлечение действий (субъект-глагол-объект)
+    /// Извлечение действий (субъект-глагол-объект); субъект и объект приводятся к леммам,
+    /// глагол остаётся как в тексте (он служит меткой связи). Отрицания и модальные слова
+    /// ("не", "возможно") не мешают сопоставлению и отмечаются в полярности и модальности факта
     pub fn extract_actions(&self, text: &str) -> Vec<Action> {
+        self.extract_actions_in(text, language::detect_language(text))
+    }
+
+    /// Извлечение действий правилами заданного языка
+    pub fn extract_actions_in(&self, text: &str, language: Language) -> Vec<Action> {
+        let patterns = self.patterns.lock().unwrap();
+        let marked = MarkedText::new(text, language);
         let mut actions = Vec::new();
-        
-        for pattern in &self.action_patterns {
-            for cap in pattern.captures_iter(text) {
+
+        for pattern in patterns.actions_for(language) {
+            for cap in pattern.regex.captures_iter(&marked.text) {
                 if cap.len() == 4 {
+                    let range = marked.source_range(cap.get(0).unwrap().range());
+                    let (polarity, modality) = marked.assess(range.clone());
                     actions.push(Action {
-                        subject: cap[1].to_string(),
+                        subject: self.lemma(&patterns, language, &cap[1]),
                         verb: cap[2].to_string(),
-                        object: cap[3].to_string(),
-                        confidence: 0.7,
+                        object: self.lemma(&patterns, language, &cap[3]),
+                        confidence: pattern.confidence * modality.confidence_factor(),
+                        polarity,
+                        modality,
+                        span: TextSpan::new(text, range, 0),
                     });
                 }
             }
@@ -166,18 +477,38 @@ impl TextProcessor {
         actions
     }
 
-    /// Извлечение свойств (сущность-атрибут-значение)
+    /// Извлечение свойств (сущность-атрибут-значение); сущность и значение приводятся к леммам.
+    /// Числовые величины ("масса 5 кг", "с 4 моторами") добавляются как свойства с `quantity`
     pub fn extract_properties(&self, text: &str) -> Vec<Property> {
+        self.extract_properties_in(text, language::detect_language(text))
+    }
+
+    /// Извлечение свойств правилами заданного языка
+    pub fn extract_properties_in(&self, text: &str, language: Language) -> Vec<Property> {
+        let mut properties = self.extract_pattern_properties(text, language);
+        properties.extend(self.extract_quantities_in(text, language));
+        properties
+    }
+
+    pub(crate) fn extract_pattern_properties(&self, text: &str, language: Language) -> Vec<Property> {
+        let patterns = self.patterns.lock().unwrap();
+        let marked = MarkedText::new(text, language);
         let mut properties = Vec::new();
-        
-        for pattern in &self.property_patterns {
-            for cap in pattern.captures_iter(text) {
+
+        for pattern in patterns.properties_for(language) {
+            for cap in pattern.regex.captures_iter(&marked.text) {
                 if cap.len() == 4 {
+                    let range = marked.source_range(cap.get(0).unwrap().range());
+                    let (polarity, modality) = marked.assess(range.clone());
                     properties.push(Property {
-                        entity: cap[1].to_string(),
+                        entity: self.lemma(&patterns, language, &cap[1]),
                         attribute: cap[2].to_string(),
-                        value: cap[3].to_string(),
-                        confidence: 0.6,
+                        value: self.lemma(&patterns, language, &cap[3]),
+                        confidence: pattern.confidence * modality.confidence_factor(),
+                        polarity,
+                        modality,
+                        quantity: None,
+                        span: TextSpan::new(text, range, 0),
                     });
                 }
             }
@@ -186,84 +517,180 @@ impl TextProcessor {
         properties
     }
 
-    /// Построение графа знаний из сегментов
-    pub fn build_knowledge_graph(&self, segments: Vec<Segment>) -> KnowledgeGraph {
-        let db = sled::Config::new().temporary(true).open().expect("sled db");
-        let mut graph = KnowledgeGraph::new(db);
-        
-        for segment in segments {
-            let _node_idx = graph.add_node(segment);
-            // Здесь можно добавить логику связывания узлов
+    /// Числовые величины как свойства: "масса 5 кг" → (масса, масса, 5 кг),
+    /// "аппарат с 4 моторами" → (аппарат, мотор, 4). Сущность — ближайшая перед величиной
+    /// найденная сущность или существительное
+    pub fn extract_quantities_in(&self, text: &str, language: Language) -> Vec<Property> {
+        self.extract_quantities_with(text, language, &self.extract_entities_in(text, language), TextSpan::clone)
+    }
+
+    /// Величины с уже найденными сущностями; числа внутри дат ("5 мая", "19 века") пропускаются.
+    /// `locate` переводит фрагменты `text` в систему отсчёта спанов `entities`
+    pub(crate) fn extract_quantities_with(&self, text: &str, language: Language, entities: &[Entity], locate: impl Fn(&TextSpan) -> TextSpan) -> Vec<Property> {
+        let patterns = self.patterns.lock().unwrap();
+        let mut properties = Vec::new();
+
+        let quantities = quantity::find_quantities(text);
+        for found in &quantities {
+            let span = locate(&TextSpan::new(text, found.range.clone(), 0));
+            let in_date = entities
+                .iter()
+                .any(|e| e.entity_type == EntityType::Date && e.span.start < span.end && span.start < e.span.end);
+            if in_date {
+                continue;
+            }
+            let attribute = match (&found.noun, found.quantity.dimension.attribute(language)) {
+                (Some(noun), _) => self.lemma(&patterns, language, &text[noun.clone()]),
+                (None, Some(attribute)) => attribute.to_string(),
+                (None, None) => continue, // Число без единицы и существительного
+            };
+            // Ближайшая перед величиной сущность или существительное (глаголы, служебные
+            // слова, слова о самой величине — "весом", "длиной" — и другие величины пропускаются)
+            let nearest_entity = entities
+                .iter()
+                .filter(|e| e.entity_type != EntityType::Date && e.span.end <= span.start)
+                .max_by_key(|e| e.span.end)
+                .map(|e| (e.span.end, e.text.clone()));
+            let lemmatizer = self.lemmatizer(language);
+            let nearest_word = WORD
+                .find_iter(&text[..found.range.start])
+                .collect::<Vec<_>>()
+                .into_iter()
+                .rev()
+                .filter(|w| w.as_str().chars().count() > 2 && !quantity::is_measure_word(w.as_str(), &attribute))
+                // Существительное другой величины ("с 4 моторами весит 2 кг") — не сущность
+                .filter(|w| !quantities.iter().any(|q| q.range.start <= w.start() && w.end() <= q.range.end))
+                .filter(|w| lemmatizer.lemmatize_verb(w.as_str()) == w.as_str().to_lowercase())
+                .map(|w| (locate(&TextSpan::new(text, w.range(), 0)).end, self.lemma(&patterns, language, w.as_str())))
+                .find(|(_, lemma)| *lemma != attribute);
+            let entity = match (nearest_entity, nearest_word) {
+                (Some(entity), Some(word)) => if word.0 > entity.0 { word.1 } else { entity.1 },
+                (Some((_, text)), None) | (None, Some((_, text))) => text,
+                (None, None) => attribute.clone(),
+            };
+
+            properties.push(Property {
+                entity,
+                attribute,
+                value: found.quantity.to_string(),
+                confidence: QUANTITY_CONFIDENCE,
+                polarity: Polarity::Positive,
+                modality: Modality::Asserted,
+                quantity: Some(found.quantity.clone()),
+                span,
+            });
         }
-        
-        graph
+
+        properties
     }
 
-    /// Полный пайплайн обработки текста
+    /// Полный пайплайн обработки текста (относительные даты — от сегодняшнего дня)
     pub async fn process_text(&self, text: &str) -> ProcessingResult {
-        // 1. Предобработка
-        let sentences = self.preprocess_text(text);
-        
-        // 2. Семантическая сегментация
-        let mut all_entities = Vec::new();
-        let mut all_actions = Vec::new();
-        let mut all_properties = Vec::new();
-        let mut all_segments = Vec::new();
-        
-        for sentence in &sentences {
-            let entities = self.extract_entities(sentence);
-            let actions = self.extract_actions(sentence);
-            let properties = self.extract_properties(sentence);
-            
-            all_entities.extend(entities.clone());
-            all_actions.extend(actions.clone());
-            all_properties.extend(properties.clone());
-            
-            // Создание сегментов (как строки для сериализации)
-            for entity in &entities {
-                all_segments.push(entity.text.clone());
+        self.process_text_at(text, Utc::now().date_naive()).await
+    }
+
+    /// Полный пайплайн обработки текста, написанного `document_date`
+    pub async fn process_text_at(&self, text: &str, document_date: NaiveDate) -> ProcessingResult {
+        self.process_text_with(text, document_date, &StageSelection::default()).await
+    }
+
+    /// Обработка с выбором этапов конвейера ("без кореференции", "только сущности")
+    pub async fn process_text_with(&self, text: &str, document_date: NaiveDate, selection: &StageSelection) -> ProcessingResult {
+        self.process_document(text, document_date, selection)
+    }
+
+    /// Синхронный вариант `process_text_with`: конвейер не ждёт сети, поэтому его можно
+    /// выполнить под блокировкой процессора, не удерживая её через `.await`
+    pub fn process_document(&self, text: &str, document_date: NaiveDate, selection: &StageSelection) -> ProcessingResult {
+        self.refresh_patterns();
+        self.pipeline.run(self, text, document_date, selection)
+    }
+
+    /// Запись результата обработки в граф знаний: сущности становятся узлами
+    /// (тип сущности — тегом узла), действия и свойства — типизированными рёбрами
+    /// с указанием источника. Узлы и рёбра дедуплицируются по тексту, метке и полярности:
+    /// отрицание ("кот не ест рыбу") записывается отдельным ребром и не подтверждает утверждение.
+    pub fn link_into_graph(&self, result: &ProcessingResult, graph: &mut KnowledgeGraph, source: Source) -> Result<GraphUpdate, GraphError> {
+        let nodes_before = graph.graph.node_count();
+        let mut edges_added = 0;
+
+        // Дата факта — первая разрешённая дата того же предложения
+        let mut dates: HashMap<usize, TimeValue> = HashMap::new();
+        for entity in &result.entities {
+            let idx = graph.upsert_primitive(&entity.text);
+            graph.add_tag(idx, &format!("{:?}", entity.entity_type))?;
+            if let Some(time) = entity.time {
+                dates.entry(entity.span.sentence_id).or_insert(time);
             }
-            
-            for action in &actions {
-                let action_text = format!("{} {} {}", action.subject, action.verb, action.object);
-                all_segments.push(action_text);
+        }
+
+        for action in &result.actions {
+            let subject = graph.upsert_primitive(&action.subject);
+            let object = graph.upsert_primitive(&action.object);
+            let mut edge = Edge::new(EdgeType::from_label(&action.verb, EdgeType::Action), action.verb.as_str(), action.confidence, source);
+            edge.polarity = action.polarity;
+            edge.modality = action.modality;
+            edge.time = dates.get(&action.span.sentence_id).copied();
+            edge.span = Some(action.span.text.clone());
+            if graph.ensure_edge(subject, object, edge)? {
+                edges_added += 1;
             }
         }
-        
-        // 3. Построение графа знаний
-        let _graph = self.build_knowledge_graph(vec![]); // Пустой вектор для демонстрации
-        
-        // 4. Извлечение узлов и связей для ответа
-        let graph_nodes: Vec<String> = all_entities.iter()
-            .map(|e| e.text.clone())
-            .collect();
-        
-        let graph_edges: Vec<(String, String, String)> = all_actions.iter()
-            .map(|a| (a.subject.clone(), a.verb.clone(), a.object.clone()))
-            .collect();
-        
-        ProcessingResult {
-            entities: all_entities,
-            actions: all_actions,
-            properties: all_properties,
-            segments: all_segments,
-            graph_nodes,
-            graph_edges,
+
+        for property in &result.properties {
+            let entity = graph.upsert_primitive(&property.entity);
+            let value = graph.upsert_primitive(&property.value);
+            let mut edge = Edge::new(EdgeType::from_label(&property.attribute, EdgeType::Property), property.attribute.as_str(), property.confidence, source);
+            edge.polarity = property.polarity;
+            edge.modality = property.modality;
+            edge.quantity = property.quantity.clone();
+            edge.time = dates.get(&property.span.sentence_id).copied();
+            edge.span = Some(property.span.text.clone());
+            if graph.ensure_edge(entity, value, edge)? {
+                edges_added += 1;
+            }
         }
+        graph.flush()?;
+
+        Ok(GraphUpdate {
+            nodes_added: graph.graph.node_count() - nodes_before,
+            edges_added,
+        })
     }
 
     /// Обучение на новых данных с верификацией
-    pub async fn learn_from_text(&mut self, text: &str, _annotation: &str) -> bool {
+    pub async fn learn_from_text(&mut self, text: &str, annotation: &str) -> bool {
         // Обработка текста
-        let _result = self.process_text(text).await;
-        
+        let result = self.process_text(text).await;
+        self.learn_from_result(text, &result, annotation).await
+    }
+
+    /// Обучение на уже обработанном тексте: результат (с датой документа и выбранными
+    /// этапами) записывается в граф как есть, без повторной обработки
+    pub async fn learn_from_result(&mut self, text: &str, result: &ProcessingResult, _annotation: &str) -> bool {
         // Проверка через TrustedScraper
-        let is_verified = self.scraper.check(text).await;
+        let report = self.scraper.check_multi(text).await;
+        self.learn_verified(text, result, &report)
+    }
+
+    /// Запись результата в граф по уже полученному отчёту проверки: подтверждённый факт
+    /// добавляется, остальные отправляются на модерацию
+    pub fn learn_verified(&self, text: &str, result: &ProcessingResult, report: &VerificationReport) -> bool {
+        let sources = report.confirming_sources();
         
-        if is_verified {
-            // Добавление в граф знаний
-            println!("✅ Факт верифицирован и добавлен: {}", text);
-            true
+        if report.verified() {
+            // Добавление в граф знаний (источник — первый подтвердивший)
+            let mut graph = self.graph.lock().unwrap();
+            match self.link_into_graph(result, &mut graph, sources[0]) {
+                Ok(update) => {
+                    println!("✅ Факт верифицирован и добавлен: {} (+{} узлов, +{} связей)", text, update.nodes_added, update.edges_added);
+                    true
+                },
+                Err(e) => {
+                    println!("⚠️ Не удалось записать факт в граф знаний: {}", e);
+                    false
+                }
+            }
         } else {
             // Отправка на модерацию
             println!("⚠️ Факт требует модерации: {}", text);
@@ -271,9 +698,14 @@ impl TextProcessor {
         }
     }
 
-    /// Верификация факта через TrustedScraper
-    pub async fn verify_fact(&self, fact: &str) -> bool {
-        self.scraper.check(fact).await
+    /// Скрапер процессора; клоны разделяют источники и их предохранители
+    pub fn scraper(&self) -> &TrustedScraper {
+        &self.scraper
+    }
+
+    /// Верификация факта через TrustedScraper: оценка, вердикт и доказательства
+    pub async fn verify_fact(&self, fact: &str) -> VerificationReport {
+        self.scraper.check_multi(fact).await
     }
 }
 
@@ -288,6 +720,8 @@ mod tests {
         let sentences = processor.preprocess_text(text);
         assert_eq!(sentences.len(), 2);
         assert!(sentences[0].contains("кот"));
+        // Словоформы приведены к начальной форме
+        assert!(sentences[0].contains("рыба"));
     }
 
     #[test]
@@ -308,6 +742,174 @@ mod tests {
         assert!(actions.iter().any(|a| a.subject == "кот" && a.verb == "ест"));
     }
 
+    #[tokio::test]
+    async fn test_link_into_graph_deduplicates() {
+        let processor = TextProcessor::new();
+        let result = processor.process_text("Кот ест рыбу. Рыба водится в море.").await;
+        let graph = processor.graph();
+        let mut graph = graph.lock().unwrap();
+
+        let first = processor.link_into_graph(&result, &mut graph, Source::Wikipedia).unwrap();
+        assert!(first.nodes_added > 0);
+        assert!(first.edges_added > 0);
+
+        let second = processor.link_into_graph(&result, &mut graph, Source::Wikipedia).unwrap();
+        assert_eq!(second.nodes_added, 0);
+        assert_eq!(second.edges_added, 0);
+
+        let cat = graph.find_primitive("кот").unwrap();
+        assert!(graph.tags(cat).iter().any(|t| t == "Animal"));
+        // "рыбу" и "рыба" сведены к одному узлу
+        assert!(graph.find_primitive("рыбу").is_none());
+        let fish = graph.find_primitive("рыба").unwrap();
+        let edge = &graph.graph[graph.find_edge_labeled(cat, fish, "ест").unwrap()];
+        assert_eq!(edge.kind, EdgeType::Action);
+        assert_eq!(edge.source, Source::Wikipedia);
+        // В ребре хранится исходный фрагмент текста
+        assert_eq!(edge.span.as_deref(), Some("Кот ест рыбу"));
+    }
+
+    #[tokio::test]
+    async fn test_negated_and_hedged_facts() {
+        let processor = TextProcessor::new();
+        let result = processor.process_text("Кот не ест рыбу. Возможно, собака ест мясо.").await;
+
+        let negated = result.actions.iter().find(|a| a.subject == "кот").unwrap();
+        assert_eq!((negated.verb.as_str(), negated.object.as_str()), ("ест", "рыба"));
+        assert_eq!(negated.polarity, Polarity::Negative);
+        assert_eq!(negated.span.text, "Кот не ест рыбу");
+
+        let hedged = result.actions.iter().find(|a| a.subject == "собака").unwrap();
+        assert_eq!(hedged.polarity, Polarity::Positive);
+        assert_eq!(hedged.modality, Modality::Possible);
+        assert!(hedged.confidence < processor.extract_actions("собака ест мясо")[0].confidence);
+
+        // Отрицание не становится утверждением в графе
+        let graph = processor.graph();
+        let mut graph = graph.lock().unwrap();
+        processor.link_into_graph(&result, &mut graph, Source::Extracted).unwrap();
+        let cat = graph.find_primitive("кот").unwrap();
+        let fish = graph.find_primitive("рыба").unwrap();
+        let edge = &graph.graph[graph.find_edge_labeled(cat, fish, "ест").unwrap()];
+        assert_eq!(edge.polarity, Polarity::Negative);
+    }
+
+    #[tokio::test]
+    async fn test_quantities_become_properties() {
+        let processor = TextProcessor::new();
+        let result = processor.process_text("Квадрокоптер — это летательный аппарат с 4 моторами. Масса кота 4500 г.").await;
+
+        let motors = result.properties.iter().find(|p| p.attribute == "мотор").unwrap();
+        assert_eq!((motors.entity.as_str(), motors.value.as_str()), ("аппарат", "4"));
+        assert_eq!(motors.span.text, "4 моторами");
+
+        let mass = result.properties.iter().find(|p| p.attribute == "масса").unwrap();
+        assert_eq!((mass.entity.as_str(), mass.value.as_str()), ("кот", "4.5 кг"));
+        assert_eq!(mass.quantity.as_ref().unwrap().value, 4.5);
+
+        // Величина сохраняется на ребре графа
+        let graph = processor.graph();
+        let mut graph = graph.lock().unwrap();
+        processor.link_into_graph(&result, &mut graph, Source::Extracted).unwrap();
+        let cat = graph.find_primitive("кот").unwrap();
+        let value = graph.find_primitive("4.5 кг").unwrap();
+        let edge = &graph.graph[graph.find_edge_labeled(cat, value, "масса").unwrap()];
+        assert_eq!(edge.quantity.as_ref().unwrap().unit, "кг");
+    }
+
+    #[tokio::test]
+    async fn test_dates_resolved_against_document_date() {
+        let processor = TextProcessor::new();
+        let document_date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let result = processor.process_text_at("Кот родился 5 мая 2023 года. Вчера кот съел 3 рыбы.", document_date).await;
+
+        let dates: Vec<&str> = result.entities.iter().filter(|e| e.entity_type == EntityType::Date).map(|e| e.text.as_str()).collect();
+        assert_eq!(dates, vec!["2023-05-05", "2024-03-14"]);
+        let born = result.entities.iter().find(|e| e.text == "2023-05-05").unwrap();
+        assert_eq!(born.span.text, "5 мая 2023 года");
+        assert_eq!(born.time.unwrap().start, NaiveDate::from_ymd_opt(2023, 5, 5).unwrap());
+        assert_eq!(result.document_date, Some(document_date));
+        // Числа внутри дат — не величины, "3 рыбы" — величина
+        assert!(result.properties.iter().all(|p| p.attribute != "май"));
+        assert!(result.properties.iter().any(|p| p.attribute == "рыба" && p.value == "3"));
+    }
+
+    #[tokio::test]
+    async fn test_dated_facts_queryable_by_time() {
+        let processor = TextProcessor::new();
+        let document_date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let result = processor.process_text_at("В 2020 году кот ест рыбу. В 2023 году собака ест мясо. Лиса ест мышей.", document_date).await;
+        let graph = processor.graph();
+        let mut graph = graph.lock().unwrap();
+        processor.link_into_graph(&result, &mut graph, Source::Extracted).unwrap();
+
+        // Дата предложения хранится на ребре факта
+        let cat = graph.find_primitive("кот").unwrap();
+        let fish = graph.find_primitive("рыба").unwrap();
+        let edge = &graph.graph[graph.find_edge_labeled(cat, fish, "ест").unwrap()];
+        assert_eq!(edge.time.unwrap().start, NaiveDate::from_ymd_opt(2020, 1, 1).unwrap());
+
+        let rows = crate::graph_query::Query::parse("?x ест ?y . FILTER time >= 2022").unwrap().execute(&graph).unwrap().rows;
+        assert_eq!(rows.len(), 1);
+        assert_eq!((rows[0].bindings["x"].as_str(), rows[0].bindings["y"].as_str()), ("собака", "мясо"));
+        assert_eq!(rows[0].edges[0].time.unwrap().start, NaiveDate::from_ymd_opt(2023, 1, 1).unwrap());
+        // Факты без даты фильтр по времени не проходят
+        let rows = crate::graph_query::Query::parse("?x ест ?y . FILTER time < 2022").unwrap().execute(&graph).unwrap().rows;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].bindings["x"], "кот");
+    }
+
+    #[tokio::test]
+    async fn test_pronouns_resolved_across_sentences() {
+        let processor = TextProcessor::new();
+        let result = processor.process_text("Кот ест рыбу. Он живет дома.").await;
+        let lives = result.properties.iter().find(|p| p.attribute == "живет").unwrap();
+        assert_eq!(lives.entity, "кот");
+        assert_eq!(lives.span.text, "Он живет дома");
+        assert_eq!(result.coreferences.len(), 1);
+        assert_eq!(result.coreferences[0].antecedent_sentence_id, 0);
+
+        // Род выбирает антецедент: "она" — собака, а не ближайший кот
+        let result = processor.process_text("Собака видит кота. Она ест рыбу.").await;
+        assert!(result.graph_edges.contains(&("собака".to_string(), "ест".to_string(), "рыба".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_spans_point_into_original_text() {
+        let processor = TextProcessor::new();
+        let text = "<p>Привет.</p> <b>Кот</b> ест рыбу! Рыба водится в Москве.";
+        let result = processor.process_text(text).await;
+
+        assert_eq!(result.sentences.len(), 3);
+        assert_eq!(result.sentences[1].span.text, "Кот ест рыбу!");
+
+        // Смещения указывают в исходный текст, но разметка в текст фрагмента не попадает
+        let action = result.actions.iter().find(|a| a.verb == "ест").unwrap();
+        assert_eq!(action.span.sentence_id, 1);
+        assert_eq!(&text[action.span.start..action.span.end], "Кот</b> ест рыбу");
+        assert_eq!(action.span.text, "Кот ест рыбу");
+
+        let city = result.entities.iter().find(|e| e.text == "москва").unwrap();
+        assert_eq!(city.span.text, "Москве");
+        assert_eq!(city.span.sentence_id, 2);
+        let chars: Vec<char> = text.chars().collect();
+        assert_eq!(chars[city.span.char_start..city.span.char_end].iter().collect::<String>(), "Москве");
+    }
+
+    #[tokio::test]
+    async fn test_english_text() {
+        let processor = TextProcessor::new();
+        let result = processor.process_text("The cats eat fish. Whales live in oceans.").await;
+
+        assert_eq!(result.language, Language::English);
+        assert!(result.entities.iter().any(|e| e.text == "cat" && e.entity_type == EntityType::Animal));
+        assert!(result.actions.iter().any(|a| a.subject == "cat" && a.verb == "eat" && a.object == "fish"));
+        assert!(result.properties.iter().any(|p| p.entity == "whale" && p.value == "ocean"));
+        // Русские правила к английскому тексту не применяются и наоборот
+        assert!(processor.extract_actions_in("Кот ест рыбу", Language::English).is_empty());
+        assert_eq!(processor.process_text("Кот ест рыбу.").await.language, Language::Russian);
+    }
+
     #[tokio::test]
     async fn test_process_text() {
         let processor = TextProcessor::new();
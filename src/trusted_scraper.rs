@@ -1,79 +1,445 @@
-use reqwest::Client;
+use reqwest::{Client, Url};
 use tokio;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::core::Source;
+use crate::fact_source::{BoxFuture, Evidence, EvidenceDocument, FactSource, SourceRegistry, SourceStatus};
+use crate::feeds::{self, Publication};
+use crate::language::detect_language;
+use crate::resilience::{FetchError, HttpPolicy, PoliteClient};
+use crate::verification::VerificationReport;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref HTML_TAG: Regex = Regex::new(r"<[^>]*>").unwrap();
+}
+
+/// Сколько документов запрашивать у каждого источника
+const MAX_RESULTS: usize = 5;
+
+/// Заголовок User-Agent (Wikipedia и NCBI просят представляться)
+const USER_AGENT: &str = "metastasa/0.1 (TrustedScraper)";
+
+/// Минимальные интервалы между запросами, которых просят сами источники
+const WIKIPEDIA_INTERVAL: Duration = Duration::from_millis(100);
+const ARXIV_INTERVAL: Duration = Duration::from_secs(3);
+const PUBMED_INTERVAL: Duration = Duration::from_millis(350); // 3 запроса в секунду без API-ключа
+
+/// Убрать HTML-разметку и сущности из фрагмента поиска
+fn strip_html(html: &str) -> String {
+    HTML_TAG.replace_all(html, "")
+        .replace("&quot;", "\"")
+        .replace("&#039;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Ответ источника: найденные документы или ошибка, с числом запросов и повторов
+fn to_evidence(source: &dyn FactSource, documents: Result<Vec<EvidenceDocument>, FetchError>, status: SourceStatus) -> Evidence {
+    match documents {
+        Ok(documents) => Evidence::documents(source, documents),
+        Err(e) => Evidence::failed(source, e),
+    }
+    .with_status(status)
+}
+
+/// Политика источника: не чаще, чем он просит, даже если общая политика допускает больше
+fn polite(client: Client, policy: HttpPolicy, min_interval: Duration) -> PoliteClient {
+    let min_interval = policy.min_interval.max(min_interval);
+    PoliteClient::new(client, HttpPolicy { min_interval, ..policy })
+}
+
+/// Поиск по Wikipedia (раздел на языке утверждения)
+pub struct WikipediaSource {
+    client: PoliteClient,
+}
+
+impl WikipediaSource {
+    pub fn new(client: Client) -> Self {
+        Self::with_policy(client, HttpPolicy::default())
+    }
+
+    /// Свои таймауты и повторы (запросы — не чаще раза в 100 мс)
+    pub fn with_policy(client: Client, policy: HttpPolicy) -> Self {
+        Self { client: polite(client, policy, WIKIPEDIA_INTERVAL) }
+    }
+
+    async fn documents(&self, claim: &str, status: &mut SourceStatus) -> Result<Vec<EvidenceDocument>, FetchError> {
+        let keywords = feeds::keywords(claim);
+        if keywords.is_empty() {
+            return Ok(Vec::new());
+        }
+        let language = detect_language(claim);
+        let api = format!("https://{}.wikipedia.org/w/api.php", language.code());
+        let limit = MAX_RESULTS.to_string();
+        let query = keywords.join(" ");
+        let params = [("action", "query"), ("list", "search"), ("srsearch", query.as_str()), ("srlimit", limit.as_str()), ("format", "json")];
+        let json: serde_json::Value = serde_json::from_str(&self.client.get_text(&api, &params, status).await?)?;
+
+        let mut documents = Vec::new();
+        for result in json["query"]["search"].as_array().into_iter().flatten() {
+            let Some(title) = result["title"].as_str() else { continue };
+            // Заголовок — один сегмент пути: пробелы → "_", остальное кодирует Url
+            let mut url = Url::parse(&format!("https://{}.wikipedia.org/wiki/", language.code()))?;
+            url.path_segments_mut().map_err(|_| "некорректный адрес Wikipedia")?.pop_if_empty().push(&title.replace(' ', "_"));
+            documents.push(EvidenceDocument::new(title, Some(url.to_string()), strip_html(result["snippet"].as_str().unwrap_or(""))));
+        }
+        Ok(documents)
+    }
+}
+
+impl FactSource for WikipediaSource {
+    fn name(&self) -> &str {
+        "wikipedia"
+    }
+
+    fn source(&self) -> Source {
+        Source::Wikipedia
+    }
+
+    fn weight(&self) -> f32 {
+        0.8
+    }
+
+    fn search<'a>(&'a self, claim: &'a str) -> BoxFuture<'a, Evidence> {
+        Box::pin(async move {
+            let mut status = SourceStatus::default();
+            let documents = self.documents(claim, &mut status).await;
+            to_evidence(self, documents, status)
+        })
+    }
+}
+
+/// Поиск по arXiv (лента Atom)
+pub struct ArxivSource {
+    client: PoliteClient,
+}
+
+impl ArxivSource {
+    pub fn new(client: Client) -> Self {
+        Self::with_policy(client, HttpPolicy::default())
+    }
+
+    /// Свои таймауты и повторы (запросы — не чаще раза в 3 с, как просит arXiv)
+    pub fn with_policy(client: Client, policy: HttpPolicy) -> Self {
+        Self { client: polite(client, policy, ARXIV_INTERVAL) }
+    }
+
+    /// Статьи, содержащие все ключевые слова утверждения
+    pub async fn publications(&self, claim: &str, status: &mut SourceStatus) -> Result<Vec<Publication>, FetchError> {
+        let keywords = feeds::keywords(claim);
+        if keywords.is_empty() {
+            return Ok(Vec::new());
+        }
+        let query = keywords.iter().map(|k| format!("all:{}", k)).collect::<Vec<_>>().join(" AND ");
+        let limit = MAX_RESULTS.to_string();
+        let params = [("search_query", query.as_str()), ("max_results", limit.as_str())];
+        let xml = self.client.get_text("https://export.arxiv.org/api/query", &params, status).await?;
+        Ok(feeds::parse_arxiv(&xml)?)
+    }
+}
+
+impl FactSource for ArxivSource {
+    fn name(&self) -> &str {
+        "arxiv"
+    }
+
+    fn source(&self) -> Source {
+        Source::ArXiv
+    }
+
+    fn weight(&self) -> f32 {
+        0.7
+    }
+
+    fn search<'a>(&'a self, claim: &'a str) -> BoxFuture<'a, Evidence> {
+        Box::pin(async move {
+            let mut status = SourceStatus::default();
+            let documents = self.publications(claim, &mut status).await.map(|p| p.into_iter().map(EvidenceDocument::from).collect());
+            to_evidence(self, documents, status)
+        })
+    }
+}
+
+/// Поиск по PubMed (E-utilities)
+pub struct PubMedSource {
+    client: PoliteClient,
+}
+
+impl PubMedSource {
+    const EUTILS: &'static str = "https://eutils.ncbi.nlm.nih.gov/entrez/eutils";
+
+    pub fn new(client: Client) -> Self {
+        Self::with_policy(client, HttpPolicy::default())
+    }
+
+    /// Свои таймауты и повторы (запросы — не чаще трёх в секунду, лимит NCBI)
+    pub fn with_policy(client: Client, policy: HttpPolicy) -> Self {
+        Self { client: polite(client, policy, PUBMED_INTERVAL) }
+    }
+
+    /// eSearch → идентификаторы, eSummary → заголовки и даты, eFetch → аннотации.
+    /// Без аннотаций (eFetch недоступен) статьи оцениваются по заголовкам
+    pub async fn publications(&self, claim: &str, status: &mut SourceStatus) -> Result<Vec<Publication>, FetchError> {
+        let keywords = feeds::keywords(claim);
+        if keywords.is_empty() {
+            return Ok(Vec::new());
+        }
+        let term = keywords.join(" ");
+        let limit = MAX_RESULTS.to_string();
+        let search = [("db", "pubmed"), ("term", term.as_str()), ("retmax", limit.as_str())];
+        let ids = feeds::parse_esearch(&self.client.get_text(&format!("{}/esearch.fcgi", Self::EUTILS), &search, status).await?)?;
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let ids = ids.join(",");
+        let summary = [("db", "pubmed"), ("id", ids.as_str())];
+        let mut publications = feeds::parse_esummary(&self.client.get_text(&format!("{}/esummary.fcgi", Self::EUTILS), &summary, status).await?)?;
+
+        let fetch = [("db", "pubmed"), ("id", ids.as_str()), ("rettype", "abstract"), ("retmode", "xml")];
+        match self.client.get_text(&format!("{}/efetch.fcgi", Self::EUTILS), &fetch, status).await.and_then(|xml| Ok(feeds::parse_efetch_abstracts(&xml)?)) {
+            Ok(mut abstracts) => {
+                for publication in publications.iter_mut() {
+                    publication.summary = abstracts.remove(&publication.id).unwrap_or_default();
+                }
+            },
+            Err(e) => println!("⚠️ PubMed: аннотации недоступны: {}", e),
+        }
+        Ok(publications)
+    }
+}
+
+impl FactSource for PubMedSource {
+    fn name(&self) -> &str {
+        "pubmed"
+    }
+
+    fn source(&self) -> Source {
+        Source::PubMed
+    }
+
+    fn weight(&self) -> f32 {
+        0.9
+    }
+
+    fn search<'a>(&'a self, claim: &'a str) -> BoxFuture<'a, Evidence> {
+        Box::pin(async move {
+            let mut status = SourceStatus::default();
+            let documents = self.publications(claim, &mut status).await.map(|p| p.into_iter().map(EvidenceDocument::from).collect());
+            to_evidence(self, documents, status)
+        })
+    }
+}
 
 #[derive(Clone)]
-/// TrustedScraper: асинхронный сбор и проверка фактов
+/// TrustedScraper: асинхронный сбор и проверка фактов по зарегистрированным источникам
 pub struct TrustedScraper {
     client: reqwest::Client,
+    policy: HttpPolicy,
+    sources: SourceRegistry,
+    min_confirmations: usize, // Сколько источников должны подтвердить утверждение
 }
 
 impl TrustedScraper {
+    /// Скрапер со стандартными источниками: Wikipedia, arXiv, PubMed
     pub fn new() -> Self {
-        Self {
-            client: Client::new(),
-        }
+        Self::with_policy(HttpPolicy::default())
+    }
+
+    /// Стандартные источники со своими таймаутами и повторами запросов
+    pub fn with_policy(policy: HttpPolicy) -> Self {
+        let client = Self::http_client();
+        let mut sources = SourceRegistry::new();
+        sources.register(Arc::new(WikipediaSource::with_policy(client.clone(), policy.clone())));
+        sources.register(Arc::new(ArxivSource::with_policy(client.clone(), policy.clone())));
+        sources.register(Arc::new(PubMedSource::with_policy(client.clone(), policy.clone())));
+        Self { client, policy, sources, min_confirmations: 2 }
+    }
+
+    /// Скрапер с заданным набором источников (например, только внутренних)
+    pub fn with_sources(sources: SourceRegistry) -> Self {
+        Self { client: Self::http_client(), policy: HttpPolicy::default(), sources, min_confirmations: 2 }
+    }
+
+    fn http_client() -> Client {
+        Client::builder().user_agent(USER_AGENT).build().unwrap_or_default()
     }
-    /// Асинхронная проверка утверждения через Wikipedia
+
+    /// Сколько ждать ответа каждого источника (вместе с повторами), по умолчанию 30 с
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.sources.set_timeout(timeout);
+        self
+    }
+
+    /// Сколько подтверждений достаточно (1 — для единственного локального источника)
+    pub fn with_min_confirmations(mut self, min_confirmations: usize) -> Self {
+        self.min_confirmations = min_confirmations.max(1);
+        self
+    }
+
+    pub fn min_confirmations(&self) -> usize {
+        self.min_confirmations
+    }
+
+    /// Добавить источник (или заменить источник с тем же именем)
+    pub fn register(&mut self, source: Arc<dyn FactSource>) {
+        self.sources.register(source);
+    }
+
+    /// Зарегистрированные источники
+    pub fn sources(&self) -> &SourceRegistry {
+        &self.sources
+    }
+
+    pub fn sources_mut(&mut self) -> &mut SourceRegistry {
+        &mut self.sources
+    }
+
+    /// Проверка одним источником: есть ли документ, подтверждающий утверждение.
+    /// Зарегистрированный источник с тем же именем предпочтительнее (общий ограничитель частоты)
+    async fn check_single(&self, name: &str, fallback: impl FnOnce() -> Arc<dyn FactSource>, claim: &str) -> bool {
+        let source = self.sources.get(name).cloned().unwrap_or_else(fallback);
+        VerificationReport::aggregate(claim, vec![source.search(claim).await], 1).verified()
+    }
+    /// Асинхронная проверка утверждения через Wikipedia (раздел на языке утверждения)
     pub async fn check_wikipedia(&self, claim: &str) -> bool {
-        let url = format!("https://ru.wikipedia.org/w/api.php?action=query&list=search&srsearch={}&format=json", claim);
-        match self.client.get(&url).send().await {
-            Ok(resp) => {
-                if let Ok(json) = resp.json::<serde_json::Value>().await {
-                    let hits = json["query"]["search"].as_array().map(|a| !a.is_empty()).unwrap_or(false);
-                    return hits;
-                }
-                false
-            },
-            Err(_) => false,
-        }
+        self.check_single("wikipedia", || Arc::new(WikipediaSource::with_policy(self.client.clone(), self.policy.clone())), claim).await
     }
     /// Проверка по arXiv (реальный запрос)
     pub async fn check_arxiv(&self, claim: &str) -> bool {
-        let url = format!("http://export.arxiv.org/api/query?search_query=all:{}&max_results=1", claim);
-        match self.client.get(&url).send().await {
-            Ok(resp) => {
-                if let Ok(text) = resp.text().await {
-                    // arXiv возвращает XML, ищем <entry>
-                    text.contains("<entry>")
-                } else { false }
-            },
-            Err(_) => false,
-        }
+        self.check_single("arxiv", || Arc::new(ArxivSource::with_policy(self.client.clone(), self.policy.clone())), claim).await
     }
     /// Проверка по PubMed (реальный запрос)
     pub async fn check_pubmed(&self, claim: &str) -> bool {
-        let url = format!("https://eutils.ncbi.nlm.nih.gov/entrez/eutils/esearch.fcgi?db=pubmed&term={}&retmax=1", claim);
-        match self.client.get(&url).send().await {
-            Ok(resp) => {
-                if let Ok(text) = resp.text().await {
-                    // PubMed возвращает XML, ищем <IdList><Id>
-                    text.contains("<IdList>") && text.contains("<Id>")
-                } else { false }
-            },
-            Err(_) => false,
-        }
+        self.check_single("pubmed", || Arc::new(PubMedSource::with_policy(self.client.clone(), self.policy.clone())), claim).await
+    }
+    /// Ответы всех зарегистрированных источников (без оценки, опрос параллельный)
+    pub async fn evidence(&self, claim: &str) -> Vec<Evidence> {
+        self.sources.search(claim).await
     }
-    /// Проверка по нескольким источникам (Wikipedia + arXiv + PubMed)
-    pub async fn check_multi(&self, claim: &str) -> usize {
-        let mut count = 0;
-        if self.check_wikipedia(claim).await { count += 1; }
-        if self.check_arxiv(claim).await { count += 1; }
-        if self.check_pubmed(claim).await { count += 1; }
-        count
+    /// Источники, подтвердившие утверждение (в порядке опроса)
+    pub async fn confirming_sources(&self, claim: &str) -> Vec<Source> {
+        self.check_multi(claim).await.confirming_sources()
     }
-    /// Главная проверка: найдено ли 2+ подтверждения
+    /// Проверка по нескольким источникам (по умолчанию Wikipedia + arXiv + PubMed):
+    /// оценка документов и итоговая уверенность
+    pub async fn check_multi(&self, claim: &str) -> VerificationReport {
+        VerificationReport::aggregate(claim, self.evidence(claim).await, self.min_confirmations)
+    }
+    /// Главная проверка: подтверждено ли утверждение (по умолчанию нужно 2+ источника)
     pub async fn check(&self, claim: &str) -> bool {
-        self.check_multi(claim).await >= 2
+        self.check_multi(claim).await.verified()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::resilience::{BreakerConfig, CircuitState};
+    use crate::verification::Verdict;
+
+    /// Внутренний источник: все записи глоссария (оценка — на стороне скрапера)
+    struct Glossary {
+        name: &'static str,
+        entries: &'static [&'static str],
+    }
+
+    impl FactSource for Glossary {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn search<'a>(&'a self, _claim: &'a str) -> BoxFuture<'a, Evidence> {
+            let documents = self.entries.iter().map(|e| EvidenceDocument::new(self.name, None, *e)).collect();
+            Box::pin(async move { Evidence::documents(self, documents) })
+        }
+    }
+
+    /// Источник, который отвечает с задержкой (или не отвечает вовсе)
+    struct Slow {
+        name: &'static str,
+        delay: Duration,
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    impl FactSource for Slow {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn search<'a>(&'a self, _claim: &'a str) -> BoxFuture<'a, Evidence> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Box::pin(async move {
+                tokio::time::sleep(self.delay).await;
+                Evidence::documents(self, vec![EvidenceDocument::new(self.name, None, "Кот ест рыбу.")])
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timeouts_and_circuit_breaker() {
+        let slow = |name, millis| Arc::new(Slow { name, delay: Duration::from_millis(millis), calls: Default::default() });
+        let hanging = slow("hanging", 10_000);
+        let breaker = BreakerConfig { failure_threshold: 2, cooldown: Duration::from_secs(60) };
+        let mut sources = SourceRegistry::new().with_timeout(Duration::from_millis(300)).with_breaker(breaker);
+        sources.register(slow("a", 150));
+        sources.register(slow("b", 150));
+        sources.register(hanging.clone());
+        let scraper = TrustedScraper::with_sources(sources);
+
+        // Источники опрашиваются параллельно: общее время — по самому долгому (таймауту)
+        let started = std::time::Instant::now();
+        let report = scraper.check_multi("Кот ест рыбу").await;
+        assert!(started.elapsed() < Duration::from_millis(550));
+        assert!(report.verified());
+        assert_eq!(report.unavailable, vec!["hanging"]);
+        assert!(report.evidence[2].status.timed_out);
+        assert_eq!(report.evidence[2].status.circuit, CircuitState::Closed);
+
+        // Вторая неудача подряд отключает источник; третий опрос его не вызывает
+        scraper.check_multi("Кот ест рыбу").await;
+        assert_eq!(scraper.sources().circuit("hanging"), Some(CircuitState::Open));
+        let report = scraper.check_multi("Кот ест рыбу").await;
+        assert_eq!(report.evidence[2].status.circuit, CircuitState::Open);
+        assert_eq!(hanging.calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert_eq!(report.evidence[0].status.circuit, CircuitState::Closed);
+    }
+
     #[tokio::test]
     async fn test_check_wikipedia() {
         let scraper = TrustedScraper::new();
         let found = scraper.check_wikipedia("Кошка").await;
         assert!(found);
     }
-} 
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_custom_sources() {
+        assert_eq!(TrustedScraper::new().sources().names(), vec!["wikipedia", "arxiv", "pubmed"]);
+
+        let mut scraper = TrustedScraper::with_sources(SourceRegistry::new());
+        scraper.register(Arc::new(Glossary { name: "wiki", entries: &["Кот — хищник. Кот ест рыбу и мясо.", "Собака — друг человека."] }));
+        scraper.register(Arc::new(Glossary { name: "docs", entries: &["Домашний кот охотно ест рыбу."] }));
+        assert!(scraper.check("Кот ест рыбу").await);
+        assert!(!scraper.check("Собака ест рыбу").await);
+
+        let evidence = scraper.evidence("Кот ест рыбу").await;
+        assert_eq!(evidence[0].hits, 2);
+        assert_eq!(evidence[1].source, Source::Internal);
+
+        // Замена по имени сохраняет порядок, удаление — убирает источник
+        scraper.register(Arc::new(Glossary { name: "wiki", entries: &["Кот не ест рыбу."] }));
+        assert_eq!(scraper.sources().names(), vec!["wiki", "docs"]);
+        let report = scraper.check_multi("Кот ест рыбу").await;
+        assert_eq!(report.confirmations, 1);
+        assert!(report.contradiction > 0.0 && report.score < report.support);
+        assert!(!scraper.check("Кот ест рыбу").await);
+        assert!(scraper.sources_mut().unregister("docs").is_some());
+        assert_eq!(scraper.check_multi("Кот ест рыбу").await.verdict, Verdict::Refuted);
+    }
+}
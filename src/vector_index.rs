@@ -0,0 +1,437 @@
+// vector_index.rs
+// Приближённый поиск ближайших соседей (HNSW) для эмбеддингов узлов графа знаний
+//
+// Hierarchical Navigable Small World: многоуровневый граф близости.
+// Верхние уровни разрежены и служат для быстрого спуска к нужной области,
+// нижний уровень содержит все векторы. Расстояние — 1 - косинусное сходство.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use sled::Tree;
+use uuid::Uuid;
+
+/// Ключ записи с описанием модели эмбеддингов (ключи векторов — 16 байт uuid)
+const MODEL_KEY: &[u8] = b"meta:model";
+
+/// Максимальное число соседей на верхних уровнях (на нулевом — вдвое больше)
+const DEFAULT_M: usize = 16;
+/// Ширина поиска при построении
+const DEFAULT_EF_CONSTRUCTION: usize = 100;
+/// Минимальная ширина поиска при запросе
+const DEFAULT_EF_SEARCH: usize = 50;
+
+/// Ошибка векторного индекса
+#[derive(Debug)]
+pub enum IndexError {
+    Storage(sled::Error),
+    Dimension { expected: usize, found: usize },
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexError::Storage(e) => write!(f, "Ошибка хранилища векторов: {}", e),
+            IndexError::Dimension { expected, found } => {
+                write!(f, "Размерность вектора {} не совпадает с размерностью индекса {}", found, expected)
+            },
+        }
+    }
+}
+
+impl Error for IndexError {}
+
+impl From<sled::Error> for IndexError {
+    fn from(e: sled::Error) -> Self {
+        IndexError::Storage(e)
+    }
+}
+
+/// Кандидат поиска: расстояние до запроса и внутренний номер узла
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Candidate {
+    distance: f32,
+    node: usize,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .partial_cmp(&other.distance)
+            .unwrap_or(Ordering::Equal)
+            .then(self.node.cmp(&other.node))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// HNSW-индекс векторов, ключом служит стабильный id узла графа
+pub struct VectorIndex {
+    dimension: usize,
+    m: usize,
+    ef_construction: usize,
+    level_mult: f64,
+    ids: Vec<Uuid>,                  // внутренний номер -> id узла
+    vectors: Vec<Vec<f32>>,          // нормализованные векторы
+    neighbors: Vec<Vec<Vec<usize>>>, // [узел][уровень] -> соседи
+    deleted: Vec<bool>,              // удалённые узлы остаются в графе как транзитные
+    by_id: HashMap<Uuid, usize>,
+    entry_point: Option<usize>,
+    max_level: usize,
+    tree: Option<Tree>,
+}
+
+impl VectorIndex {
+    /// Создать пустой индекс в памяти
+    pub fn new(dimension: usize) -> Self {
+        Self {
+            dimension,
+            m: DEFAULT_M,
+            ef_construction: DEFAULT_EF_CONSTRUCTION,
+            level_mult: 1.0 / (DEFAULT_M as f64).ln(),
+            ids: Vec::new(),
+            vectors: Vec::new(),
+            neighbors: Vec::new(),
+            deleted: Vec::new(),
+            by_id: HashMap::new(),
+            entry_point: None,
+            max_level: 0,
+            tree: None,
+        }
+    }
+
+    /// Открыть индекс поверх дерева sled и перестроить граф из сохранённых векторов.
+    /// Если векторы сохранены другой моделью (`model`), дерево очищается —
+    /// векторы разных моделей несравнимы, их нужно пересчитать.
+    pub fn open(tree: Tree, dimension: usize, model: &str) -> Result<Self, IndexError> {
+        let mut index = Self::new(dimension);
+        let stored_model = tree.get(MODEL_KEY)?;
+        if stored_model.as_deref() != Some(model.as_bytes()) {
+            tree.clear()?;
+            tree.insert(MODEL_KEY, model.as_bytes())?;
+        }
+
+        for entry in tree.iter() {
+            let (key, value) = entry?;
+            let Ok(bytes) = <[u8; 16]>::try_from(key.as_ref()) else { continue };
+            let vector: Vec<f32> = value
+                .chunks_exact(4)
+                .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+                .collect();
+            if vector.len() == dimension {
+                index.insert_in_memory(Uuid::from_bytes(bytes), vector);
+            }
+        }
+
+        index.tree = Some(tree);
+        Ok(index)
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Число активных (не удалённых) векторов
+    pub fn len(&self) -> usize {
+        self.by_id.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_id.is_empty()
+    }
+
+    pub fn contains(&self, id: Uuid) -> bool {
+        self.by_id.contains_key(&id)
+    }
+
+    /// Добавить или заменить вектор узла (с записью в sled, если индекс открыт на диске)
+    pub fn insert(&mut self, id: Uuid, vector: Vec<f32>) -> Result<(), IndexError> {
+        if vector.len() != self.dimension {
+            return Err(IndexError::Dimension { expected: self.dimension, found: vector.len() });
+        }
+        if let Some(tree) = &self.tree {
+            let bytes: Vec<u8> = vector.iter().flat_map(|v| v.to_le_bytes()).collect();
+            tree.insert(id.as_bytes(), bytes)?;
+        }
+        self.insert_in_memory(id, vector);
+        Ok(())
+    }
+
+    /// Удалить вектор узла
+    pub fn remove(&mut self, id: Uuid) -> Result<bool, IndexError> {
+        if let Some(tree) = &self.tree {
+            tree.remove(id.as_bytes())?;
+        }
+        match self.by_id.remove(&id) {
+            Some(node) => {
+                self.deleted[node] = true;
+                Ok(true)
+            },
+            None => Ok(false),
+        }
+    }
+
+    /// k ближайших векторов: пары (id, косинусное сходство) по убыванию сходства.
+    /// `filter` отбирает допустимые id; при строгом фильтре ширина поиска растёт.
+    pub fn search(&self, query: &[f32], k: usize, filter: Option<&dyn Fn(&Uuid) -> bool>) -> Vec<(Uuid, f32)> {
+        if k == 0 || query.len() != self.dimension {
+            return Vec::new();
+        }
+        let query = normalized(query);
+        let mut ef = k.max(DEFAULT_EF_SEARCH);
+        loop {
+            let hits = self.accepted(&self.search_all(&query, ef), filter);
+            if hits.len() >= k || ef >= self.vectors.len() {
+                return hits.into_iter().take(k).collect();
+            }
+            ef *= 2;
+        }
+    }
+
+    /// Все векторы со сходством не ниже `min_similarity`, по убыванию сходства
+    pub fn search_radius(&self, query: &[f32], min_similarity: f32, filter: Option<&dyn Fn(&Uuid) -> bool>) -> Vec<(Uuid, f32)> {
+        if query.len() != self.dimension {
+            return Vec::new();
+        }
+        let query = normalized(query);
+        let mut ef = DEFAULT_EF_SEARCH;
+        loop {
+            let found = self.search_all(&query, ef);
+            // Если даже самый дальний из найденных внутри радиуса — радиус покрыт не полностью
+            let covered = found.last().is_none_or(|c| 1.0 - c.distance < min_similarity);
+            if covered || ef >= self.vectors.len() {
+                return self
+                    .accepted(&found, filter)
+                    .into_iter()
+                    .filter(|(_, sim)| *sim >= min_similarity)
+                    .collect();
+            }
+            ef *= 2;
+        }
+    }
+
+    fn accepted(&self, found: &[Candidate], filter: Option<&dyn Fn(&Uuid) -> bool>) -> Vec<(Uuid, f32)> {
+        found
+            .iter()
+            .filter(|c| !self.deleted[c.node])
+            .map(|c| (self.ids[c.node], 1.0 - c.distance))
+            .filter(|(id, _)| filter.is_none_or(|f| f(id)))
+            .collect()
+    }
+
+    /// Спуск по уровням и поиск шириной `ef` на нулевом уровне
+    fn search_all(&self, query: &[f32], ef: usize) -> Vec<Candidate> {
+        let Some(entry) = self.entry_point else { return Vec::new() };
+        let mut entry_points = vec![entry];
+        for layer in (1..=self.max_level).rev() {
+            entry_points = vec![self.search_layer(query, &entry_points, 1, layer)[0].node];
+        }
+        self.search_layer(query, &entry_points, ef, 0)
+    }
+
+    fn insert_in_memory(&mut self, id: Uuid, vector: Vec<f32>) {
+        if let Some(old) = self.by_id.remove(&id) {
+            self.deleted[old] = true;
+        }
+
+        let node = self.ids.len();
+        let level = self.random_level(id);
+        self.ids.push(id);
+        self.vectors.push(normalized(&vector));
+        self.neighbors.push(vec![Vec::new(); level + 1]);
+        self.deleted.push(false);
+        self.by_id.insert(id, node);
+
+        let Some(entry) = self.entry_point else {
+            self.entry_point = Some(node);
+            self.max_level = level;
+            return;
+        };
+
+        let query = self.vectors[node].clone();
+        let mut entry_points = vec![entry];
+        for layer in (level + 1..=self.max_level).rev() {
+            entry_points = vec![self.search_layer(&query, &entry_points, 1, layer)[0].node];
+        }
+
+        for layer in (0..=level.min(self.max_level)).rev() {
+            let found = self.search_layer(&query, &entry_points, self.ef_construction, layer);
+            let selected: Vec<usize> = found.iter().map(|c| c.node).filter(|&n| n != node).take(self.m).collect();
+            let capacity = if layer == 0 { self.m * 2 } else { self.m };
+
+            for &neighbor in &selected {
+                self.neighbors[neighbor][layer].push(node);
+                if self.neighbors[neighbor][layer].len() > capacity {
+                    self.prune(neighbor, layer, capacity);
+                }
+            }
+            self.neighbors[node][layer] = selected;
+            entry_points = found.iter().map(|c| c.node).collect();
+        }
+
+        if level > self.max_level {
+            self.max_level = level;
+            self.entry_point = Some(node);
+        }
+    }
+
+    /// Оставить у узла `capacity` ближайших соседей на уровне
+    fn prune(&mut self, node: usize, layer: usize, capacity: usize) {
+        let base = &self.vectors[node];
+        let mut scored: Vec<Candidate> = self.neighbors[node][layer]
+            .iter()
+            .map(|&n| Candidate { distance: distance(base, &self.vectors[n]), node: n })
+            .collect();
+        scored.sort();
+        self.neighbors[node][layer] = scored.into_iter().take(capacity).map(|c| c.node).collect();
+    }
+
+    /// Жадный поиск на одном уровне; результат отсортирован по возрастанию расстояния
+    fn search_layer(&self, query: &[f32], entry_points: &[usize], ef: usize, layer: usize) -> Vec<Candidate> {
+        let mut visited: HashSet<usize> = entry_points.iter().copied().collect();
+        let mut candidates = BinaryHeap::new();
+        let mut results = BinaryHeap::new();
+
+        for &ep in entry_points {
+            let c = Candidate { distance: distance(query, &self.vectors[ep]), node: ep };
+            candidates.push(Reverse(c));
+            results.push(c);
+        }
+        while results.len() > ef {
+            results.pop();
+        }
+
+        while let Some(Reverse(current)) = candidates.pop() {
+            let worst = results.peek().map(|c: &Candidate| c.distance).unwrap_or(f32::MAX);
+            if current.distance > worst && results.len() >= ef {
+                break;
+            }
+            let Some(neighbors) = self.neighbors[current.node].get(layer) else { continue };
+            for &n in neighbors {
+                if !visited.insert(n) {
+                    continue;
+                }
+                let d = distance(query, &self.vectors[n]);
+                let worst = results.peek().map(|c: &Candidate| c.distance).unwrap_or(f32::MAX);
+                if results.len() < ef || d < worst {
+                    let c = Candidate { distance: d, node: n };
+                    candidates.push(Reverse(c));
+                    results.push(c);
+                    if results.len() > ef {
+                        results.pop();
+                    }
+                }
+            }
+        }
+
+        results.into_sorted_vec()
+    }
+
+    /// Уровень узла: детерминирован по id, чтобы индекс перестраивался одинаково
+    fn random_level(&self, id: Uuid) -> usize {
+        let (bits, _) = id.as_u64_pair();
+        let uniform = ((bits >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+        (-uniform.ln() * self.level_mult).floor() as usize
+    }
+}
+
+fn normalized(vector: &[f32]) -> Vec<f32> {
+    let norm: f32 = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter().map(|x| x / norm).collect()
+    } else {
+        vector.to_vec()
+    }
+}
+
+/// Косинусное расстояние между нормализованными векторами
+fn distance(a: &[f32], b: &[f32]) -> f32 {
+    1.0 - a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding_cache::EmbeddingCache;
+
+    /// Детерминированные псевдослучайные векторы (LCG)
+    fn random_vectors(count: usize, dimension: usize, seed: u64) -> Vec<Vec<f32>> {
+        let mut state = seed;
+        (0..count)
+            .map(|_| {
+                (0..dimension)
+                    .map(|_| {
+                        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                        ((state >> 33) as f32 / (1u64 << 31) as f32) - 0.5
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_recall_against_linear_scan() {
+        let vectors = random_vectors(500, 16, 42);
+        let mut index = VectorIndex::new(16);
+        let ids: Vec<Uuid> = vectors.iter().map(|_| Uuid::new_v4()).collect();
+        for (id, v) in ids.iter().zip(&vectors) {
+            index.insert(*id, v.clone()).unwrap();
+        }
+
+        let mut hits = 0;
+        let queries = random_vectors(20, 16, 7);
+        for query in &queries {
+            let exact: Vec<Uuid> = EmbeddingCache::find_similar_embeddings(query, &vectors, 10)
+                .into_iter()
+                .map(|(i, _)| ids[i])
+                .collect();
+            let approx = index.search(query, 10, None);
+            assert_eq!(approx.len(), 10);
+            hits += approx.iter().filter(|(id, _)| exact.contains(id)).count();
+        }
+        assert!(hits as f32 / 200.0 > 0.9, "recall {}", hits as f32 / 200.0);
+    }
+
+    #[test]
+    fn test_filter_radius_and_remove() {
+        let mut index = VectorIndex::new(2);
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        index.insert(a, vec![1.0, 0.0]).unwrap();
+        index.insert(b, vec![0.9, 0.1]).unwrap();
+        index.insert(c, vec![0.0, 1.0]).unwrap();
+
+        let only_c = |id: &Uuid| *id == c;
+        assert_eq!(index.search(&[1.0, 0.0], 1, Some(&only_c))[0].0, c);
+
+        let near: Vec<Uuid> = index.search_radius(&[1.0, 0.0], 0.9, None).into_iter().map(|(id, _)| id).collect();
+        assert_eq!(near, vec![a, b]);
+
+        assert!(index.remove(a).unwrap());
+        assert_eq!(index.search(&[1.0, 0.0], 1, None)[0].0, b);
+        assert!(index.insert(a, vec![1.0]).is_err());
+    }
+
+    #[test]
+    fn test_persistence_and_model_change() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let id = Uuid::new_v4();
+        {
+            let mut index = VectorIndex::open(db.open_tree("vectors").unwrap(), 2, "ngram:2").unwrap();
+            index.insert(id, vec![0.6, 0.8]).unwrap();
+        }
+
+        let index = VectorIndex::open(db.open_tree("vectors").unwrap(), 2, "ngram:2").unwrap();
+        assert!(index.contains(id));
+        assert_eq!(index.search(&[0.6, 0.8], 1, None)[0].0, id);
+
+        let index = VectorIndex::open(db.open_tree("vectors").unwrap(), 2, "hash:2").unwrap();
+        assert!(index.is_empty());
+    }
+}
@@ -0,0 +1,168 @@
+// verification.rs
+// Оценка доказательств и итоговая проверка утверждения для "Мыслящего Ядро"
+//
+// Каждый найденный источником документ сравнивается с утверждением: лексическое
+// перекрытие (доля слов утверждения в заголовке и лучшем предложении фрагмента)
+// и позиция (подтверждает, опровергает — то же с противоположной полярностью — или
+// нейтрален). Источники объединяются noisy-OR с весом надёжности источника:
+// support = 1 − Π(1 − wᵢ·sᵢ), contradiction — так же по опровержениям,
+// score = support · (1 − contradiction).
+
+use std::collections::HashSet;
+use serde::{Serialize, Deserialize};
+use crate::core::Polarity;
+use crate::fact_source::Evidence;
+use crate::language::{self, Language};
+use crate::lemmatizer::Lemmatizer;
+use crate::modality::MarkedText;
+use crate::sentence_splitter::SentenceSplitter;
+
+/// Перекрытие, начиная с которого документ считается относящимся к утверждению
+pub const RELEVANT_OVERLAP: f32 = 0.75;
+
+/// Итоговая оценка, начиная с которой утверждение подтверждено (или опровергнуто)
+pub const VERDICT_SCORE: f32 = 0.5;
+
+/// Сколько первых букв леммы сравнивается ("рыбы"/"рыбой" → "рыб")
+const STEM_CHARS: usize = 5;
+
+/// Позиция документа относительно утверждения
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Stance {
+    Supports,
+    Contradicts,
+    #[default]
+    Neutral,
+}
+
+/// Итог проверки
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Verdict {
+    Confirmed,
+    Refuted,
+    #[default]
+    Unverified,
+}
+
+/// Основы значимых слов текста (слова короче трёх букв пропускаются)
+fn stems(text: &str, language: Language) -> HashSet<String> {
+    let lemmatizer = Lemmatizer::shared_for(language);
+    text.split(|c: char| !c.is_alphanumeric() && c != '-')
+        .filter(|w| w.chars().count() > 2)
+        .map(|w| lemmatizer.lemmatize(&w.to_lowercase()).chars().take(STEM_CHARS).collect())
+        .collect()
+}
+
+/// Доля слов утверждения, встречающихся в тексте
+pub fn lexical_overlap(claim: &str, text: &str) -> f32 {
+    let language = language::detect_language(claim);
+    let claim = stems(claim, language);
+    if claim.is_empty() {
+        return 0.0;
+    }
+    let text = stems(text, language);
+    claim.intersection(&text).count() as f32 / claim.len() as f32
+}
+
+fn polarity(text: &str, language: Language) -> Polarity {
+    let marked = MarkedText::new(text, language);
+    marked.assess(0..text.len()).0
+}
+
+/// Перекрытие и позиция документа: по заголовку и лучшему предложению фрагмента
+pub fn assess_document(claim: &str, title: &str, snippet: &str) -> (f32, Stance) {
+    let language = language::detect_language(claim);
+    let sentences = SentenceSplitter::new().split_str(snippet);
+    let (overlap, sentence) = sentences
+        .into_iter()
+        .map(|s| (lexical_overlap(claim, &format!("{} {}", title, s)), s))
+        .fold((lexical_overlap(claim, title), ""), |best, next| if next.0 > best.0 { next } else { best });
+
+    let stance = if overlap < RELEVANT_OVERLAP || sentence.is_empty() {
+        Stance::Neutral
+    } else if polarity(sentence, language) == polarity(claim, language) {
+        Stance::Supports
+    } else {
+        Stance::Contradicts
+    };
+    (overlap, stance)
+}
+
+/// Итог проверки утверждения по всем источникам
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VerificationReport {
+    pub claim: String,
+    pub score: f32,         // 0..1: уверенность в утверждении
+    pub support: f32,
+    pub contradiction: f32,
+    pub confirmations: usize, // Источники хотя бы с одним подтверждающим документом
+    pub verdict: Verdict,
+    #[serde(default)]
+    pub unavailable: Vec<String>, // Источники, которые не ответили (ошибка, таймаут, предохранитель)
+    pub evidence: Vec<Evidence>,
+}
+
+impl VerificationReport {
+    /// Оценить документы источников и объединить их.
+    /// Подтверждено — score ≥ 0.5 и не меньше `min_confirmations` подтвердивших источников
+    pub fn aggregate(claim: &str, mut evidence: Vec<Evidence>, min_confirmations: usize) -> Self {
+        let (mut against_support, mut against_contradiction) = (1.0f32, 1.0f32);
+        let mut confirmations = 0;
+
+        for e in evidence.iter_mut() {
+            for document in e.documents.iter_mut() {
+                let (score, stance) = assess_document(claim, &document.title, &document.snippet);
+                document.score = score;
+                document.stance = stance;
+            }
+            let best = |stance: Stance| e.documents.iter().filter(|d| d.stance == stance).map(|d| d.score).fold(0.0f32, f32::max);
+            let (supports, contradicts) = (best(Stance::Supports), best(Stance::Contradicts));
+            let weight = e.weight.clamp(0.0, 1.0);
+            against_support *= 1.0 - weight * supports;
+            against_contradiction *= 1.0 - weight * contradicts;
+            if supports > 0.0 {
+                confirmations += 1;
+            }
+        }
+
+        let support = 1.0 - against_support;
+        let contradiction = 1.0 - against_contradiction;
+        let score = support * (1.0 - contradiction);
+        let verdict = if score >= VERDICT_SCORE && confirmations >= min_confirmations {
+            Verdict::Confirmed
+        } else if contradiction >= VERDICT_SCORE && contradiction > support {
+            Verdict::Refuted
+        } else {
+            Verdict::Unverified
+        };
+
+        let unavailable = evidence.iter().filter(|e| e.error.is_some()).map(|e| e.source_name.clone()).collect();
+        Self { claim: claim.to_string(), score, support, contradiction, confirmations, verdict, unavailable, evidence }
+    }
+
+    pub fn verified(&self) -> bool {
+        self.verdict == Verdict::Confirmed
+    }
+
+    /// Источники с подтверждающими документами (в порядке опроса)
+    pub fn confirming_sources(&self) -> Vec<crate::core::Source> {
+        self.evidence
+            .iter()
+            .filter(|e| e.documents.iter().any(|d| d.stance == Stance::Supports))
+            .map(|e| e.source)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stance_and_overlap() {
+        assert_eq!(lexical_overlap("Кошка ест рыбу", "Домашняя кошка часто ест рыбу и мясо"), 1.0);
+        assert_eq!(assess_document("Кошка ест рыбу", "Кошка", "Кошка — хищник. Кошка ест рыбу и мясо.").1, Stance::Supports);
+        assert_eq!(assess_document("Кошка ест рыбу", "Кошка", "Кошка не ест рыбу.").1, Stance::Contradicts);
+        assert_eq!(assess_document("Кошка ест рыбу", "Москва", "Столица России.").1, Stance::Neutral);
+    }
+}
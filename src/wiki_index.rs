@@ -0,0 +1,756 @@
+// wiki_index.rs
+// Локальный полнотекстовый индекс статей (дамп Wikipedia) для "Мыслящего Ядро"
+//
+// Серверы проверки работают без интернета: дамп MediaWiki XML (или каталог текстовых
+// статей) один раз загружается в индекс на диске (sled), а LocalWikiSource отвечает
+// на утверждения по нему: BM25 по леммам слов, фрагмент статьи с наибольшим числом
+// слов утверждения — в качестве доказательства. Служебные слова не индексируются,
+// длины статей при открытии индекса читаются в память.
+//
+// Деревья sled: articles (id → заголовок и текст), titles (заголовок → id),
+// lengths (id → число слов; у заменённых статей удаляется),
+// postings (лемма, байт 0 и номер порции → пары id/частота, по 8 байт: каждое слияние
+// дописывает новую порцию, не переписывая прежние), meta (число статей, следующий id,
+// сумма длин, число лемм и порций).
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::Path;
+use std::sync::Arc;
+use lazy_static::lazy_static;
+use quick_xml::events::Event;
+use quick_xml::reader::Reader;
+use regex::Regex;
+use serde::{Serialize, Deserialize};
+use sled::transaction::{ConflictableTransactionResult, TransactionError, Transactional};
+use sled::{Batch, Db, Tree};
+use crate::core::Source;
+use crate::feeds;
+use crate::fact_source::{BoxFuture, Evidence, EvidenceDocument, FactSource};
+use crate::language;
+use crate::lemmatizer::Lemmatizer;
+use crate::sentence_splitter::SentenceSplitter;
+
+/// Параметры BM25
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+/// Сколько статей держать в памяти перед слиянием списков в sled
+const FLUSH_EVERY: usize = 5_000;
+
+/// Длина фрагмента-доказательства (символов)
+const SNIPPET_CHARS: usize = 300;
+
+lazy_static! {
+    static ref WORD: Regex = Regex::new(r"\p{L}[\p{L}\p{N}]*(?:-\p{L}+)*|\p{N}+").unwrap();
+    static ref TEMPLATE: Regex = Regex::new(r"\{\{[^{}]*\}\}").unwrap();
+    static ref TABLE: Regex = Regex::new(r"(?s)\{\|.*?\|\}").unwrap();
+    static ref REF: Regex = Regex::new(r"(?s)<ref[^>/]*/>|<ref[^>]*>.*?</ref>|<!--.*?-->").unwrap();
+    static ref TAG: Regex = Regex::new(r"<[^>]+>").unwrap();
+    static ref FILE_LINK: Regex = Regex::new(r"\[\[(?:File|Image|Файл|Изображение|Категория|Category):[^\[\]]*(?:\[\[[^\]]*\]\][^\[\]]*)*\]\]").unwrap();
+    static ref LINK: Regex = Regex::new(r"\[\[(?:[^\]|]*\|)?([^\]]*)\]\]").unwrap();
+    static ref EXTERNAL_LINK: Regex = Regex::new(r"\[https?://\S+\s*([^\]]*)\]").unwrap();
+    static ref HEADING: Regex = Regex::new(r"(?m)^=+\s*(.*?)\s*=+\s*$").unwrap();
+    static ref EMPHASIS: Regex = Regex::new(r"'{2,}").unwrap();
+    static ref BLANK_LINES: Regex = Regex::new(r"\n{3,}").unwrap();
+}
+
+/// Ошибка построения или чтения индекса
+#[derive(Debug)]
+pub enum WikiIndexError {
+    Storage(sled::Error),
+    Io(io::Error),
+    Xml(String),
+    Corrupt(String),
+}
+
+impl fmt::Display for WikiIndexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WikiIndexError::Storage(e) => write!(f, "Ошибка хранилища sled: {}", e),
+            WikiIndexError::Io(e) => write!(f, "Ошибка чтения дампа: {}", e),
+            WikiIndexError::Xml(e) => write!(f, "Ошибка разбора XML: {}", e),
+            WikiIndexError::Corrupt(e) => write!(f, "Индекс повреждён: {}", e),
+        }
+    }
+}
+
+impl Error for WikiIndexError {}
+
+impl From<sled::Error> for WikiIndexError {
+    fn from(e: sled::Error) -> Self {
+        WikiIndexError::Storage(e)
+    }
+}
+
+impl From<io::Error> for WikiIndexError {
+    fn from(e: io::Error) -> Self {
+        WikiIndexError::Io(e)
+    }
+}
+
+impl From<quick_xml::Error> for WikiIndexError {
+    fn from(e: quick_xml::Error) -> Self {
+        WikiIndexError::Xml(e.to_string())
+    }
+}
+
+/// Статья индекса
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Article {
+    pub title: String,
+    pub text: String,
+}
+
+/// Вики-разметка → простой текст: шаблоны, таблицы, сноски, файлы и теги удаляются,
+/// ссылки заменяются подписью, заголовки — текстом
+pub fn strip_wikitext(markup: &str) -> String {
+    let mut text = REF.replace_all(markup, "").into_owned();
+    // Вложенные шаблоны снимаются изнутри наружу
+    loop {
+        let stripped = TEMPLATE.replace_all(&text, "");
+        if stripped.len() == text.len() {
+            break;
+        }
+        text = stripped.into_owned();
+    }
+    let text = TABLE.replace_all(&text, "");
+    let text = FILE_LINK.replace_all(&text, "");
+    let text = LINK.replace_all(&text, "$1");
+    let text = EXTERNAL_LINK.replace_all(&text, "$1");
+    let text = TAG.replace_all(&text, "");
+    let text = HEADING.replace_all(&text, "$1.");
+    let text = EMPHASIS.replace_all(&text, "");
+    BLANK_LINES.replace_all(text.trim(), "\n\n").into_owned()
+}
+
+/// Статьи основного пространства дампа MediaWiki XML (перенаправления пропускаются)
+pub fn read_mediawiki<R: BufRead>(input: R, mut on_article: impl FnMut(Article) -> Result<(), WikiIndexError>) -> Result<usize, WikiIndexError> {
+    #[derive(PartialEq)]
+    enum Field { None, Title, Namespace, Text }
+
+    let mut reader = Reader::from_reader(input);
+    let mut buf = Vec::new();
+    let mut field = Field::None;
+    let (mut title, mut namespace, mut text, mut redirect) = (String::new(), String::new(), String::new(), false);
+    let mut count = 0;
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) => match e.name().as_ref() {
+                b"page" => {
+                    title.clear();
+                    namespace.clear();
+                    text.clear();
+                    redirect = false;
+                },
+                b"title" => field = Field::Title,
+                b"ns" => field = Field::Namespace,
+                b"text" => field = Field::Text,
+                b"redirect" => redirect = true,
+                _ => {},
+            },
+            Event::Empty(e) if e.name().as_ref() == b"redirect" => redirect = true,
+            Event::Text(e) if field != Field::None => {
+                let value = e.unescape().map_err(|e| WikiIndexError::Xml(e.to_string()))?;
+                match field {
+                    Field::Title => title.push_str(&value),
+                    Field::Namespace => namespace.push_str(&value),
+                    Field::Text => text.push_str(&value),
+                    Field::None => {},
+                }
+            },
+            Event::CData(e) if field == Field::Text => text.push_str(&String::from_utf8_lossy(&e)),
+            Event::End(e) => match e.name().as_ref() {
+                b"page" => {
+                    let main = namespace.trim().is_empty() || namespace.trim() == "0";
+                    if main && !redirect && !text.trim_start().to_lowercase().starts_with("#redirect") {
+                        on_article(Article { title: title.clone(), text: strip_wikitext(&text) })?;
+                        count += 1;
+                    }
+                },
+                b"title" | b"ns" | b"text" => field = Field::None,
+                _ => {},
+            },
+            Event::Eof => break,
+            _ => {},
+        }
+        buf.clear();
+    }
+    Ok(count)
+}
+
+/// Леммы значимых слов текста (язык определяется по началу текста)
+fn terms(text: &str) -> Vec<String> {
+    let sample: String = text.chars().take(1_000).collect();
+    let lemmatizer = Lemmatizer::shared_for(language::detect_language(&sample));
+    WORD.find_iter(text)
+        .map(|m| m.as_str().to_lowercase())
+        .filter(|w| w.chars().count() > 1 && !feeds::is_stopword(w))
+        .map(|w| lemmatizer.lemmatize(&w))
+        .collect()
+}
+
+fn encode_postings(postings: &[(u32, u32)]) -> Vec<u8> {
+    postings.iter().flat_map(|(doc, tf)| doc.to_be_bytes().into_iter().chain(tf.to_be_bytes())).collect()
+}
+
+fn decode_postings(bytes: &[u8]) -> Vec<(u32, u32)> {
+    bytes
+        .chunks_exact(8)
+        .map(|c| (u32::from_be_bytes([c[0], c[1], c[2], c[3]]), u32::from_be_bytes([c[4], c[5], c[6], c[7]])))
+        .collect()
+}
+
+/// Ключи порций леммы: лемма и байт 0 (в словах его нет), затем номер порции
+fn postings_prefix(term: &str) -> Vec<u8> {
+    let mut prefix = term.as_bytes().to_vec();
+    prefix.push(0);
+    prefix
+}
+
+fn read_u64(tree: &Tree, key: &str) -> Result<u64, WikiIndexError> {
+    match tree.get(key)? {
+        Some(bytes) => Ok(u64::from_be_bytes(bytes.as_ref().try_into().map_err(|_| WikiIndexError::Corrupt(key.to_string()))?)),
+        None => Ok(0),
+    }
+}
+
+/// Итог построения индекса
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct IndexStats {
+    pub articles: u64,
+    pub terms: u64,
+}
+
+/// Построение индекса: статьи добавляются потоком и копятся в памяти, а затем порцией
+/// (статьи, заголовки, длины, списки вхождений и счётчики) записываются одной транзакцией —
+/// прерванное построение не оставляет статей с id, которые выдадут повторно
+pub struct IndexBuilder {
+    db: Db,
+    articles: Tree,
+    titles: Tree,
+    lengths: Tree,
+    postings: Tree,
+    meta: Tree,
+    pending: HashMap<String, Vec<(u32, u32)>>,
+    pending_articles: HashMap<u32, (Article, u32)>, // id → статья и число слов
+    pending_titles: HashMap<String, u32>,
+    removed: Vec<u32>, // Заменённые статьи, уже записанные в sled
+    article_count: u64,
+    next_id: u32,
+    total_length: u64,
+    term_count: u64,
+    chunk: u32, // Номер следующей порции списков вхождений
+}
+
+impl IndexBuilder {
+    /// Открыть (или создать) индекс в каталоге; новые статьи дописываются к существующим,
+    /// статья с уже известным заголовком заменяет прежнюю (неизменённая — пропускается)
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, WikiIndexError> {
+        let db = sled::open(path)?;
+        let meta = db.open_tree("meta")?;
+        let article_count = read_u64(&meta, "articles")?;
+        let next_id = match read_u64(&meta, "next_id")? {
+            0 => article_count,
+            next_id => next_id,
+        };
+        Ok(Self {
+            articles: db.open_tree("articles")?,
+            titles: db.open_tree("titles")?,
+            lengths: db.open_tree("lengths")?,
+            postings: db.open_tree("postings")?,
+            article_count,
+            next_id: next_id as u32,
+            total_length: read_u64(&meta, "total_length")?,
+            term_count: read_u64(&meta, "terms")?,
+            chunk: read_u64(&meta, "chunks")? as u32,
+            meta,
+            db,
+            pending: HashMap::new(),
+            pending_articles: HashMap::new(),
+            pending_titles: HashMap::new(),
+            removed: Vec::new(),
+        })
+    }
+
+    /// Добавить статью
+    pub fn add(&mut self, article: Article) -> Result<(), WikiIndexError> {
+        let terms = terms(&format!("{}\n{}", article.title, article.text));
+        if terms.is_empty() {
+            return Ok(());
+        }
+        let previous = match self.pending_titles.get(&article.title) {
+            Some(&id) => Some(id),
+            None => match self.titles.get(&article.title)? {
+                Some(id) => Some(u32::from_be_bytes(id.as_ref().try_into().map_err(|_| WikiIndexError::Corrupt(article.title.clone()))?)),
+                None => None,
+            },
+        };
+        if let Some(previous) = previous
+            && !self.replace(previous, &article)?
+        {
+            return Ok(());
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        for term in &terms {
+            *counts.entry(term.clone()).or_default() += 1;
+        }
+        for (term, tf) in counts {
+            self.pending.entry(term).or_default().push((id, tf));
+        }
+        self.total_length += terms.len() as u64;
+        self.article_count += 1;
+        self.pending_titles.insert(article.title.clone(), id);
+        self.pending_articles.insert(id, (article, terms.len() as u32));
+
+        if self.pending_articles.len() >= FLUSH_EVERY {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Убрать прежнюю версию статьи, если текст изменился; false — статья та же.
+    /// Вхождения старого id остаются в списках, но без длины поиск их пропускает
+    fn replace(&mut self, previous: u32, article: &Article) -> Result<bool, WikiIndexError> {
+        if let Some((stored, length)) = self.pending_articles.get(&previous) {
+            if stored.text == article.text {
+                return Ok(false);
+            }
+            self.total_length -= *length as u64;
+            self.article_count -= 1;
+            self.pending_articles.remove(&previous);
+            return Ok(true);
+        }
+        if let Some(bytes) = self.articles.get(previous.to_be_bytes())? {
+            let stored: Article = serde_json::from_slice(&bytes).map_err(|e| WikiIndexError::Corrupt(e.to_string()))?;
+            if stored.text == article.text {
+                return Ok(false);
+            }
+        }
+        if let Some(length) = self.lengths.get(previous.to_be_bytes())? {
+            let length = u32::from_be_bytes(length.as_ref().try_into().map_err(|_| WikiIndexError::Corrupt(format!("длина {}", previous)))?);
+            self.total_length -= length as u64;
+            self.article_count -= 1;
+        }
+        self.removed.push(previous);
+        Ok(true)
+    }
+
+    /// Записать накопленную порцию одной транзакцией: статьи, заголовки, длины,
+    /// по новой порции вхождений на лемму и счётчики
+    fn flush(&mut self) -> Result<(), WikiIndexError> {
+        if self.pending.is_empty() && self.removed.is_empty() {
+            return Ok(());
+        }
+        let (mut articles, mut titles, mut lengths, mut postings, mut meta) =
+            (Batch::default(), Batch::default(), Batch::default(), Batch::default(), Batch::default());
+        for id in self.removed.drain(..) {
+            articles.remove(&id.to_be_bytes());
+            lengths.remove(&id.to_be_bytes());
+        }
+        for (id, (article, length)) in self.pending_articles.drain() {
+            articles.insert(&id.to_be_bytes(), serde_json::to_vec(&article).map_err(|e| WikiIndexError::Corrupt(e.to_string()))?);
+            titles.insert(article.title.as_bytes(), &id.to_be_bytes());
+            lengths.insert(&id.to_be_bytes(), &length.to_be_bytes());
+        }
+        self.pending_titles.clear();
+        let mut term_count = self.term_count;
+        for (term, entries) in self.pending.drain() {
+            let mut key = postings_prefix(&term);
+            if self.postings.scan_prefix(&key).next().is_none() {
+                term_count += 1;
+            }
+            key.extend(self.chunk.to_be_bytes());
+            postings.insert(key, encode_postings(&entries));
+        }
+        meta.insert("articles", &self.article_count.to_be_bytes());
+        meta.insert("next_id", &(self.next_id as u64).to_be_bytes());
+        meta.insert("total_length", &self.total_length.to_be_bytes());
+        meta.insert("terms", &term_count.to_be_bytes());
+        meta.insert("chunks", &(self.chunk as u64 + 1).to_be_bytes());
+
+        (&self.articles, &self.titles, &self.lengths, &self.postings, &self.meta)
+            .transaction(|(a, t, l, p, m)| -> ConflictableTransactionResult<(), ()> {
+                a.apply_batch(&articles)?;
+                t.apply_batch(&titles)?;
+                l.apply_batch(&lengths)?;
+                p.apply_batch(&postings)?;
+                m.apply_batch(&meta)?;
+                Ok(())
+            })
+            .map_err(|e| match e {
+                TransactionError::Storage(e) => WikiIndexError::Storage(e),
+                TransactionError::Abort(()) => WikiIndexError::Corrupt("транзакция прервана".to_string()),
+            })?;
+        self.term_count = term_count;
+        self.chunk += 1;
+        Ok(())
+    }
+
+    /// Дамп MediaWiki XML
+    pub fn add_mediawiki<R: BufRead>(&mut self, input: R) -> Result<usize, WikiIndexError> {
+        read_mediawiki(input, |article| self.add(article))
+    }
+
+    /// Каталог статей: файлы .txt и .md, заголовок — имя файла
+    pub fn add_directory<P: AsRef<Path>>(&mut self, dir: P) -> Result<usize, WikiIndexError> {
+        let mut paths: Vec<_> = fs::read_dir(dir)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|e| e == "txt" || e == "md"))
+            .collect();
+        paths.sort();
+        for path in &paths {
+            let title = path.file_stem().map(|s| s.to_string_lossy().replace('_', " ")).unwrap_or_default();
+            self.add(Article { title, text: fs::read_to_string(path)? })?;
+        }
+        Ok(paths.len())
+    }
+
+    /// Завершить построение
+    pub fn finish(mut self) -> Result<IndexStats, WikiIndexError> {
+        self.flush()?;
+        self.db.flush()?;
+        Ok(IndexStats { articles: self.article_count, terms: self.term_count })
+    }
+}
+
+/// Найденная статья
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub title: String,
+    pub score: f64,
+    pub matched_terms: usize, // Сколько различных слов запроса есть в статье
+    pub query_terms: usize,
+    pub snippet: String,
+}
+
+impl SearchHit {
+    /// Доля слов запроса, найденных в статье
+    pub fn coverage(&self) -> f64 {
+        if self.query_terms == 0 { 0.0 } else { self.matched_terms as f64 / self.query_terms as f64 }
+    }
+}
+
+/// Индекс только для чтения
+pub struct WikiIndex {
+    articles: Tree,
+    lengths: Vec<u32>, // Число слов статьи по id (0 — статьи нет)
+    postings: Tree,
+    article_count: u64,
+    average_length: f64,
+    splitter: SentenceSplitter,
+}
+
+impl WikiIndex {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, WikiIndexError> {
+        Self::from_db(sled::open(path)?)
+    }
+
+    fn from_db(db: Db) -> Result<Self, WikiIndexError> {
+        let meta = db.open_tree("meta")?;
+        let article_count = read_u64(&meta, "articles")?;
+        let total_length = read_u64(&meta, "total_length")?;
+        let mut lengths = Vec::new();
+        for entry in db.open_tree("lengths")?.iter() {
+            let (key, value) = entry?;
+            let corrupt = || WikiIndexError::Corrupt(format!("длина {:?}", key));
+            let id = u32::from_be_bytes(key.as_ref().try_into().map_err(|_| corrupt())?) as usize;
+            if lengths.len() <= id {
+                lengths.resize(id + 1, 0);
+            }
+            lengths[id] = u32::from_be_bytes(value.as_ref().try_into().map_err(|_| corrupt())?);
+        }
+        Ok(Self {
+            articles: db.open_tree("articles")?,
+            lengths,
+            postings: db.open_tree("postings")?,
+            average_length: if article_count == 0 { 0.0 } else { total_length as f64 / article_count as f64 },
+            article_count,
+            splitter: SentenceSplitter::new(),
+        })
+    }
+
+    pub fn len(&self) -> u64 {
+        self.article_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.article_count == 0
+    }
+
+    pub fn article(&self, id: u32) -> Result<Option<Article>, WikiIndexError> {
+        match self.articles.get(id.to_be_bytes())? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes).map_err(|e| WikiIndexError::Corrupt(e.to_string()))?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Лучшие статьи по BM25 с фрагментом, содержащим больше всего слов запроса
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>, WikiIndexError> {
+        let query_terms: HashSet<String> = terms(query).into_iter().collect();
+        let mut scores: HashMap<u32, (f64, usize)> = HashMap::new();
+        let n = self.article_count as f64;
+
+        for term in &query_terms {
+            let mut postings = Vec::new();
+            for chunk in self.postings.scan_prefix(postings_prefix(term)) {
+                // Вхождения заменённых статей (без длины) не учитываются
+                let live = decode_postings(&chunk?.1).into_iter().filter(|(doc, _)| self.lengths.get(*doc as usize).is_some_and(|&l| l > 0));
+                postings.extend(live);
+            }
+            if postings.is_empty() {
+                continue;
+            }
+            let df = postings.len() as f64;
+            let idf = (1.0 + (n - df + 0.5) / (df + 0.5)).ln();
+            for (doc, tf) in postings {
+                let length = self.lengths[doc as usize] as f64;
+                let tf = tf as f64;
+                let norm = tf * (K1 + 1.0) / (tf + K1 * (1.0 - B + B * length / self.average_length.max(1.0)));
+                let entry = scores.entry(doc).or_default();
+                entry.0 += idf * norm;
+                entry.1 += 1;
+            }
+        }
+
+        let mut ranked: Vec<(u32, f64, usize)> = scores.into_iter().map(|(doc, (score, matched))| (doc, score, matched)).collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked.truncate(limit);
+
+        let mut hits = Vec::with_capacity(ranked.len());
+        for (doc, score, matched_terms) in ranked {
+            let Some(article) = self.article(doc)? else { continue };
+            hits.push(SearchHit {
+                snippet: self.snippet(&article.text, &query_terms),
+                title: article.title,
+                score,
+                matched_terms,
+                query_terms: query_terms.len(),
+            });
+        }
+        Ok(hits)
+    }
+
+    /// Предложение статьи с наибольшим числом слов запроса
+    fn snippet(&self, text: &str, query_terms: &HashSet<String>) -> String {
+        let best = self
+            .splitter
+            .split_str(text)
+            .into_iter()
+            .enumerate()
+            .max_by_key(|(i, sentence)| {
+                let found: HashSet<String> = terms(sentence).into_iter().filter(|t| query_terms.contains(t)).collect();
+                (found.len(), std::cmp::Reverse(*i))
+            })
+            .map(|(_, sentence)| sentence.trim())
+            .unwrap_or("");
+        match best.char_indices().nth(SNIPPET_CHARS) {
+            Some((cut, _)) => format!("{}…", &best[..cut]),
+            None => best.to_string(),
+        }
+    }
+}
+
+/// Источник проверки фактов по локальному индексу Wikipedia
+pub struct LocalWikiSource {
+    index: Arc<WikiIndex>,
+    min_coverage: f64, // Статья подтверждает, если содержит эту долю слов утверждения
+    limit: usize,
+}
+
+impl LocalWikiSource {
+    pub fn new(index: Arc<WikiIndex>) -> Self {
+        Self { index, min_coverage: 0.6, limit: 5 }
+    }
+
+    pub fn with_min_coverage(mut self, min_coverage: f64) -> Self {
+        self.min_coverage = min_coverage;
+        self
+    }
+}
+
+impl FactSource for LocalWikiSource {
+    fn name(&self) -> &str {
+        "local-wikipedia"
+    }
+
+    fn source(&self) -> Source {
+        Source::Wikipedia
+    }
+
+    fn search<'a>(&'a self, claim: &'a str) -> BoxFuture<'a, Evidence> {
+        Box::pin(async move {
+            // Чтение sled и BM25 блокируют поток — выполняются вне исполнителя tokio
+            let (index, query, limit) = (self.index.clone(), claim.to_string(), self.limit);
+            let found = match tokio::task::spawn_blocking(move || index.search(&query, limit)).await {
+                Ok(found) => found,
+                Err(e) => return Evidence::failed(self, e),
+            };
+            match found {
+                Ok(hits) => {
+                    let documents = hits
+                        .into_iter()
+                        .filter(|h| h.coverage() >= self.min_coverage)
+                        .map(|h| EvidenceDocument::new(h.title, None, h.snippet))
+                        .collect();
+                    Evidence::documents(self, documents)
+                },
+                Err(e) => Evidence::failed(self, e),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fact_source::SourceRegistry;
+    use crate::trusted_scraper::TrustedScraper;
+
+    const DUMP: &str = r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.10/">
+  <page>
+    <title>Кошка</title>
+    <ns>0</ns>
+    <revision><text xml:space="preserve">{{Таксон|имя=Кошка}}'''Кошка''' — домашнее [[животное]]. Кошка ест [[Рыба|рыбу]] и мясо.&lt;ref&gt;Брем, 1876&lt;/ref&gt;
+== Поведение ==
+Кошки спят до 16 часов в сутки. [[Файл:Cat.jpg|мини|Кошка]]</text></revision>
+  </page>
+  <page>
+    <title>Кот</title>
+    <ns>0</ns>
+    <redirect title="Кошка" />
+    <revision><text>#REDIRECT [[Кошка]]</text></revision>
+  </page>
+  <page>
+    <title>Обсуждение:Кошка</title>
+    <ns>1</ns>
+    <revision><text>Кошка ест рыбу?</text></revision>
+  </page>
+  <page>
+    <title>Москва</title>
+    <ns>0</ns>
+    <revision><text>'''Москва''' — столица России &amp; крупнейший город.</text></revision>
+  </page>
+</mediawiki>"#;
+
+    #[test]
+    fn test_strip_wikitext() {
+        let text = strip_wikitext("{{a|{{b}}}}'''Кот''' ест [[Рыба|рыбу]].<ref name=\"x\">сноска</ref>\n== Ареал ==\n[[Москва]]");
+        assert_eq!(text, "Кот ест рыбу.\nАреал.\nМосква");
+    }
+
+    #[test]
+    fn test_flush_appends_chunks() {
+        let path = std::env::temp_dir().join(format!("wiki-index-{}", uuid::Uuid::new_v4()));
+        let mut builder = IndexBuilder::create(&path).unwrap();
+        builder.add(Article { title: "Кошка".to_string(), text: "Кошка ест рыбу.".to_string() }).unwrap();
+        builder.flush().unwrap();
+        builder.add(Article { title: "Тюлень".to_string(), text: "Тюлень ест рыбу.".to_string() }).unwrap();
+        builder.flush().unwrap();
+        // Каждое слияние — отдельная порция, прежние не переписываются
+        assert_eq!(builder.postings.scan_prefix(postings_prefix("рыба")).count(), 2);
+        assert_eq!(builder.term_count, 4);
+
+        let index = WikiIndex::from_db(builder.db.clone()).unwrap();
+        let titles: Vec<String> = index.search("рыба", 5).unwrap().into_iter().map(|h| h.title).collect();
+        assert_eq!(titles.len(), 2);
+        assert!(titles.contains(&"Кошка".to_string()) && titles.contains(&"Тюлень".to_string()));
+        drop((index, builder));
+        std::fs::remove_dir_all(&path).ok();
+    }
+
+    #[test]
+    fn test_reingest_replaces_by_title() {
+        let path = std::env::temp_dir().join(format!("wiki-index-{}", uuid::Uuid::new_v4()));
+        let mut builder = IndexBuilder::create(&path).unwrap();
+        builder.add_mediawiki(DUMP.as_bytes()).unwrap();
+        builder.add_mediawiki(DUMP.as_bytes()).unwrap();
+        builder.add(Article { title: "Москва".to_string(), text: "Москва — столица России.".to_string() }).unwrap();
+        builder.flush().unwrap();
+        assert_eq!(builder.article_count, 2);
+
+        let index = WikiIndex::from_db(builder.db.clone()).unwrap();
+        assert_eq!(index.len(), 2);
+        let hits = index.search("кошка", 5).unwrap();
+        assert_eq!(hits.len(), 1);
+        // Заменённая версия статьи в поиск не попадает
+        let hits = index.search("столица", 5).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].snippet, "Москва — столица России.");
+        assert!(index.search("крупнейший город", 5).unwrap().is_empty());
+        drop((index, builder));
+        std::fs::remove_dir_all(&path).ok();
+    }
+
+    /// Открыть индекс заново (sled отпускает каталог не сразу после сброса)
+    fn reopen<T>(open: impl Fn() -> Result<T, WikiIndexError>) -> T {
+        for _ in 0..50 {
+            if let Ok(opened) = open() {
+                return opened;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        open().unwrap()
+    }
+
+    #[test]
+    fn test_interrupted_build_keeps_ids_consistent() {
+        let path = std::env::temp_dir().join(format!("wiki-index-{}", uuid::Uuid::new_v4()));
+        // Дамп обрывается ошибкой XML после первой статьи: построение не доходит до finish
+        let broken = "<mediawiki><page><title>Кошка</title><ns>0</ns><revision><text>Кошка ест рыбу.</text></revision></page><page><title>Сломано</oops></mediawiki>";
+        let mut builder = IndexBuilder::create(&path).unwrap();
+        assert!(builder.add_mediawiki(broken.as_bytes()).is_err());
+        drop(builder);
+
+        let mut builder = reopen(|| IndexBuilder::create(&path));
+        builder.add(Article { title: "Тюлень".to_string(), text: "Тюлень ест рыбу.".to_string() }).unwrap();
+        builder.add(Article { title: "Кошка".to_string(), text: "Кошка спит на диване.".to_string() }).unwrap();
+        builder.flush().unwrap();
+        assert_eq!(builder.article_count, 2);
+
+        let index = WikiIndex::from_db(builder.db.clone()).unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.search("тюлень", 5).unwrap()[0].title, "Тюлень");
+        assert_eq!(index.search("кошка", 5).unwrap()[0].snippet, "Кошка спит на диване.");
+        drop((index, builder));
+        std::fs::remove_dir_all(&path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_build_and_search_offline() {
+        let path = std::env::temp_dir().join(format!("wiki-index-{}", uuid::Uuid::new_v4()));
+        let mut builder = IndexBuilder::create(&path).unwrap();
+        assert_eq!(builder.add_mediawiki(DUMP.as_bytes()).unwrap(), 2);
+        let stats = builder.finish().unwrap();
+        assert_eq!(stats.articles, 2);
+
+        let index = Arc::new(reopen(|| WikiIndex::open(&path)));
+        let hits = index.search("Кошка ест рыбу", 3).unwrap();
+        assert_eq!(hits[0].title, "Кошка");
+        assert_eq!(hits[0].snippet, "Кошка ест рыбу и мясо.");
+        assert_eq!(hits[0].coverage(), 1.0);
+        assert_eq!(index.search("столица России", 3).unwrap()[0].snippet, "Москва — столица России & крупнейший город.");
+        // Служебные слова не влияют на поиск и долю найденных слов
+        let hits = index.search("кошка и рыба", 3).unwrap();
+        assert_eq!((hits.len(), hits[0].query_terms), (1, 2));
+        assert!(index.search("и в на", 3).unwrap().is_empty());
+
+        let mut sources = SourceRegistry::new();
+        sources.register(Arc::new(LocalWikiSource::new(index)));
+        let scraper = TrustedScraper::with_sources(sources).with_min_confirmations(1);
+        assert!(scraper.check("Кошка ест рыбу").await);
+        assert!(!scraper.check("Собака летает над океаном").await);
+        let evidence = scraper.evidence("Кошка ест рыбу").await;
+        assert_eq!(evidence[0].documents[0].title, "Кошка");
+
+        drop(scraper);
+        std::fs::remove_dir_all(&path).ok();
+    }
+}
@@ -0,0 +1,31 @@
+// tests/evaluation.rs
+// Регрессия качества извлечения по эталонному корпусу tests/data/gold.jsonl
+//
+// Пороги — текущее качество с небольшим запасом: изменение шаблонов, которое их
+// нарушает, ухудшает извлечение. После улучшения пороги стоит поднять.
+
+use metastasa::evaluation::{self, NONE};
+use metastasa::text_processor::TextProcessor;
+
+#[tokio::test]
+async fn test_gold_corpus_quality() {
+    let corpus = evaluation::parse_corpus(include_str!("data/gold.jsonl")).unwrap();
+    assert!(corpus.len() >= 15);
+
+    let report = evaluation::evaluate(&TextProcessor::new(), &corpus).await;
+    let details = format!("{}\nНе найдено: {:#?}\nЛишнее: {:#?}", report, report.missed, report.spurious);
+
+    assert!(report.entities_total().f1() >= 0.9, "{}", details);
+    assert!(report.entities["Animal"].f1() >= 0.95, "{}", details);
+    assert!(report.actions.f1() >= 0.7, "{}", details);
+    assert!(report.properties.f1() >= 0.45, "{}", details);
+    assert!(report.total().f1() >= 0.78, "{}", details);
+
+    // Найденные сущности не получают чужой тип
+    let confused: Vec<_> = report
+        .confusion
+        .iter()
+        .flat_map(|(gold, row)| row.keys().filter(move |found| *found != gold && gold != NONE && *found != NONE).map(move |found| (gold, found)))
+        .collect();
+    assert!(confused.is_empty(), "{:?}\n{}", confused, details);
+}
@@ -15,7 +15,8 @@ async fn test_trusted_scraper_and_active_learning() {
         justification: "Вижу в энциклопедии".to_string(),
     };
     let res = al.check_correction(&corr).await;
-    assert!(matches!(res, CorrectionResult::NeedsModeration | CorrectionResult::AutoAccepted));
+    assert!(matches!(res.result, CorrectionResult::NeedsModeration | CorrectionResult::AutoAccepted));
+    assert_eq!(res.report.claim, "Кошка");
 }
 
 #[test]
@@ -0,0 +1,60 @@
+// tests/sentence_splitting.rs
+// Тесты разбиения на предложения по корпусу tests/data/sentences.txt
+
+use metastasa::sentence_splitter::SentenceSplitter;
+use metastasa::text_processor::TextProcessor;
+
+/// Случаи корпуса: текст и ожидаемые предложения
+fn load_corpus() -> Vec<(String, Vec<String>)> {
+    let content = include_str!("data/sentences.txt");
+    let mut cases = Vec::new();
+    let mut current: Option<(String, Vec<String>)> = None;
+
+    for line in content.lines() {
+        if let Some(text) = line.strip_prefix("< ") {
+            cases.extend(current.take());
+            current = Some((text.replace("\\n", "\n"), Vec::new()));
+        } else if let Some(sentence) = line.strip_prefix("> ") {
+            current.as_mut().expect("предложение без текста").1.push(sentence.to_string());
+        }
+    }
+    cases.extend(current);
+    cases
+}
+
+#[test]
+fn test_sentence_corpus() {
+    let splitter = SentenceSplitter::new();
+    let cases = load_corpus();
+    assert!(cases.len() >= 15);
+
+    let failures: Vec<String> = cases
+        .iter()
+        .filter_map(|(text, expected)| {
+            let actual = splitter.split_str(text);
+            (actual != *expected).then(|| format!("{:?}\n  ожидалось: {:?}\n  получено:  {:?}", text, expected, actual))
+        })
+        .collect();
+    assert!(failures.is_empty(), "Ошибки разбиения:\n{}", failures.join("\n"));
+}
+
+#[test]
+fn test_custom_abbreviations() {
+    let text = "Доклад сделал чл.-корр. Иванов. Все согласились.";
+    assert_eq!(SentenceSplitter::new().split_str(text).len(), 3);
+    let splitter = SentenceSplitter::new().with_abbreviations(["чл.-корр"]);
+    assert_eq!(splitter.split_str(text), vec!["Доклад сделал чл.-корр. Иванов.", "Все согласились."]);
+}
+
+#[test]
+fn test_dates_survive_preprocessing() {
+    let processor = TextProcessor::new();
+    let sentences = processor.preprocess_text("Статья вышла 12.05.2024. Её процитировали.");
+    assert_eq!(sentences.len(), 2);
+    assert!(sentences[0].contains("12.05.2024"));
+
+    // Дата нормализуется в ISO 8601, фрагмент остаётся как в тексте
+    let entities = processor.extract_entities(&sentences[0]);
+    let date = entities.iter().find(|e| e.text == "2024-05-12").unwrap();
+    assert_eq!(date.span.text, "12.05.2024");
+}
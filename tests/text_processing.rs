@@ -61,7 +61,7 @@ fn test_action_extraction() {
     let action = &actions[0];
     assert_eq!(action.subject, "кот");
     assert_eq!(action.verb, "ест");
-    assert_eq!(action.object, "рыбу");
+    assert_eq!(action.object, "рыба");
     assert!(action.confidence > 0.0);
 }
 
@@ -160,7 +160,7 @@ fn test_entity_types() {
     
     let entities = processor.extract_entities(text);
     
-    assert!(entities.iter().any(|e| e.entity_type == EntityType::Person));
+    assert!(entities.iter().any(|e| e.entity_type == EntityType::Animal));
     assert!(entities.iter().any(|e| e.entity_type == EntityType::Location));
     assert!(entities.iter().any(|e| e.entity_type == EntityType::Date));
 }